@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- table to store item activity used by seller analytics
+CREATE TABLE item_event (
+  id bigserial NOT NULL PRIMARY KEY,
+  item_id bigint NOT NULL  REFERENCES item(id),
+  --- user_id is the user who triggered the event
+  user_id bigint NOT NULL  REFERENCES users(id),
+  --- one of View, Favorite, RoomOpened, Offer, Sale
+  event_type VARCHAR NOT NULL,
+  created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+CREATE INDEX item_event_item_id_created_at_idx ON item_event (item_id, created_at);
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+-- in-app notifications shown to the user, also pushed over websocket when connected
+CREATE TABLE notification (
+  id bigserial NOT NULL PRIMARY KEY,
+  user_id bigint NOT NULL  REFERENCES users(id),
+  notification_type VARCHAR NOT NULL,
+  title VARCHAR NOT NULL,
+  body VARCHAR NOT NULL,
+  item_id bigint DEFAULT NULL REFERENCES item(id),
+  room_id bigint DEFAULT NULL REFERENCES room(id),
+  is_read BOOLEAN NOT NULL DEFAULT FALSE,
+  created_at timestamp with time zone DEFAULT now() NOT NULL,
+  read_at timestamp with time zone DEFAULT NULL
+);
+
+CREATE INDEX notification_user_id_created_at_idx ON notification (user_id, created_at);
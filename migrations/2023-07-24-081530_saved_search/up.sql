@@ -0,0 +1,17 @@
+-- Your SQL goes here
+-- item filters saved by a user, matched against every newly published item
+CREATE TABLE saved_search (
+  id bigserial NOT NULL PRIMARY KEY,
+  user_id bigint NOT NULL  REFERENCES users(id),
+  name VARCHAR NOT NULL,
+  category_id bigint DEFAULT NULL REFERENCES category(id),
+  karat_id bigint DEFAULT NULL REFERENCES karat(id),
+  geofence_id bigint DEFAULT NULL REFERENCES geofence(id),
+  min_price bigint DEFAULT NULL,
+  max_price bigint DEFAULT NULL,
+  --- whether matches are also pushed over the websocket connection
+  push_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+  created_at timestamp with time zone DEFAULT now() NOT NULL,
+  updated_at timestamp with time zone DEFAULT now() NOT NULL,
+  deleted_at timestamp with time zone DEFAULT NULL
+);
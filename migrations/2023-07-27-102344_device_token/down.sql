@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE room_member DROP COLUMN IF EXISTS is_muted;
+DROP TABLE IF EXISTS device_token;
\ No newline at end of file
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+-- push notification tokens of user devices
+CREATE TABLE device_token (
+  id bigserial NOT NULL PRIMARY KEY,
+  user_id bigint NOT NULL  REFERENCES users(id),
+  token VARCHAR NOT NULL,
+  --- Android or iOS
+  platform VARCHAR NOT NULL,
+  created_at timestamp with time zone DEFAULT now() NOT NULL,
+  updated_at timestamp with time zone DEFAULT now() NOT NULL,
+  deleted_at timestamp with time zone DEFAULT NULL,
+  unique(token)
+);
+
+--- muted rooms do not send push notifications to the member
+ALTER TABLE room_member ADD COLUMN is_muted BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS moderation_action;
+DROP TABLE IF EXISTS report;
+ALTER TABLE item DROP COLUMN IF EXISTS hidden_by_moderator;
+ALTER TABLE users DROP COLUMN IF EXISTS suspended_at;
+ALTER TABLE users DROP COLUMN IF EXISTS role;
\ No newline at end of file
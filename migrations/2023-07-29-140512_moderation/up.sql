@@ -0,0 +1,40 @@
+-- Your SQL goes here
+--- role is one of User, Moderator, Admin
+ALTER TABLE users ADD COLUMN role VARCHAR NOT NULL DEFAULT 'User';
+ALTER TABLE users ADD COLUMN suspended_at timestamp with time zone DEFAULT NULL;
+
+--- items hidden by a moderator can not be unhidden by the owner
+ALTER TABLE item ADD COLUMN hidden_by_moderator BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- reports of items, users and messages submitted by users
+CREATE TABLE report (
+  id bigserial NOT NULL PRIMARY KEY,
+  reporter_id bigint NOT NULL  REFERENCES users(id),
+  --- one of Item, User, Message
+  target_type VARCHAR NOT NULL,
+  target_id bigint NOT NULL,
+  --- one of Fraud, Prohibited, Counterfeit, Abuse, Spam, Other
+  reason VARCHAR NOT NULL,
+  description VARCHAR NOT NULL DEFAULT '',
+  --- one of Open, Assigned, Resolved, Dismissed
+  report_status VARCHAR NOT NULL DEFAULT 'Open',
+  assignee_id bigint DEFAULT NULL REFERENCES users(id),
+  created_at timestamp with time zone DEFAULT now() NOT NULL,
+  updated_at timestamp with time zone DEFAULT now() NOT NULL,
+  resolved_at timestamp with time zone DEFAULT NULL
+);
+
+CREATE INDEX report_report_status_idx ON report (report_status);
+
+-- audit trail of every moderator decision
+CREATE TABLE moderation_action (
+  id bigserial NOT NULL PRIMARY KEY,
+  report_id bigint DEFAULT NULL REFERENCES report(id),
+  moderator_id bigint NOT NULL  REFERENCES users(id),
+  --- one of Assign, HideItem, SuspendUser, DeleteMessage, Resolve, Dismiss
+  action VARCHAR NOT NULL,
+  target_type VARCHAR NOT NULL,
+  target_id bigint NOT NULL,
+  note VARCHAR NOT NULL DEFAULT '',
+  created_at timestamp with time zone DEFAULT now() NOT NULL
+);
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- users blocked by the blocker can not contact them or show up in their feed
+CREATE TABLE user_block (
+  id bigserial NOT NULL PRIMARY KEY,
+  blocker_id bigint NOT NULL  REFERENCES users(id),
+  blocked_id bigint NOT NULL  REFERENCES users(id),
+  created_at timestamp with time zone DEFAULT now() NOT NULL,
+  updated_at timestamp with time zone DEFAULT now() NOT NULL,
+  deleted_at timestamp with time zone DEFAULT NULL,
+  unique(blocker_id, blocked_id)
+);
+
+CREATE INDEX user_block_blocked_id_idx ON user_block (blocked_id);
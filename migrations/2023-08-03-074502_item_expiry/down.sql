@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS item_expires_at_idx;
+DROP INDEX IF EXISTS item_bumped_at_idx;
+ALTER TABLE item DROP COLUMN IF EXISTS expiry_notified_at;
+ALTER TABLE item DROP COLUMN IF EXISTS expires_at;
+ALTER TABLE item DROP COLUMN IF EXISTS bumped_at;
\ No newline at end of file
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+--- the feed is ordered by the last bump, which is the creation time for new items
+ALTER TABLE item ADD COLUMN bumped_at timestamp with time zone DEFAULT now() NOT NULL;
+--- active items become Expired after this time unless the owner renews them
+ALTER TABLE item ADD COLUMN expires_at timestamp with time zone DEFAULT now() + interval '30 days' NOT NULL;
+ALTER TABLE item ADD COLUMN expiry_notified_at timestamp with time zone DEFAULT NULL;
+
+UPDATE item SET bumped_at = created_at;
+
+CREATE INDEX item_bumped_at_idx ON item (bumped_at);
+CREATE INDEX item_expires_at_idx ON item (expires_at);
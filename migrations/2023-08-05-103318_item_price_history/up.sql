@@ -0,0 +1,11 @@
+-- Your SQL goes here
+-- every price change of an item
+CREATE TABLE item_price_history (
+  id bigserial NOT NULL PRIMARY KEY,
+  item_id bigint NOT NULL  REFERENCES item(id),
+  old_price bigint NOT NULL,
+  new_price bigint NOT NULL,
+  created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+CREATE INDEX item_price_history_item_id_idx ON item_price_history (item_id);
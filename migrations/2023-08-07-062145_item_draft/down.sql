@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE item ALTER COLUMN item_status SET DEFAULT 'Active';
\ No newline at end of file
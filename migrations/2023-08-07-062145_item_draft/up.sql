@@ -0,0 +1,3 @@
+-- Your SQL goes here
+--- new items are drafts until the owner publishes them
+ALTER TABLE item ALTER COLUMN item_status SET DEFAULT 'Draft';
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS item_attribute;
+DROP TABLE IF EXISTS category_attribute;
\ No newline at end of file
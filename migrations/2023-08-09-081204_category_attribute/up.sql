@@ -0,0 +1,29 @@
+-- Your SQL goes here
+-- the attributes listings of a category describe, like the ring size of rings
+CREATE TABLE category_attribute (
+  id bigserial NOT NULL PRIMARY KEY,
+  category_id bigint NOT NULL REFERENCES category(id),
+  name varchar NOT NULL,
+  attribute_type varchar NOT NULL,
+  unit varchar,
+  allowed_values text[] NOT NULL DEFAULT '{}',
+  required boolean NOT NULL DEFAULT false,
+  created_at timestamp with time zone DEFAULT now() NOT NULL,
+  updated_at timestamp with time zone DEFAULT now() NOT NULL,
+  deleted_at timestamp with time zone
+);
+
+CREATE UNIQUE INDEX category_attribute_category_id_name_idx ON category_attribute (category_id, name) WHERE deleted_at IS NULL;
+
+-- number attributes also keep the parsed value so searches can use ranges
+CREATE TABLE item_attribute (
+  id bigserial NOT NULL PRIMARY KEY,
+  item_id bigint NOT NULL REFERENCES item(id),
+  attribute_id bigint NOT NULL REFERENCES category_attribute(id),
+  value varchar NOT NULL,
+  number_value double precision,
+  created_at timestamp with time zone DEFAULT now() NOT NULL,
+  UNIQUE (item_id, attribute_id)
+);
+
+CREATE INDEX item_attribute_attribute_id_idx ON item_attribute (attribute_id);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE category DROP COLUMN IF EXISTS position;
+ALTER TABLE category DROP COLUMN IF EXISTS parent_id;
\ No newline at end of file
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+-- categories without a parent are the roots of the tree, siblings are ordered by position
+ALTER TABLE category ADD COLUMN parent_id bigint REFERENCES category(id);
+ALTER TABLE category ADD COLUMN position integer NOT NULL DEFAULT 0;
+
+UPDATE category SET position = id;
+
+CREATE INDEX category_parent_id_idx ON category (parent_id);
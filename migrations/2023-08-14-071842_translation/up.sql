@@ -0,0 +1,56 @@
+-- Your SQL goes here
+-- names of catalog entries in other locales, the stored names are English
+CREATE TABLE translation (
+  id bigserial NOT NULL PRIMARY KEY,
+  entity_type varchar NOT NULL,
+  entity_id bigint NOT NULL,
+  locale varchar NOT NULL,
+  name varchar NOT NULL,
+  created_at timestamp with time zone DEFAULT now() NOT NULL,
+  updated_at timestamp with time zone DEFAULT now() NOT NULL,
+  UNIQUE (entity_type, entity_id, locale)
+);
+
+INSERT INTO translation (entity_type, entity_id, locale, name)
+SELECT 'Category', category.id, t.locale, t.name
+FROM (VALUES
+  ('Rings', 'ru', 'Кольца'),
+  ('Rings', 'tg', 'Ангуштаринҳо'),
+  ('Earings', 'ru', 'Серьги'),
+  ('Earings', 'tg', 'Гӯшвораҳо'),
+  ('Nosepins', 'ru', 'Пусеты для носа'),
+  ('Nosepins', 'tg', 'Хизомаҳо'),
+  ('Bracelets', 'ru', 'Браслеты'),
+  ('Bracelets', 'tg', 'Дастпонаҳо'),
+  ('Necklaces', 'ru', 'Ожерелья'),
+  ('Necklaces', 'tg', 'Гарданбандҳо')
+) AS t (source, locale, name)
+JOIN category ON category.name = t.source;
+
+INSERT INTO translation (entity_type, entity_id, locale, name)
+SELECT 'Karat', karat.id, t.locale, t.name
+FROM (VALUES
+  ('9K', 'ru', '9 карат'),
+  ('9K', 'tg', '9 карат'),
+  ('14K', 'ru', '14 карат'),
+  ('14K', 'tg', '14 карат'),
+  ('18K', 'ru', '18 карат'),
+  ('18K', 'tg', '18 карат'),
+  ('24K', 'ru', '24 карата'),
+  ('24K', 'tg', '24 карат')
+) AS t (source, locale, name)
+JOIN karat ON karat.name = t.source;
+
+INSERT INTO translation (entity_type, entity_id, locale, name)
+SELECT 'Geofence', geofence.id, t.locale, t.name
+FROM (VALUES
+  ('Global', 'ru', 'Весь мир'),
+  ('Global', 'tg', 'Тамоми ҷаҳон'),
+  ('Tajikistan', 'ru', 'Таджикистан'),
+  ('Tajikistan', 'tg', 'Тоҷикистон'),
+  ('Dushanbe', 'ru', 'Душанбе'),
+  ('Dushanbe', 'tg', 'Душанбе'),
+  ('Tursunzade', 'ru', 'Турсунзаде'),
+  ('Tursunzade', 'tg', 'Турсунзода')
+) AS t (source, locale, name)
+JOIN geofence ON geofence.name = t.source;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE geofence DROP COLUMN IF EXISTS max_longitude;
+ALTER TABLE geofence DROP COLUMN IF EXISTS max_latitude;
+ALTER TABLE geofence DROP COLUMN IF EXISTS min_longitude;
+ALTER TABLE geofence DROP COLUMN IF EXISTS min_latitude;
+ALTER TABLE geofence DROP COLUMN IF EXISTS boundary;
\ No newline at end of file
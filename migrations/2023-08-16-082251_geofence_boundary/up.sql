@@ -0,0 +1,8 @@
+-- Your SQL goes here
+-- the GeoJSON MultiPolygon of the region, the bounding box narrows down the regions
+-- a point has to be tested against
+ALTER TABLE geofence ADD COLUMN boundary text;
+ALTER TABLE geofence ADD COLUMN min_latitude double precision;
+ALTER TABLE geofence ADD COLUMN min_longitude double precision;
+ALTER TABLE geofence ADD COLUMN max_latitude double precision;
+ALTER TABLE geofence ADD COLUMN max_longitude double precision;
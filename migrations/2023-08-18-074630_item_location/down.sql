@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS item_location_idx;
+ALTER TABLE item DROP COLUMN IF EXISTS longitude;
+ALTER TABLE item DROP COLUMN IF EXISTS latitude;
\ No newline at end of file
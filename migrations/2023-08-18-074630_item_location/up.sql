@@ -0,0 +1,5 @@
+-- Your SQL goes here
+-- an approximate location, the seller's position snapped to a grid cell
+ALTER TABLE item ADD COLUMN latitude double precision;
+ALTER TABLE item ADD COLUMN longitude double precision;
+CREATE INDEX item_location_idx ON item (latitude, longitude) WHERE latitude IS NOT NULL;
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE item_image DROP COLUMN IF EXISTS processed_at;
+ALTER TABLE item_image DROP COLUMN IF EXISTS medium_key;
+ALTER TABLE item_image DROP COLUMN IF EXISTS thumbnail_key;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE item_image ADD COLUMN thumbnail_key VARCHAR NULL;
+ALTER TABLE item_image ADD COLUMN medium_key VARCHAR NULL;
+ALTER TABLE item_image ADD COLUMN processed_at TIMESTAMPTZ NULL;
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS item_image_position_idx;
+DROP INDEX IF EXISTS item_image_cover_idx;
+ALTER TABLE item_image DROP COLUMN IF EXISTS position;
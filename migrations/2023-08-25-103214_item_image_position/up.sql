@@ -0,0 +1,26 @@
+-- Your SQL goes here
+ALTER TABLE item_image ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+
+UPDATE item_image SET position = ordered.position
+FROM (
+  SELECT id, ROW_NUMBER() OVER (PARTITION BY item_id ORDER BY id) - 1 AS position
+  FROM item_image
+  WHERE deleted_at IS NULL
+) AS ordered
+WHERE item_image.id = ordered.id;
+
+-- keep the first cover of items that have several
+UPDATE item_image SET is_cover = false
+WHERE is_cover AND deleted_at IS NULL AND id NOT IN (
+  SELECT MIN(id) FROM item_image WHERE is_cover AND deleted_at IS NULL GROUP BY item_id
+);
+
+-- items without a cover get their first image as the cover
+UPDATE item_image SET is_cover = true
+WHERE id IN (
+  SELECT MIN(id) FROM item_image WHERE deleted_at IS NULL GROUP BY item_id
+  HAVING NOT BOOL_OR(is_cover)
+);
+
+CREATE UNIQUE INDEX item_image_cover_idx ON item_image (item_id) WHERE is_cover AND deleted_at IS NULL;
+CREATE INDEX item_image_position_idx ON item_image (item_id, position);
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE item_image DROP COLUMN IF EXISTS pending_cover;
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE item_image ADD COLUMN pending_cover BOOLEAN NOT NULL DEFAULT false;
@@ -1,5 +1,6 @@
+use actix_web::error::{ErrorForbidden, ErrorInternalServerError};
 use actix_web::HttpMessage;
-use actix_web::{dev::ServiceRequest, Error};
+use actix_web::{dev::ServiceRequest, web, Error};
 use actix_web_httpauth::extractors::bearer::{BearerAuth, Config};
 use actix_web_httpauth::extractors::AuthenticationError;
 use chrono::Utc;
@@ -7,7 +8,11 @@ use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header,
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use diesel::result::Error as DieselError;
+
 use crate::errors::ServiceError;
+use crate::repository::user::get_user_by_id;
+use crate::routes::{DbPool, RouteError};
 
 // TODO: Move them to .env file
 const BEARER: &str = "Bearer ";
@@ -48,15 +53,37 @@ pub async fn validator(
     .app_data::<Config>()
     .map(|data| data.clone())
     .unwrap_or_else(Default::default);
-  match validate_token(credentials.token()) {
-    Ok(res) => {
-      req.request().extensions_mut().insert(res.user_id);
-      Ok(req)
-    }
+  let claim = match validate_token(credentials.token()) {
+    Ok(claim) => claim,
     Err(e) => {
       println!("invalid token err: {}", e);
+      return Err((AuthenticationError::from(config).into(), req));
+    }
+  };
+  // tokens stay valid until they expire, suspended users are refused here
+  let pool = match req.app_data::<web::Data<DbPool>>() {
+    Some(pool) => pool.clone(),
+    None => return Err((ErrorInternalServerError("Database pool is missing"), req)),
+  };
+  let user = web::block(move || {
+    let mut conn = pool.get().map_err(|_| RouteError::PoolingErr)?;
+    get_user_by_id(&mut conn, claim.user_id).map_err(RouteError::from)
+  })
+  .await;
+  match user {
+    Ok(Ok(user)) if user.is_suspended() => Err((ErrorForbidden("User is suspended"), req)),
+    Ok(Ok(user)) => {
+      req.request().extensions_mut().insert(user.id);
+      Ok(req)
+    }
+    Ok(Err(RouteError::DbError(DieselError::NotFound))) => {
       Err((AuthenticationError::from(config).into(), req))
     }
+    Ok(Err(e)) => {
+      println!("failed to load the user of the token: {}", e);
+      Err((ErrorInternalServerError("Internal Server Error"), req))
+    }
+    Err(e) => Err((e.into(), req)),
   }
 }
 
@@ -0,0 +1,69 @@
+use diesel::r2d2;
+use dotenv::dotenv;
+use std::fs::File;
+use std::io;
+use std::process::exit;
+
+use ketalk::item_csv::{export_items, import_items, CsvError};
+use ketalk::repository::db::connection_manager;
+use ketalk::repository::user::get_user_by_id;
+
+const USAGE: &str = "usage:
+  ketalk-admin import-items <seller id> <file>
+  ketalk-admin export-items <seller id> [file]";
+
+fn main() {
+  dotenv().ok();
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  let (command, seller_id, path) = match args.as_slice() {
+    [command, seller_id] => (command.as_str(), seller_id, None),
+    [command, seller_id, path] => (command.as_str(), seller_id, Some(path.as_str())),
+    _ => fail(USAGE),
+  };
+  let seller_id: i64 = match seller_id.parse() {
+    Ok(id) => id,
+    Err(_) => fail(&format!("invalid seller id: {}", seller_id)),
+  };
+
+  let pool = r2d2::Pool::builder()
+    .max_size(1)
+    .build(connection_manager())
+    .expect("Failed to create pool.");
+  let mut conn = pool.get().expect("Failed to get connection.");
+  if let Err(e) = get_user_by_id(&mut conn, seller_id) {
+    fail(&format!("failed to get seller {}: {}", seller_id, e));
+  }
+
+  match (command, path) {
+    ("import-items", Some(path)) => {
+      let file = File::open(path).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+      match import_items(&mut conn, seller_id, file) {
+        Ok(items) => println!("imported {} draft items", items.len()),
+        Err(CsvError::InvalidRows(errors)) => {
+          for error in errors {
+            eprintln!("row {}: {}", error.row, error.message);
+          }
+          fail("nothing was imported");
+        }
+        Err(e) => fail(&format!("import failed: {}", e)),
+      }
+    }
+    ("export-items", Some(path)) => {
+      let file = File::create(path).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+      if let Err(e) = export_items(&mut conn, seller_id, file) {
+        fail(&format!("export failed: {}", e));
+      }
+    }
+    ("export-items", None) => {
+      if let Err(e) = export_items(&mut conn, seller_id, io::stdout().lock()) {
+        fail(&format!("export failed: {}", e));
+      }
+    }
+    _ => fail(USAGE),
+  }
+}
+
+fn fail(message: &str) -> ! {
+  eprintln!("{}", message);
+  exit(1);
+}
@@ -3,25 +3,62 @@ use actix_cors::Cors;
 use actix_web::{http, web, App, HttpServer};
 use diesel::r2d2;
 use dotenv::dotenv;
+use std::sync::Arc;
 
 use actix_web_httpauth::middleware::HttpAuthentication;
 use ketalk::auth::validator;
-use ketalk::helpers::get_env;
+use ketalk::helpers::{get_env, get_env_or};
+use ketalk::jobs::image_processing::ImageProcessingJob;
+use ketalk::jobs::listing_expiry::{ListingConfig, ListingExpiryJob};
+use ketalk::jobs::upload_cleanup::{UploadCleanupConfig, UploadCleanupJob};
+use ketalk::push::LogPushSender;
 use ketalk::repository::db::connection_manager;
+use ketalk::routes::analytics::get_seller_analytics;
 use ketalk::routes::auth::{logout, refresh_auth_token};
-use ketalk::routes::category::{create_category, delete_category, get_categories, get_category};
+use ketalk::routes::category::{
+  create_category, delete_category, get_categories, get_category, move_category,
+};
+use ketalk::routes::category_attribute::{
+  create_category_attribute, delete_category_attribute, get_category_attributes,
+};
+use ketalk::routes::device::{register_device, unregister_device};
+use ketalk::routes::geofence::{
+  create_geofence, get_geofences, move_geofence, resolve_geofence, retire_geofence, set_boundary,
+};
 use ketalk::routes::heartbeat::heartbeat;
 use ketalk::routes::item::{
-  create_item, create_purchase, get_item, get_item_buyers, get_items, hide_or_unhide_item,
-  new_item_status, update_favorite_status,
+  bump_item, create_item, create_purchase, get_item, get_item_buyers, get_items,
+  hide_or_unhide_item, new_item_status, publish_item, renew_item, update_favorite_status,
+  update_item,
+};
+use ketalk::routes::item_csv::{export_items, import_items};
+use ketalk::routes::item_image::{
+  create_upload_presigned_url, delete_item_image, receive_storage_events, reorder_item_images,
+  set_item_cover_image, update_status, StorageEventConfig,
+};
+use ketalk::routes::karat::{delete_karat, get_karat, get_karats};
+use ketalk::routes::locale::set_translation;
+use ketalk::routes::moderation::{
+  assign_report, delete_message_by_moderator, dismiss_report, get_report, get_reports, hide_item,
+  resolve_report, suspend_user,
+};
+use ketalk::routes::notification::{
+  get_notifications, get_unread_notifications_count, read_all_notifications, read_notification,
 };
-use ketalk::routes::item_image::{create_upload_presigned_url, update_status};
-use ketalk::routes::room::{create_room, get_user_rooms, join_room};
+use ketalk::routes::recommendation::{get_recommended_items, get_similar_items};
+use ketalk::routes::report::create_report;
+use ketalk::routes::room::{create_room, get_user_rooms, join_room, mute_room};
+use ketalk::routes::saved_search::{create_saved_search, delete_saved_search, get_saved_searches};
+use ketalk::routes::storage::configure_local_storage;
+use ketalk::routes::user_block::{block_user, get_blocked_users, unblock_user};
 use ketalk::routes::users::{
   delete_cover_image, get_presigned_url_for_cover_image, get_user, get_user_favorite_items,
   get_user_items, get_user_purchased_items, signin, signup, update_profile,
 };
-use ketalk::s3_bucket::get_s3_bucket;
+use ketalk::storage::cdn::MediaUrls;
+use ketalk::storage::local::LocalStorage;
+use ketalk::storage::s3::S3Storage;
+use ketalk::storage::{ObjectStorage, LOCAL_STORAGE_BACKEND, S3_STORAGE_BACKEND};
 use ketalk::ws::lobby::Lobby;
 
 #[actix_web::main]
@@ -30,7 +67,15 @@ async fn main() -> std::io::Result<()> {
   let server_addr = get_env("SERVER_ADDR");
   let server_port: u16 = get_env("SERVER_PORT").parse().unwrap();
 
-  let bucket = get_s3_bucket();
+  // images go to S3 unless STORAGE_BACKEND=local
+  let local_storage = match get_env_or("STORAGE_BACKEND", S3_STORAGE_BACKEND).as_str() {
+    LOCAL_STORAGE_BACKEND => Some(Arc::new(LocalStorage::from_env())),
+    _ => None,
+  };
+  let storage: Arc<dyn ObjectStorage> = match local_storage {
+    Some(ref local_storage) => local_storage.clone(),
+    None => Arc::new(S3Storage::from_env()),
+  };
 
   // connect to postgres db
   let connection_manager = connection_manager();
@@ -38,14 +83,25 @@ async fn main() -> std::io::Result<()> {
   let pool = r2d2::Pool::builder()
     .build(connection_manager)
     .expect("Failed to create pool.");
-  let chat_server: actix::Addr<Lobby> = Lobby::new(pool.clone()).start(); //create and spin up a lobby
+  let chat_server: actix::Addr<Lobby> = Lobby::new(pool.clone(), Arc::new(LogPushSender)).start(); //create and spin up a lobby
+  let listing_config = ListingConfig::from_env();
+  let storage_event_config = StorageEventConfig::from_env();
+  let media_urls = web::Data::new(MediaUrls::from_env());
+  ListingExpiryJob::new(pool.clone(), chat_server.clone(), listing_config.clone()).start();
+  ImageProcessingJob::new(pool.clone(), storage.clone()).start();
+  UploadCleanupJob::new(
+    pool.clone(),
+    storage.clone(),
+    UploadCleanupConfig::from_env(),
+  )
+  .start();
 
   let app = HttpServer::new(move || {
     let bearer_middleware = HttpAuthentication::bearer(validator);
     let cors = Cors::default()
       .allowed_origin("http://localhost:3000")
       .allowed_origin("http://localhost:8080")
-      .allowed_methods(vec!["GET", "POST"])
+      .allowed_methods(vec!["GET", "POST", "PUT"])
       .allowed_headers(vec![
         http::header::AUTHORIZATION,
         http::header::ACCEPT,
@@ -55,7 +111,10 @@ async fn main() -> std::io::Result<()> {
     App::new()
       .app_data(web::Data::new(pool.clone()))
       .app_data(web::Data::new(chat_server.clone()))
-      .app_data(web::Data::new(bucket.clone()))
+      .app_data(web::Data::from(storage.clone()))
+      .app_data(web::Data::new(listing_config.clone()))
+      .app_data(web::Data::new(storage_event_config.clone()))
+      .app_data(media_urls.clone())
       .wrap(cors)
       .service(heartbeat)
       .service(signin)
@@ -65,6 +124,17 @@ async fn main() -> std::io::Result<()> {
       .service(get_categories)
       .service(get_category)
       .service(delete_category)
+      .service(get_category_attributes)
+      .service(get_karats)
+      .service(get_karat)
+      .service(get_geofences)
+      .service(resolve_geofence)
+      .service(receive_storage_events)
+      .configure(|cfg| {
+        if let Some(ref local_storage) = local_storage {
+          configure_local_storage(local_storage.clone())(cfg);
+        }
+      })
       // .service(web::scope("").wrap(bearer_middleware.clone()).service())
       .service(
         web::scope("")
@@ -77,6 +147,9 @@ async fn main() -> std::io::Result<()> {
           .service(create_upload_presigned_url)
           .service(create_item)
           .service(update_status)
+          .service(reorder_item_images)
+          .service(set_item_cover_image)
+          .service(delete_item_image)
           .service(get_items)
           .service(get_item)
           .service(get_user_items)
@@ -89,7 +162,47 @@ async fn main() -> std::io::Result<()> {
           .service(get_user_favorite_items)
           .service(get_user_purchased_items)
           .service(get_item_buyers)
-          .service(create_purchase),
+          .service(create_purchase)
+          .service(get_seller_analytics)
+          .service(create_saved_search)
+          .service(get_saved_searches)
+          .service(delete_saved_search)
+          .service(get_notifications)
+          .service(get_unread_notifications_count)
+          .service(read_all_notifications)
+          .service(read_notification)
+          .service(register_device)
+          .service(unregister_device)
+          .service(mute_room)
+          .service(create_report)
+          .service(get_reports)
+          .service(get_report)
+          .service(assign_report)
+          .service(resolve_report)
+          .service(dismiss_report)
+          .service(hide_item)
+          .service(suspend_user)
+          .service(delete_message_by_moderator)
+          .service(block_user)
+          .service(unblock_user)
+          .service(get_blocked_users)
+          .service(renew_item)
+          .service(bump_item)
+          .service(get_similar_items)
+          .service(get_recommended_items)
+          .service(update_item)
+          .service(publish_item)
+          .service(import_items)
+          .service(export_items)
+          .service(create_category_attribute)
+          .service(delete_category_attribute)
+          .service(set_translation)
+          .service(move_category)
+          .service(delete_karat)
+          .service(create_geofence)
+          .service(move_geofence)
+          .service(retire_geofence)
+          .service(set_boundary),
       )
   })
   .workers(2)
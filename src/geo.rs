@@ -0,0 +1,367 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+const KM_PER_DEGREE: f64 = 111.32;
+// about a kilometer north to south
+const FUZZ_GRID_DEGREES: f64 = 0.01;
+
+// a closed ring of (longitude, latitude) points, GeoJSON order
+pub type Ring = Vec<(f64, f64)>;
+
+// the outer ring first, then the holes
+pub type Polygon = Vec<Ring>;
+
+#[derive(Debug)]
+pub struct GeoError(pub String);
+
+impl fmt::Display for GeoError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Invalid GeoJSON: {}", self.0)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+  pub min_latitude: f64,
+  pub min_longitude: f64,
+  pub max_latitude: f64,
+  pub max_longitude: f64,
+}
+
+impl BoundingBox {
+  // the box enclosing every point within `radius_km` of the center, it does not wrap
+  // around the antimeridian
+  pub fn around(latitude: f64, longitude: f64, radius_km: f64) -> BoundingBox {
+    let latitude_delta = radius_km / KM_PER_DEGREE;
+    let longitude_delta = radius_km / (KM_PER_DEGREE * latitude.to_radians().cos().max(0.01));
+    BoundingBox {
+      min_latitude: (latitude - latitude_delta).max(-90.0),
+      min_longitude: (longitude - longitude_delta).max(-180.0),
+      max_latitude: (latitude + latitude_delta).min(90.0),
+      max_longitude: (longitude + longitude_delta).min(180.0),
+    }
+  }
+
+  pub fn area(&self) -> f64 {
+    (self.max_latitude - self.min_latitude) * (self.max_longitude - self.min_longitude)
+  }
+}
+
+// A region boundary parsed from a GeoJSON Polygon or MultiPolygon. Features and
+// feature collections are unwrapped, every polygon of a collection is kept.
+#[derive(Debug, Clone)]
+pub struct Boundary {
+  polygons: Vec<Polygon>,
+}
+
+impl Boundary {
+  pub fn from_geojson(value: &Value) -> Result<Boundary, GeoError> {
+    let mut polygons = vec![];
+    collect_polygons(value, &mut polygons)?;
+    if polygons.is_empty() {
+      return Err(GeoError("no polygon found".to_string()));
+    }
+    Ok(Boundary { polygons })
+  }
+
+  pub fn parse(text: &str) -> Result<Boundary, GeoError> {
+    let value: Value = serde_json::from_str(text).map_err(|e| GeoError(e.to_string()))?;
+    Boundary::from_geojson(&value)
+  }
+
+  // the boundary as a GeoJSON MultiPolygon
+  pub fn to_geojson(&self) -> Value {
+    let coordinates: Vec<Vec<Vec<[f64; 2]>>> = self
+      .polygons
+      .iter()
+      .map(|polygon| {
+        polygon
+          .iter()
+          .map(|ring| ring.iter().map(|(lon, lat)| [*lon, *lat]).collect())
+          .collect()
+      })
+      .collect();
+    serde_json::json!({ "type": "MultiPolygon", "coordinates": coordinates })
+  }
+
+  pub fn bounding_box(&self) -> BoundingBox {
+    let mut bounding_box = BoundingBox {
+      min_latitude: f64::MAX,
+      min_longitude: f64::MAX,
+      max_latitude: f64::MIN,
+      max_longitude: f64::MIN,
+    };
+    for polygon in self.polygons.iter() {
+      for (lon, lat) in polygon[0].iter() {
+        bounding_box.min_latitude = bounding_box.min_latitude.min(*lat);
+        bounding_box.min_longitude = bounding_box.min_longitude.min(*lon);
+        bounding_box.max_latitude = bounding_box.max_latitude.max(*lat);
+        bounding_box.max_longitude = bounding_box.max_longitude.max(*lon);
+      }
+    }
+    bounding_box
+  }
+
+  // points on an edge may fall on either side
+  pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+    self.polygons.iter().any(|polygon| {
+      ring_contains(&polygon[0], latitude, longitude)
+        && !polygon[1..]
+          .iter()
+          .any(|hole| ring_contains(hole, latitude, longitude))
+    })
+  }
+}
+
+// great circle distance by the haversine formula
+pub fn distance_km(latitude_a: f64, longitude_a: f64, latitude_b: f64, longitude_b: f64) -> f64 {
+  let delta_latitude = (latitude_b - latitude_a).to_radians();
+  let delta_longitude = (longitude_b - longitude_a).to_radians();
+  let a = (delta_latitude / 2.0).sin().powi(2)
+    + latitude_a.to_radians().cos()
+      * latitude_b.to_radians().cos()
+      * (delta_longitude / 2.0).sin().powi(2);
+  2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+// The center of the grid cell the point is in. Every point of a cell maps to the
+// same location so listings of one seller can not be averaged to their position.
+pub fn fuzz_location(latitude: f64, longitude: f64) -> (f64, f64) {
+  let snap =
+    |value: f64| (value / FUZZ_GRID_DEGREES).floor() * FUZZ_GRID_DEGREES + FUZZ_GRID_DEGREES / 2.0;
+  (
+    snap(latitude).clamp(-90.0, 90.0),
+    snap(longitude).clamp(-180.0, 180.0),
+  )
+}
+
+pub fn is_valid_coordinate(latitude: f64, longitude: f64) -> bool {
+  (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude)
+}
+
+fn collect_polygons(value: &Value, polygons: &mut Vec<Polygon>) -> Result<(), GeoError> {
+  let geometry_type = value
+    .get("type")
+    .and_then(Value::as_str)
+    .ok_or_else(|| GeoError("missing type".to_string()))?;
+  match geometry_type {
+    "FeatureCollection" => {
+      let features = value
+        .get("features")
+        .and_then(Value::as_array)
+        .ok_or_else(|| GeoError("missing features".to_string()))?;
+      for feature in features {
+        collect_polygons(feature, polygons)?;
+      }
+    }
+    "Feature" => {
+      let geometry = value
+        .get("geometry")
+        .ok_or_else(|| GeoError("missing geometry".to_string()))?;
+      collect_polygons(geometry, polygons)?;
+    }
+    "Polygon" => polygons.push(parse_polygon(coordinates(value)?)?),
+    "MultiPolygon" => {
+      let parts = coordinates(value)?
+        .as_array()
+        .ok_or_else(|| GeoError("coordinates must be an array".to_string()))?;
+      for part in parts {
+        polygons.push(parse_polygon(part)?);
+      }
+    }
+    other => return Err(GeoError(format!("unsupported type {}", other))),
+  }
+  Ok(())
+}
+
+fn coordinates(value: &Value) -> Result<&Value, GeoError> {
+  value
+    .get("coordinates")
+    .ok_or_else(|| GeoError("missing coordinates".to_string()))
+}
+
+fn parse_polygon(value: &Value) -> Result<Polygon, GeoError> {
+  let rings = value
+    .as_array()
+    .ok_or_else(|| GeoError("polygon must be an array of rings".to_string()))?;
+  if rings.is_empty() {
+    return Err(GeoError("polygon has no rings".to_string()));
+  }
+  rings.iter().map(parse_ring).collect()
+}
+
+fn parse_ring(value: &Value) -> Result<Ring, GeoError> {
+  let positions = value
+    .as_array()
+    .ok_or_else(|| GeoError("ring must be an array of positions".to_string()))?;
+  let mut ring = Ring::with_capacity(positions.len());
+  for position in positions {
+    let (lon, lat) = match position.as_array().map(|values| values.as_slice()) {
+      Some([lon, lat, ..]) => match (lon.as_f64(), lat.as_f64()) {
+        (Some(lon), Some(lat)) => (lon, lat),
+        _ => return Err(GeoError("position must be numbers".to_string())),
+      },
+      _ => {
+        return Err(GeoError(
+          "position must be [longitude, latitude]".to_string(),
+        ))
+      }
+    };
+    if !is_valid_coordinate(lat, lon) {
+      return Err(GeoError(format!(
+        "position out of range: [{}, {}]",
+        lon, lat
+      )));
+    }
+    ring.push((lon, lat));
+  }
+  // GeoJSON rings are closed, the first position is repeated at the end
+  if ring.len() < 4 || ring.first() != ring.last() {
+    return Err(GeoError(
+      "ring must be closed and have at least 4 positions".to_string(),
+    ));
+  }
+  Ok(ring)
+}
+
+// even-odd rule, casting a ray towards increasing longitude
+fn ring_contains(ring: &Ring, latitude: f64, longitude: f64) -> bool {
+  let mut inside = false;
+  for edge in ring.windows(2) {
+    let (lon_a, lat_a) = edge[0];
+    let (lon_b, lat_b) = edge[1];
+    if (lat_a > latitude) != (lat_b > latitude) {
+      let crossing = lon_a + (latitude - lat_a) / (lat_b - lat_a) * (lon_b - lon_a);
+      if longitude < crossing {
+        inside = !inside;
+      }
+    }
+  }
+  inside
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn square(min: f64, max: f64) -> Value {
+    json!([[min, min], [max, min], [max, max], [min, max], [min, min]])
+  }
+
+  #[test]
+  fn polygon_with_hole() {
+    let boundary = Boundary::from_geojson(&json!({
+      "type": "Polygon",
+      "coordinates": [square(0.0, 10.0), square(4.0, 6.0)],
+    }))
+    .unwrap();
+    assert!(boundary.contains(2.0, 2.0));
+    assert!(!boundary.contains(5.0, 5.0));
+    assert!(!boundary.contains(11.0, 5.0));
+  }
+
+  #[test]
+  fn multi_polygon() {
+    let boundary = Boundary::from_geojson(&json!({
+      "type": "MultiPolygon",
+      "coordinates": [[square(0.0, 1.0)], [square(5.0, 6.0)]],
+    }))
+    .unwrap();
+    assert!(boundary.contains(0.5, 0.5));
+    assert!(boundary.contains(5.5, 5.5));
+    assert!(!boundary.contains(3.0, 3.0));
+    assert_eq!(boundary.bounding_box().max_latitude, 6.0);
+  }
+
+  #[test]
+  fn feature_collection_keeps_every_polygon() {
+    let boundary = Boundary::from_geojson(&json!({
+      "type": "FeatureCollection",
+      "features": [
+        { "type": "Feature", "geometry": { "type": "Polygon", "coordinates": [square(0.0, 1.0)] } },
+        { "type": "Feature", "geometry": { "type": "Polygon", "coordinates": [square(5.0, 6.0)] } },
+      ],
+    }))
+    .unwrap();
+    assert!(boundary.contains(0.5, 0.5));
+    assert!(boundary.contains(5.5, 5.5));
+  }
+
+  #[test]
+  fn unclosed_ring_is_refused() {
+    let result = Boundary::from_geojson(&json!({
+      "type": "Polygon",
+      "coordinates": [[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]],
+    }));
+    assert!(result.is_err());
+    let result = Boundary::from_geojson(&json!({
+      "type": "Polygon",
+      "coordinates": [[[0.0, 0.0], [1.0, 0.0], [0.0, 0.0]]],
+    }));
+    assert!(result.is_err());
+  }
+
+  #[test]
+  fn out_of_range_position_is_refused() {
+    let result = Boundary::from_geojson(&json!({
+      "type": "Polygon",
+      "coordinates": [[[0.0, 0.0], [200.0, 0.0], [1.0, 1.0], [0.0, 0.0]]],
+    }));
+    assert!(result.is_err());
+  }
+
+  #[test]
+  fn haversine_distance() {
+    assert_eq!(distance_km(41.0, 29.0, 41.0, 29.0), 0.0);
+    // a degree of latitude along a meridian
+    assert!((distance_km(0.0, 0.0, 1.0, 0.0) - 111.19).abs() < 0.01);
+    // Istanbul to Ankara
+    let distance = distance_km(41.0082, 28.9784, 39.9334, 32.8597);
+    assert!((distance - 350.0).abs() < 5.0);
+    assert_eq!(
+      distance_km(41.0082, 28.9784, 39.9334, 32.8597),
+      distance_km(39.9334, 32.8597, 41.0082, 28.9784)
+    );
+  }
+
+  #[test]
+  fn fuzz_location_snaps_to_the_cell_center() {
+    let (latitude, longitude) = fuzz_location(41.0082, 28.9784);
+    assert!((latitude - 41.005).abs() < 1e-9);
+    assert!((longitude - 28.975).abs() < 1e-9);
+    // every point of a cell maps to the same location
+    assert_eq!(
+      fuzz_location(41.0001, 28.9701),
+      fuzz_location(41.0099, 28.9799)
+    );
+    let (latitude, longitude) = fuzz_location(-0.001, -0.001);
+    assert!((latitude + 0.005).abs() < 1e-9);
+    assert!((longitude + 0.005).abs() < 1e-9);
+  }
+
+  #[test]
+  fn fuzz_location_stays_in_range() {
+    let (latitude, longitude) = fuzz_location(90.0, 180.0);
+    assert!(is_valid_coordinate(latitude, longitude));
+    let (latitude, longitude) = fuzz_location(-90.0, -180.0);
+    assert!(is_valid_coordinate(latitude, longitude));
+  }
+
+  #[test]
+  fn bounding_box_around_a_point() {
+    let bounding_box = BoundingBox::around(0.0, 0.0, KM_PER_DEGREE);
+    assert!((bounding_box.min_latitude + 1.0).abs() < 1e-9);
+    assert!((bounding_box.max_latitude - 1.0).abs() < 1e-9);
+    assert!((bounding_box.max_longitude - 1.0).abs() < 1e-9);
+    // a degree of longitude gets shorter away from the equator
+    let bounding_box = BoundingBox::around(60.0, 0.0, KM_PER_DEGREE);
+    assert!((bounding_box.max_longitude - 2.0).abs() < 1e-9);
+    // the box is cut at the poles and the antimeridian
+    let bounding_box = BoundingBox::around(89.5, 179.5, 200.0);
+    assert_eq!(bounding_box.max_latitude, 90.0);
+    assert_eq!(bounding_box.max_longitude, 180.0);
+  }
+}
@@ -8,6 +8,10 @@ pub fn get_env(key: &str) -> String {
   }
 }
 
+pub fn get_env_or(key: &str, default: &str) -> String {
+  std::env::var(key).unwrap_or_else(|_| default.to_string())
+}
+
 pub fn new_naive_date() -> NaiveDateTime {
   // TOOD: improve timing
   chrono::NaiveDateTime::from_timestamp_millis(chrono::Utc::now().timestamp_millis()).unwrap()
@@ -0,0 +1,385 @@
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, ImageOutputFormat};
+use std::fmt;
+use std::io::Cursor;
+
+// the longest side of the variants, in pixels
+pub const THUMBNAIL_SIZE: u32 = 320;
+pub const MEDIUM_SIZE: u32 = 1024;
+pub const VARIANT_CONTENT_TYPE: &str = "image/jpeg";
+const VARIANT_QUALITY: u8 = 80;
+const ORIGINAL_QUALITY: u8 = 90;
+const EXIF_ORIENTATION_TAG: u16 = 0x0112;
+
+#[derive(Debug)]
+pub struct ProcessingError(pub String);
+
+impl fmt::Display for ProcessingError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Image processing error: {}", self.0)
+  }
+}
+
+impl From<image::ImageError> for ProcessingError {
+  fn from(err: image::ImageError) -> ProcessingError {
+    ProcessingError(err.to_string())
+  }
+}
+
+pub struct EncodedImage {
+  pub content: Vec<u8>,
+  pub content_type: &'static str,
+}
+
+pub struct ProcessedImage {
+  // the upload without its metadata, in the format it was uploaded in
+  pub original: EncodedImage,
+  pub thumbnail: EncodedImage,
+  pub medium: EncodedImage,
+}
+
+// Decodes the upload and encodes it again, which leaves out EXIF and every other
+// metadata block along with the GPS position. The EXIF orientation is applied to
+// the pixels first so photos keep their rotation.
+pub fn process_image(content: &[u8]) -> Result<ProcessedImage, ProcessingError> {
+  let format = image::guess_format(content)?;
+  let mut image = image::load_from_memory_with_format(content, format)?;
+  if format == ImageFormat::Jpeg {
+    image = apply_orientation(image, read_jpeg_orientation(content).unwrap_or(1));
+  }
+  let original = match format {
+    ImageFormat::Jpeg => encode_jpeg(&image, ORIGINAL_QUALITY)?,
+    ImageFormat::Png => encode(&image, ImageOutputFormat::Png, "image/png")?,
+    ImageFormat::WebP => encode(&image, ImageOutputFormat::WebP, "image/webp")?,
+    other => return Err(ProcessingError(format!("unsupported format: {:?}", other))),
+  };
+  Ok(ProcessedImage {
+    original,
+    thumbnail: encode_jpeg(&fit_within(&image, THUMBNAIL_SIZE), VARIANT_QUALITY)?,
+    medium: encode_jpeg(&fit_within(&image, MEDIUM_SIZE), VARIANT_QUALITY)?,
+  })
+}
+
+// the key of a variant, next to the original so they share its prefix
+pub fn variant_key(key: &str, variant: &str) -> String {
+  format!("{}.{}.jpg", key, variant)
+}
+
+// smaller images are not scaled up
+fn fit_within(image: &DynamicImage, size: u32) -> DynamicImage {
+  if image.width() <= size && image.height() <= size {
+    return image.clone();
+  }
+  image.resize(size, size, FilterType::Triangle)
+}
+
+// JPEG has no alpha channel
+fn encode_jpeg(image: &DynamicImage, quality: u8) -> Result<EncodedImage, ProcessingError> {
+  encode(
+    &DynamicImage::ImageRgb8(image.to_rgb8()),
+    ImageOutputFormat::Jpeg(quality),
+    VARIANT_CONTENT_TYPE,
+  )
+}
+
+fn encode(
+  image: &DynamicImage,
+  format: ImageOutputFormat,
+  content_type: &'static str,
+) -> Result<EncodedImage, ProcessingError> {
+  let mut content = Cursor::new(vec![]);
+  image.write_to(&mut content, format)?;
+  Ok(EncodedImage {
+    content: content.into_inner(),
+    content_type,
+  })
+}
+
+// see the Orientation tag of the EXIF specification
+fn apply_orientation(image: DynamicImage, orientation: u16) -> DynamicImage {
+  match orientation {
+    2 => image.fliph(),
+    3 => image.rotate180(),
+    4 => image.flipv(),
+    5 => image.rotate90().fliph(),
+    6 => image.rotate90(),
+    7 => image.rotate270().fliph(),
+    8 => image.rotate270(),
+    _ => image,
+  }
+}
+
+// the orientation from the EXIF block of a JPEG, in its APP1 segment
+fn read_jpeg_orientation(content: &[u8]) -> Option<u16> {
+  if content.get(0..2)? != [0xFF, 0xD8] {
+    return None;
+  }
+  let mut position = 2;
+  loop {
+    let marker = content.get(position..position + 2)?;
+    if marker[0] != 0xFF {
+      return None;
+    }
+    // the image data starts with the start of scan segment
+    if marker[1] == 0xDA {
+      return None;
+    }
+    let length = u16::from_be_bytes([*content.get(position + 2)?, *content.get(position + 3)?]);
+    let segment = content.get(position + 4..position + 2 + length as usize)?;
+    if marker[1] == 0xE1 && segment.starts_with(b"Exif\0\0") {
+      return read_tiff_orientation(&segment[6..]);
+    }
+    position += 2 + length as usize;
+  }
+}
+
+fn read_tiff_orientation(tiff: &[u8]) -> Option<u16> {
+  let little_endian = match tiff.get(0..2)? {
+    b"II" => true,
+    b"MM" => false,
+    _ => return None,
+  };
+  let read_u16 = |offset: usize| -> Option<u16> {
+    let bytes = [*tiff.get(offset)?, *tiff.get(offset + 1)?];
+    Some(if little_endian {
+      u16::from_le_bytes(bytes)
+    } else {
+      u16::from_be_bytes(bytes)
+    })
+  };
+  let read_u32 = |offset: usize| -> Option<u32> {
+    let bytes = [
+      *tiff.get(offset)?,
+      *tiff.get(offset + 1)?,
+      *tiff.get(offset + 2)?,
+      *tiff.get(offset + 3)?,
+    ];
+    Some(if little_endian {
+      u32::from_le_bytes(bytes)
+    } else {
+      u32::from_be_bytes(bytes)
+    })
+  };
+  let first_directory = read_u32(4)? as usize;
+  let entries = read_u16(first_directory)? as usize;
+  (0..entries)
+    .map(|index| first_directory + 2 + index * 12)
+    .find(|entry| read_u16(*entry) == Some(EXIF_ORIENTATION_TAG))
+    .and_then(|entry| read_u16(entry + 8))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use image::{GenericImageView, GrayImage, Luma, RgbImage};
+
+  const MAKE_TAG: u16 = 0x010F;
+  const SHORT_TYPE: u16 = 3;
+
+  // a TIFF header and one directory with an unrelated entry before the orientation
+  fn tiff(little_endian: bool, orientation: u16) -> Vec<u8> {
+    let u16_bytes = |value: u16| {
+      if little_endian {
+        value.to_le_bytes()
+      } else {
+        value.to_be_bytes()
+      }
+    };
+    let u32_bytes = |value: u32| {
+      if little_endian {
+        value.to_le_bytes()
+      } else {
+        value.to_be_bytes()
+      }
+    };
+    let mut tiff = if little_endian {
+      b"II".to_vec()
+    } else {
+      b"MM".to_vec()
+    };
+    tiff.extend(u16_bytes(42));
+    tiff.extend(u32_bytes(8));
+    tiff.extend(u16_bytes(2));
+    for (tag, value) in [(MAKE_TAG, 0), (EXIF_ORIENTATION_TAG, orientation)] {
+      tiff.extend(u16_bytes(tag));
+      tiff.extend(u16_bytes(SHORT_TYPE));
+      tiff.extend(u32_bytes(1));
+      tiff.extend(u16_bytes(value));
+      tiff.extend([0, 0]);
+    }
+    tiff.extend(u32_bytes(0));
+    tiff
+  }
+
+  fn segment(marker: u8, content: &[u8]) -> Vec<u8> {
+    let mut segment = vec![0xFF, marker];
+    segment.extend((content.len() as u16 + 2).to_be_bytes());
+    segment.extend(content);
+    segment
+  }
+
+  fn exif_segment(tiff: &[u8]) -> Vec<u8> {
+    segment(0xE1, &[b"Exif\0\0".as_slice(), tiff].concat())
+  }
+
+  // the start of image, an APP0 segment to skip and the EXIF block
+  fn jpeg_header(tiff: &[u8]) -> Vec<u8> {
+    let mut content = vec![0xFF, 0xD8];
+    content.extend(segment(0xE0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0"));
+    content.extend(exif_segment(tiff));
+    content.extend(segment(0xDA, &[]));
+    content
+  }
+
+  // pixel (x, y) of the 3x2 test image has the value 10 * y + x
+  fn test_image() -> DynamicImage {
+    DynamicImage::ImageLuma8(GrayImage::from_fn(3, 2, |x, y| Luma([(10 * y + x) as u8])))
+  }
+
+  fn top_left(image: &DynamicImage) -> u8 {
+    image.to_luma8().get_pixel(0, 0)[0]
+  }
+
+  #[test]
+  fn reads_the_orientation_in_both_byte_orders() {
+    for orientation in 1..=8 {
+      for little_endian in [true, false] {
+        assert_eq!(
+          read_jpeg_orientation(&jpeg_header(&tiff(little_endian, orientation))),
+          Some(orientation)
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn ignores_content_without_exif() {
+    assert_eq!(read_jpeg_orientation(b""), None);
+    assert_eq!(read_jpeg_orientation(b"\x89PNG\r\n\x1a\n"), None);
+    let mut content = vec![0xFF, 0xD8];
+    content.extend(segment(0xE1, b"http://ns.adobe.com/xap/1.0/\0"));
+    content.extend(segment(0xDA, &[]));
+    assert_eq!(read_jpeg_orientation(&content), None);
+    // bytes between segments that are not a marker
+    assert_eq!(read_jpeg_orientation(&[0xFF, 0xD8, 0x00, 0xE1]), None);
+  }
+
+  #[test]
+  fn ignores_truncated_segments() {
+    let content = jpeg_header(&tiff(true, 6));
+    let exif_end = 2 + 18 + 8 + tiff(true, 6).len();
+    for length in [3, 4, 21, 25, exif_end - 1] {
+      assert_eq!(
+        read_jpeg_orientation(&content[..length]),
+        None,
+        "{}",
+        length
+      );
+    }
+  }
+
+  #[test]
+  fn ignores_segment_lengths_under_2() {
+    for length in [0u16, 1] {
+      let mut content = vec![0xFF, 0xD8, 0xFF, 0xE0];
+      content.extend(length.to_be_bytes());
+      content.extend(exif_segment(&tiff(true, 6)));
+      assert_eq!(read_jpeg_orientation(&content), None);
+    }
+  }
+
+  #[test]
+  fn ignores_invalid_tiff() {
+    let mut invalid_order = tiff(true, 6);
+    invalid_order[0..2].copy_from_slice(b"XX");
+    assert_eq!(read_jpeg_orientation(&jpeg_header(&invalid_order)), None);
+    // the directory offset points past the block
+    let mut invalid_offset = tiff(true, 6);
+    invalid_offset[4..8].copy_from_slice(&1000u32.to_le_bytes());
+    assert_eq!(read_jpeg_orientation(&jpeg_header(&invalid_offset)), None);
+    // more entries than the block holds
+    let mut invalid_count = tiff(false, 6);
+    invalid_count[8..10].copy_from_slice(&500u16.to_be_bytes());
+    invalid_count.truncate(22);
+    assert_eq!(read_jpeg_orientation(&jpeg_header(&invalid_count)), None);
+  }
+
+  #[test]
+  fn applies_every_orientation() {
+    // the original pixel that ends up in the top left corner, and the size
+    let expected = [
+      (1, 0, (3, 2)),
+      (2, 2, (3, 2)),
+      (3, 12, (3, 2)),
+      (4, 10, (3, 2)),
+      (5, 0, (2, 3)),
+      (6, 10, (2, 3)),
+      (7, 12, (2, 3)),
+      (8, 2, (2, 3)),
+      // unknown values leave the image as it is
+      (0, 0, (3, 2)),
+      (9, 0, (3, 2)),
+    ];
+    for (orientation, pixel, dimensions) in expected {
+      let oriented = apply_orientation(test_image(), orientation);
+      assert_eq!(oriented.dimensions(), dimensions, "{}", orientation);
+      assert_eq!(top_left(&oriented), pixel, "{}", orientation);
+    }
+  }
+
+  #[test]
+  fn process_strips_the_exif_block() {
+    let mut encoded = Cursor::new(vec![]);
+    DynamicImage::ImageRgb8(RgbImage::new(40, 20))
+      .write_to(&mut encoded, ImageOutputFormat::Jpeg(90))
+      .unwrap();
+    let encoded = encoded.into_inner();
+    // the EXIF block goes right after the start of image
+    let content = [&encoded[..2], &exif_segment(&tiff(false, 6)), &encoded[2..]].concat();
+    assert_eq!(read_jpeg_orientation(&content), Some(6));
+
+    let processed = process_image(&content).unwrap();
+    assert_eq!(processed.original.content_type, "image/jpeg");
+    assert_eq!(read_jpeg_orientation(&processed.original.content), None);
+    let original = image::load_from_memory(&processed.original.content).unwrap();
+    assert_eq!(original.dimensions(), (20, 40));
+  }
+
+  #[test]
+  fn process_resizes_the_variants() {
+    let mut encoded = Cursor::new(vec![]);
+    DynamicImage::ImageRgb8(RgbImage::new(2048, 512))
+      .write_to(&mut encoded, ImageOutputFormat::Png)
+      .unwrap();
+    let processed = process_image(&encoded.into_inner()).unwrap();
+    assert_eq!(processed.original.content_type, "image/png");
+    let dimensions = |encoded: &EncodedImage| {
+      image::load_from_memory(&encoded.content)
+        .unwrap()
+        .dimensions()
+    };
+    assert_eq!(dimensions(&processed.original), (2048, 512));
+    assert_eq!(dimensions(&processed.thumbnail), (320, 80));
+    assert_eq!(dimensions(&processed.medium), (1024, 256));
+    assert_eq!(processed.thumbnail.content_type, VARIANT_CONTENT_TYPE);
+  }
+
+  #[test]
+  fn process_keeps_small_images() {
+    let mut encoded = Cursor::new(vec![]);
+    test_image()
+      .write_to(&mut encoded, ImageOutputFormat::Png)
+      .unwrap();
+    let processed = process_image(&encoded.into_inner()).unwrap();
+    let thumbnail = image::load_from_memory(&processed.thumbnail.content).unwrap();
+    assert_eq!(thumbnail.dimensions(), (3, 2));
+  }
+
+  #[test]
+  fn process_refuses_invalid_content() {
+    assert!(process_image(b"").is_err());
+    assert!(process_image(b"not an image").is_err());
+    // a JPEG that ends after its header
+    assert!(process_image(&jpeg_header(&tiff(true, 1))).is_err());
+  }
+}
@@ -0,0 +1,518 @@
+use csv::{ReaderBuilder, StringRecord, Trim, WriterBuilder};
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{Read, Write};
+
+use crate::repository::category::get_categories;
+use crate::repository::geofence::{get_geofences, Geofence};
+use crate::repository::item::{get_items_by_user_id, insert_new_item, Item};
+use crate::repository::karat::get_karats;
+
+pub const MAX_IMPORT_ROWS: usize = 500;
+
+const COLUMNS: [&str; 8] = [
+  "title",
+  "description",
+  "price",
+  "karat",
+  "category",
+  "region",
+  "weight",
+  "size",
+];
+
+// `row` is the line of the CSV file, the header being line 1
+#[derive(Debug, Clone)]
+pub struct RowError {
+  pub row: u64,
+  pub message: String,
+}
+
+#[derive(Debug)]
+pub enum CsvError {
+  // nothing was imported because some rows are invalid
+  InvalidRows(Vec<RowError>),
+  DbError(DieselError),
+  CsvError(csv::Error),
+}
+
+impl From<DieselError> for CsvError {
+  fn from(err: DieselError) -> CsvError {
+    CsvError::DbError(err)
+  }
+}
+
+impl From<csv::Error> for CsvError {
+  fn from(err: csv::Error) -> CsvError {
+    CsvError::CsvError(err)
+  }
+}
+
+impl fmt::Display for CsvError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      CsvError::InvalidRows(errors) => write!(f, "{} invalid rows", errors.len()),
+      CsvError::DbError(ref e) => e.fmt(f),
+      CsvError::CsvError(ref e) => e.fmt(f),
+    }
+  }
+}
+
+struct ImportRow {
+  title: String,
+  description: String,
+  price: i64,
+  karat_id: i64,
+  category_id: i64,
+  geofence_id: i64,
+  weight: f64,
+  size: f64,
+}
+
+#[derive(Serialize)]
+struct ExportRow<'a> {
+  title: &'a str,
+  description: &'a str,
+  price: i64,
+  karat: &'a str,
+  category: &'a str,
+  region: &'a str,
+  weight: f64,
+  size: f64,
+  status: &'a str,
+}
+
+// names are matched case insensitively
+struct Catalog {
+  karats: HashMap<String, i64>,
+  categories: HashMap<String, i64>,
+  regions: HashMap<String, i64>,
+}
+
+impl Catalog {
+  fn load(conn: &mut PgConnection) -> Result<Catalog, DieselError> {
+    Ok(Catalog {
+      karats: get_karats(conn)?
+        .into_iter()
+        .filter(|karat| karat.deleted_at.is_none())
+        .map(|karat| (karat.name.to_lowercase(), karat.id))
+        .collect(),
+      categories: get_categories(conn)?
+        .into_iter()
+        .filter(|category| category.deleted_at.is_none())
+        .map(|category| (category.name.to_lowercase(), category.id))
+        .collect(),
+      regions: get_leaf_regions(conn)?,
+    })
+  }
+}
+
+// items can only be listed in live regions without subregions
+fn get_leaf_regions(conn: &mut PgConnection) -> Result<HashMap<String, i64>, DieselError> {
+  let live: Vec<Geofence> = get_geofences(conn)?
+    .into_iter()
+    .filter(|geofence| geofence.deleted_at.is_none())
+    .collect();
+  let parent_ids: HashSet<i64> = live
+    .iter()
+    .map(|geofence| geofence.parent_region_id)
+    .collect();
+  Ok(
+    live
+      .into_iter()
+      .filter(|geofence| !parent_ids.contains(&geofence.id))
+      .map(|geofence| (geofence.name.to_lowercase(), geofence.id))
+      .collect(),
+  )
+}
+
+// Imports the rows as draft items of the owner. Every row is validated first and
+// nothing is imported unless all of them are valid.
+pub fn import_items<R: Read>(
+  conn: &mut PgConnection,
+  owner_id: i64,
+  input: R,
+) -> Result<Vec<Item>, CsvError> {
+  let catalog = Catalog::load(conn)?;
+  let rows = parse_rows(input, &catalog)?;
+  let items = conn.transaction::<_, DieselError, _>(|conn| {
+    let mut items = Vec::with_capacity(rows.len());
+    for row in rows {
+      items.push(insert_new_item(
+        conn,
+        owner_id,
+        row.title,
+        row.description,
+        row.price,
+        false,
+        row.size,
+        row.weight,
+        row.karat_id,
+        row.category_id,
+        row.geofence_id,
+        None,
+      )?);
+    }
+    Ok(items)
+  })?;
+  Ok(items)
+}
+
+// every row with the line it starts on when one of them is invalid
+fn parse_rows<R: Read>(input: R, catalog: &Catalog) -> Result<Vec<ImportRow>, CsvError> {
+  let mut reader = ReaderBuilder::new().trim(Trim::All).from_reader(input);
+  let headers = reader.headers()?.clone();
+  let mut positions: HashMap<&str, usize> = HashMap::new();
+  let mut missing_columns = vec![];
+  for column in COLUMNS {
+    match headers
+      .iter()
+      .position(|header| header.eq_ignore_ascii_case(column))
+    {
+      Some(position) => {
+        positions.insert(column, position);
+      }
+      None => missing_columns.push(column),
+    }
+  }
+  if !missing_columns.is_empty() {
+    return Err(CsvError::InvalidRows(vec![RowError {
+      row: 1,
+      message: format!("missing columns: {}", missing_columns.join(", ")),
+    }]));
+  }
+
+  let mut rows: Vec<ImportRow> = vec![];
+  let mut errors: Vec<RowError> = vec![];
+  for (index, record) in reader.records().enumerate() {
+    // the line the record starts on, quoted fields can span several lines
+    let position = match &record {
+      Ok(record) => record.position(),
+      Err(e) => e.position(),
+    };
+    let row = position.map_or(index as u64 + 2, |position| position.line());
+    if index >= MAX_IMPORT_ROWS {
+      errors.push(RowError {
+        row,
+        message: format!("can not import more than {} rows", MAX_IMPORT_ROWS),
+      });
+      break;
+    }
+    let record = match record {
+      Ok(record) => record,
+      Err(e) => {
+        errors.push(RowError {
+          row,
+          message: e.to_string(),
+        });
+        continue;
+      }
+    };
+    match parse_row(&record, &positions, catalog) {
+      Ok(parsed) => rows.push(parsed),
+      Err(messages) => errors.push(RowError {
+        row,
+        message: messages.join("; "),
+      }),
+    }
+  }
+  if !errors.is_empty() {
+    return Err(CsvError::InvalidRows(errors));
+  }
+  Ok(rows)
+}
+
+fn parse_row(
+  record: &StringRecord,
+  positions: &HashMap<&str, usize>,
+  catalog: &Catalog,
+) -> Result<ImportRow, Vec<String>> {
+  let field = |column: &str| record.get(positions[column]).unwrap_or("");
+  let mut messages: Vec<String> = vec![];
+
+  let title = field("title");
+  if title.is_empty() {
+    messages.push("title is empty".to_string());
+  }
+  let description = field("description");
+  if description.is_empty() {
+    messages.push("description is empty".to_string());
+  }
+  let price = match field("price").parse::<i64>() {
+    Ok(price) if price > 0 => price,
+    _ => {
+      messages.push(format!("invalid price: {:?}", field("price")));
+      0
+    }
+  };
+  let weight = match field("weight").parse::<f64>() {
+    Ok(weight) if weight > 0.0 => weight,
+    _ => {
+      messages.push(format!("invalid weight: {:?}", field("weight")));
+      0.0
+    }
+  };
+  let size = match field("size").parse::<f64>() {
+    Ok(size) if size >= 0.0 => size,
+    _ => {
+      messages.push(format!("invalid size: {:?}", field("size")));
+      0.0
+    }
+  };
+  let mut lookup =
+    |values: &HashMap<String, i64>, column: &str| match values.get(&field(column).to_lowercase()) {
+      Some(value) => *value,
+      None => {
+        messages.push(format!("unknown {}: {:?}", column, field(column)));
+        0
+      }
+    };
+  let karat_id = lookup(&catalog.karats, "karat");
+  let category_id = lookup(&catalog.categories, "category");
+  let geofence_id = lookup(&catalog.regions, "region");
+
+  if !messages.is_empty() {
+    return Err(messages);
+  }
+  Ok(ImportRow {
+    title: title.to_string(),
+    description: description.to_string(),
+    price,
+    karat_id,
+    category_id,
+    geofence_id,
+    weight,
+    size,
+  })
+}
+
+// Writes every listing of the owner with the import columns and its status
+pub fn export_items<W: Write>(
+  conn: &mut PgConnection,
+  owner_id: i64,
+  output: W,
+) -> Result<(), CsvError> {
+  // deleted names are kept so old listings still export
+  let karats: HashMap<i64, String> = get_karats(conn)?
+    .into_iter()
+    .map(|karat| (karat.id, karat.name))
+    .collect();
+  let categories: HashMap<i64, String> = get_categories(conn)?
+    .into_iter()
+    .map(|category| (category.id, category.name))
+    .collect();
+  let regions: HashMap<i64, String> = get_geofences(conn)?
+    .into_iter()
+    .map(|geofence| (geofence.id, geofence.name))
+    .collect();
+
+  let mut writer = WriterBuilder::new().from_writer(output);
+  let items = get_items_by_user_id(conn, owner_id)?;
+  for item in items.iter().filter(|item| item.deleted_at.is_none()) {
+    writer.serialize(ExportRow {
+      title: &item.title,
+      description: &item.description,
+      price: item.price,
+      karat: karats.get(&item.karat_id).map_or("", |name| name.as_str()),
+      category: categories
+        .get(&item.category_id)
+        .map_or("", |name| name.as_str()),
+      region: regions
+        .get(&item.geofence_id)
+        .map_or("", |name| name.as_str()),
+      weight: item.weight,
+      size: item.size,
+      status: &item.item_status,
+    })?;
+  }
+  writer.flush().map_err(|e| CsvError::CsvError(e.into()))?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const HEADER: &str = "title,description,price,karat,category,region,weight,size";
+
+  fn catalog() -> Catalog {
+    Catalog {
+      karats: HashMap::from([("585".to_string(), 1)]),
+      categories: HashMap::from([("rings".to_string(), 2)]),
+      regions: HashMap::from([("dushanbe".to_string(), 3)]),
+    }
+  }
+
+  fn rows(lines: &[&str]) -> String {
+    [HEADER]
+      .iter()
+      .chain(lines)
+      .copied()
+      .collect::<Vec<_>>()
+      .join("\n")
+  }
+
+  // the line and message of every invalid row
+  fn row_errors(input: &str) -> Vec<(u64, String)> {
+    match parse_rows(input.as_bytes(), &catalog()) {
+      Err(CsvError::InvalidRows(errors)) => errors
+        .into_iter()
+        .map(|error| (error.row, error.message))
+        .collect(),
+      Err(e) => panic!("unexpected error: {}", e),
+      Ok(_) => panic!("the rows were accepted"),
+    }
+  }
+
+  fn error_lines(input: &str) -> Vec<u64> {
+    row_errors(input).into_iter().map(|(row, _)| row).collect()
+  }
+
+  #[test]
+  fn parses_valid_rows() {
+    let input = rows(&[
+      "Ring,Gold ring,1200,585,Rings,Dushanbe,3.5,17",
+      " Chain , Long chain ,900,585,RINGS,dushanbe,10,0",
+    ]);
+    let parsed = match parse_rows(input.as_bytes(), &catalog()) {
+      Ok(parsed) => parsed,
+      Err(e) => panic!("unexpected error: {}", e),
+    };
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed[0].title, "Ring");
+    assert_eq!(parsed[0].price, 1200);
+    assert_eq!(
+      (
+        parsed[0].karat_id,
+        parsed[0].category_id,
+        parsed[0].geofence_id
+      ),
+      (1, 2, 3)
+    );
+    assert_eq!((parsed[0].weight, parsed[0].size), (3.5, 17.0));
+    assert_eq!(parsed[1].title, "Chain");
+    assert_eq!(parsed[1].description, "Long chain");
+  }
+
+  #[test]
+  fn invalid_prices() {
+    let errors = row_errors(&rows(&[
+      "Ring,Gold ring,1200,585,Rings,Dushanbe,3.5,17",
+      "Ring,Gold ring,cheap,585,Rings,Dushanbe,3.5,17",
+      "Ring,Gold ring,-5,585,Rings,Dushanbe,3.5,17",
+      "Ring,Gold ring,0,585,Rings,Dushanbe,3.5,17",
+    ]));
+    assert_eq!(
+      errors,
+      vec![
+        (3, "invalid price: \"cheap\"".to_string()),
+        (4, "invalid price: \"-5\"".to_string()),
+        (5, "invalid price: \"0\"".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn invalid_weights_and_sizes() {
+    let errors = row_errors(&rows(&[
+      "Ring,Gold ring,1200,585,Rings,Dushanbe,heavy,17",
+      "Ring,Gold ring,1200,585,Rings,Dushanbe,0,17",
+      "Ring,Gold ring,1200,585,Rings,Dushanbe,3.5,-1",
+      "Ring,Gold ring,1200,585,Rings,Dushanbe,-1,big",
+    ]));
+    assert_eq!(
+      errors,
+      vec![
+        (2, "invalid weight: \"heavy\"".to_string()),
+        (3, "invalid weight: \"0\"".to_string()),
+        (4, "invalid size: \"-1\"".to_string()),
+        (
+          5,
+          "invalid weight: \"-1\"; invalid size: \"big\"".to_string()
+        ),
+      ]
+    );
+  }
+
+  #[test]
+  fn unknown_names() {
+    let errors = row_errors(&rows(&[
+      "Ring,Gold ring,1200,999,Rings,Dushanbe,3.5,17",
+      "Ring,Gold ring,1200,585,Watches,Dushanbe,3.5,17",
+      "Ring,Gold ring,1200,585,Rings,Khujand,3.5,17",
+    ]));
+    assert_eq!(
+      errors,
+      vec![
+        (2, "unknown karat: \"999\"".to_string()),
+        (3, "unknown category: \"Watches\"".to_string()),
+        (4, "unknown region: \"Khujand\"".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn empty_texts() {
+    let errors = row_errors(&rows(&[",,1200,585,Rings,Dushanbe,3.5,17"]));
+    assert_eq!(
+      errors,
+      vec![(2, "title is empty; description is empty".to_string())]
+    );
+  }
+
+  #[test]
+  fn missing_columns() {
+    let errors =
+      row_errors("title,description,price,karat,category,weight\nRing,Gold,1,585,Rings,1");
+    assert_eq!(
+      errors,
+      vec![(1, "missing columns: region, size".to_string())]
+    );
+  }
+
+  #[test]
+  fn extra_columns_are_ignored() {
+    let input = format!(
+      "notes,{}\nfragile,Ring,Gold ring,1200,585,Rings,Dushanbe,3.5,17",
+      HEADER
+    );
+    assert!(parse_rows(input.as_bytes(), &catalog()).is_ok());
+  }
+
+  #[test]
+  fn rows_with_a_different_number_of_fields() {
+    let lines = error_lines(&rows(&[
+      "Ring,Gold ring,1200,585,Rings,Dushanbe,3.5,17",
+      "Ring,Gold ring,1200,585,Rings,Dushanbe,3.5,17,extra",
+      "Ring,Gold ring,1200,585,Rings,Dushanbe,3.5",
+    ]));
+    assert_eq!(lines, vec![3, 4]);
+  }
+
+  #[test]
+  fn quoted_fields_spanning_lines() {
+    let lines = error_lines(&rows(&[
+      "Ring,\"Gold ring\nwith a stone\n\nand a box\",1200,585,Rings,Dushanbe,3.5,17",
+      "Ring,Gold ring,cheap,585,Rings,Dushanbe,3.5,17",
+      "\"Chain\nlong\",Gold chain,900,585,Rings,Nowhere,3.5,17",
+      "Ring,Gold ring,1200,585,Rings,Dushanbe,heavy,17",
+    ]));
+    assert_eq!(lines, vec![6, 7, 9]);
+  }
+
+  #[test]
+  fn too_many_rows() {
+    let line = "Ring,Gold ring,1200,585,Rings,Dushanbe,3.5,17";
+    let input = rows(&vec![line; MAX_IMPORT_ROWS + 1]);
+    assert_eq!(
+      row_errors(&input),
+      vec![(
+        MAX_IMPORT_ROWS as u64 + 2,
+        format!("can not import more than {} rows", MAX_IMPORT_ROWS)
+      )]
+    );
+  }
+}
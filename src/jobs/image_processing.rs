@@ -0,0 +1,119 @@
+use actix::prelude::{Actor, AsyncContext, Context};
+use diesel::PgConnection;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::run_off_arbiter;
+use crate::images::{process_image, variant_key, ProcessedImage, ProcessingError};
+use crate::repository::item_image::{
+  get_unprocessed_images, remove_item_image, set_image_variants, ItemImage,
+};
+use crate::routes::DbPool;
+use crate::storage::{delete_objects, ObjectStorage};
+
+const PROCESSING_INTERVAL: Duration = Duration::from_secs(30);
+// images handled per run, the rest waits for the next one
+const PROCESSING_BATCH_SIZE: i64 = 20;
+pub const THUMBNAIL_VARIANT: &str = "thumbnail";
+pub const MEDIUM_VARIANT: &str = "medium";
+
+// Picks up uploaded images, replaces the original with a copy without its
+// metadata and stores the resized variants next to it.
+#[derive(Clone)]
+pub struct ImageProcessingJob {
+  pool: DbPool,
+  storage: Arc<dyn ObjectStorage>,
+  busy: Arc<AtomicBool>,
+}
+
+impl ImageProcessingJob {
+  pub fn new(pool: DbPool, storage: Arc<dyn ObjectStorage>) -> ImageProcessingJob {
+    ImageProcessingJob {
+      pool,
+      storage,
+      busy: Arc::new(AtomicBool::new(false)),
+    }
+  }
+
+  // downloading, resizing and uploading a batch takes a while
+  fn schedule(&self) {
+    let job = self.clone();
+    run_off_arbiter(&self.busy, move || job.run());
+  }
+
+  fn run(&self) {
+    let mut conn = match self.pool.get() {
+      Ok(conn) => conn,
+      Err(e) => {
+        log::warn!("failed to get connection for image processing: {}", e);
+        return;
+      }
+    };
+    let images = match get_unprocessed_images(&mut conn, PROCESSING_BATCH_SIZE) {
+      Ok(images) => images,
+      Err(e) => {
+        log::warn!("failed to get unprocessed images: {}", e);
+        return;
+      }
+    };
+    for image in images {
+      self.process(&mut conn, &image);
+    }
+  }
+
+  // the upload keeps its metadata until it is processed, so it is removed when
+  // that fails
+  fn process(&self, conn: &mut PgConnection, image: &ItemImage) {
+    let thumbnail_key = variant_key(&image.key, THUMBNAIL_VARIANT);
+    let medium_key = variant_key(&image.key, MEDIUM_VARIANT);
+    let result = match self.store_variants(image, &thumbnail_key, &medium_key) {
+      Ok(()) => set_image_variants(conn, image.id, thumbnail_key, medium_key),
+      Err(e) => {
+        log::warn!("failed to process image {}, removing it: {}", image.id, e);
+        remove_item_image(conn, image.clone())
+          .map(|object_names| delete_objects(self.storage.as_ref(), &object_names))
+      }
+    };
+    if let Err(e) = result {
+      log::warn!("failed to update image {}: {}", image.id, e);
+    }
+  }
+
+  fn store_variants(
+    &self,
+    image: &ItemImage,
+    thumbnail_key: &str,
+    medium_key: &str,
+  ) -> Result<(), ProcessingError> {
+    let content = self
+      .storage
+      .get(&image.key)
+      .map_err(|e| ProcessingError(e.to_string()))?;
+    let ProcessedImage {
+      original,
+      thumbnail,
+      medium,
+    } = process_image(&content)?;
+    for (key, variant) in [
+      (thumbnail_key, thumbnail),
+      (medium_key, medium),
+      (image.key.as_str(), original),
+    ] {
+      self
+        .storage
+        .put(key, &variant.content, variant.content_type)
+        .map_err(|e| ProcessingError(e.to_string()))?;
+    }
+    Ok(())
+  }
+}
+
+impl Actor for ImageProcessingJob {
+  type Context = Context<Self>;
+
+  fn started(&mut self, ctx: &mut Self::Context) {
+    self.schedule();
+    ctx.run_interval(PROCESSING_INTERVAL, |act, _| act.schedule());
+  }
+}
@@ -0,0 +1,124 @@
+use actix::prelude::{Actor, Addr, AsyncContext, Context};
+use chrono::Duration as ChronoDuration;
+use diesel::PgConnection;
+use std::time::Duration;
+
+use crate::helpers::{get_env_or, new_naive_date};
+use crate::repository::item::{expire_items, get_items_expiring_before, set_expiry_notified};
+use crate::repository::notification::{
+  InsertNotification, ITEM_EXPIRED_NOTIFICATION, ITEM_EXPIRING_NOTIFICATION,
+};
+use crate::routes::notification::try_notify_user;
+use crate::routes::DbPool;
+use crate::ws::lobby::Lobby;
+
+const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone)]
+pub struct ListingConfig {
+  // how long an item stays listed after it is created or renewed
+  pub lifetime: ChronoDuration,
+  // owners are notified this long before their item expires
+  pub expiry_notice: ChronoDuration,
+  // minimum time between two bumps of the same item
+  pub bump_interval: ChronoDuration,
+}
+
+impl ListingConfig {
+  pub fn from_env() -> ListingConfig {
+    ListingConfig {
+      lifetime: ChronoDuration::days(get_env_or("LISTING_LIFETIME_DAYS", "30").parse().unwrap()),
+      expiry_notice: ChronoDuration::days(
+        get_env_or("LISTING_EXPIRY_NOTICE_DAYS", "3")
+          .parse()
+          .unwrap(),
+      ),
+      bump_interval: ChronoDuration::hours(
+        get_env_or("LISTING_BUMP_INTERVAL_HOURS", "24")
+          .parse()
+          .unwrap(),
+      ),
+    }
+  }
+}
+
+// Periodically warns owners about listings that are about to expire and
+// expires the ones that ran out of time.
+pub struct ListingExpiryJob {
+  pool: DbPool,
+  srv: Addr<Lobby>,
+  config: ListingConfig,
+}
+
+impl ListingExpiryJob {
+  pub fn new(pool: DbPool, srv: Addr<Lobby>, config: ListingConfig) -> ListingExpiryJob {
+    ListingExpiryJob { pool, srv, config }
+  }
+
+  fn run(&self) {
+    let mut conn = match self.pool.get() {
+      Ok(conn) => conn,
+      Err(e) => {
+        log::warn!("failed to get connection for listing expiry: {}", e);
+        return;
+      }
+    };
+    self.notify_expiring(&mut conn);
+    self.expire(&mut conn);
+  }
+
+  fn notify_expiring(&self, conn: &mut PgConnection) {
+    let deadline = new_naive_date() + self.config.expiry_notice;
+    let items = match get_items_expiring_before(conn, deadline) {
+      Ok(items) => items,
+      Err(e) => {
+        log::warn!("failed to get expiring items: {}", e);
+        return;
+      }
+    };
+    for item in items {
+      let new_notification = InsertNotification {
+        user_id: item.owner_id,
+        notification_type: ITEM_EXPIRING_NOTIFICATION.to_string(),
+        title: format!("{} expires soon", item.title),
+        body: "Renew the listing to keep it visible".to_string(),
+        item_id: Some(item.id),
+        room_id: None,
+      };
+      try_notify_user(conn, &self.srv, &new_notification);
+      if let Err(e) = set_expiry_notified(conn, item.id) {
+        log::warn!("failed to mark item {} as notified: {}", item.id, e);
+      }
+    }
+  }
+
+  fn expire(&self, conn: &mut PgConnection) {
+    let items = match expire_items(conn) {
+      Ok(items) => items,
+      Err(e) => {
+        log::warn!("failed to expire items: {}", e);
+        return;
+      }
+    };
+    for item in items {
+      let new_notification = InsertNotification {
+        user_id: item.owner_id,
+        notification_type: ITEM_EXPIRED_NOTIFICATION.to_string(),
+        title: format!("{} has expired", item.title),
+        body: "Renew the listing to show it again".to_string(),
+        item_id: Some(item.id),
+        room_id: None,
+      };
+      try_notify_user(conn, &self.srv, &new_notification);
+    }
+  }
+}
+
+impl Actor for ListingExpiryJob {
+  type Context = Context<Self>;
+
+  fn started(&mut self, ctx: &mut Self::Context) {
+    self.run();
+    ctx.run_interval(EXPIRY_CHECK_INTERVAL, |act, _| act.run());
+  }
+}
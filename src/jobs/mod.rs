@@ -0,0 +1,25 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+pub mod image_processing;
+pub mod listing_expiry;
+pub mod upload_cleanup;
+
+// Runs the work of a job on the blocking thread pool, the arbiter the jobs are
+// started on also runs the chat lobby. A run is skipped while the previous one
+// is still busy.
+pub fn run_off_arbiter<F>(busy: &Arc<AtomicBool>, work: F)
+where
+  F: FnOnce() + Send + 'static,
+{
+  if busy.swap(true, Ordering::AcqRel) {
+    return;
+  }
+  let busy = busy.clone();
+  actix_web::rt::spawn(async move {
+    if let Err(e) = actix_web::rt::task::spawn_blocking(work).await {
+      log::warn!("background job failed: {}", e);
+    }
+    busy.store(false, Ordering::Release);
+  });
+}
@@ -0,0 +1,150 @@
+use actix::prelude::{Actor, AsyncContext, Context};
+use chrono::Duration as ChronoDuration;
+use diesel::PgConnection;
+use std::collections::HashSet;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::run_off_arbiter;
+use crate::helpers::{get_env_or, new_naive_date};
+use crate::repository::item_image::{
+  get_live_image_keys, get_pending_images_created_before, remove_item_image,
+};
+use crate::repository::user::get_cover_image_keys;
+use crate::routes::DbPool;
+use crate::routes::RouteError;
+use crate::storage::{delete_objects, ObjectStorage};
+
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// item images and profile images are both uploaded under this prefix
+const UPLOAD_KEY_PREFIX: &str = "images/";
+
+#[derive(Debug, Clone)]
+pub struct UploadCleanupConfig {
+  // uploads younger than this are left alone, they may still be confirmed
+  pub grace_period: ChronoDuration,
+  // only report what would be removed
+  pub dry_run: bool,
+}
+
+impl UploadCleanupConfig {
+  pub fn from_env() -> UploadCleanupConfig {
+    UploadCleanupConfig {
+      grace_period: ChronoDuration::hours(
+        get_env_or("UPLOAD_CLEANUP_GRACE_HOURS", "24")
+          .parse()
+          .unwrap(),
+      ),
+      dry_run: get_env_or("UPLOAD_CLEANUP_DRY_RUN", "true")
+        .parse()
+        .unwrap(),
+    }
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct UploadCleanupReport {
+  // item images that were never confirmed as uploaded
+  pub pending_image_ids: Vec<i64>,
+  // objects no image or user refers to, such as replaced profile images
+  pub orphaned_keys: Vec<String>,
+}
+
+// Removes uploads that are not used: item images whose upload was never confirmed
+// and objects left in the storage without a row referring to them.
+#[derive(Clone)]
+pub struct UploadCleanupJob {
+  pool: DbPool,
+  storage: Arc<dyn ObjectStorage>,
+  config: UploadCleanupConfig,
+  busy: Arc<AtomicBool>,
+}
+
+impl UploadCleanupJob {
+  pub fn new(
+    pool: DbPool,
+    storage: Arc<dyn ObjectStorage>,
+    config: UploadCleanupConfig,
+  ) -> UploadCleanupJob {
+    UploadCleanupJob {
+      pool,
+      storage,
+      config,
+      busy: Arc::new(AtomicBool::new(false)),
+    }
+  }
+
+  // listing and removing the objects goes over the network
+  fn schedule(&self) {
+    let job = self.clone();
+    run_off_arbiter(&self.busy, move || job.run());
+  }
+
+  fn run(&self) {
+    let mut conn = match self.pool.get() {
+      Ok(conn) => conn,
+      Err(e) => {
+        log::warn!("failed to get connection for upload cleanup: {}", e);
+        return;
+      }
+    };
+    match self.cleanup(&mut conn) {
+      Ok(report) if self.config.dry_run => log::info!(
+        "upload cleanup dry run, would remove {} pending images {:?} and {} orphaned objects {:?}",
+        report.pending_image_ids.len(),
+        report.pending_image_ids,
+        report.orphaned_keys.len(),
+        report.orphaned_keys
+      ),
+      Ok(report) => log::info!(
+        "upload cleanup removed {} pending images and {} orphaned objects",
+        report.pending_image_ids.len(),
+        report.orphaned_keys.len()
+      ),
+      Err(e) => log::warn!("failed to clean up uploads: {}", e),
+    }
+  }
+
+  fn cleanup(&self, conn: &mut PgConnection) -> Result<UploadCleanupReport, RouteError> {
+    let deadline = new_naive_date() - self.config.grace_period;
+    let mut report = UploadCleanupReport::default();
+
+    for image in get_pending_images_created_before(conn, deadline)? {
+      report.pending_image_ids.push(image.id);
+      if !self.config.dry_run {
+        let object_names = remove_item_image(conn, image)?;
+        delete_objects(self.storage.as_ref(), &object_names);
+      }
+    }
+
+    // read after the pending images are removed, objects they left behind are orphaned
+    let mut referenced_keys: HashSet<String> = get_live_image_keys(conn)?.into_iter().collect();
+    referenced_keys.extend(get_cover_image_keys(conn)?);
+    for object in self.storage.list(UPLOAD_KEY_PREFIX)? {
+      let expired = object
+        .last_modified
+        .is_some_and(|last_modified| last_modified < deadline);
+      if !expired || referenced_keys.contains(&object.key) {
+        continue;
+      }
+      if !self.config.dry_run {
+        if let Err(e) = self.storage.delete(&object.key) {
+          log::warn!("failed to remove orphaned object {}: {}", object.key, e);
+          continue;
+        }
+      }
+      report.orphaned_keys.push(object.key);
+    }
+    Ok(report)
+  }
+}
+
+impl Actor for UploadCleanupJob {
+  type Context = Context<Self>;
+
+  fn started(&mut self, ctx: &mut Self::Context) {
+    self.schedule();
+    ctx.run_interval(CLEANUP_INTERVAL, |act, _| act.schedule());
+  }
+}
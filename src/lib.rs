@@ -1,8 +1,13 @@
 pub mod auth;
 pub mod errors;
+pub mod geo;
 pub mod helpers;
+pub mod images;
+pub mod item_csv;
+pub mod jobs;
+pub mod push;
 pub mod repository;
 pub mod routes;
-pub mod s3_bucket;
 pub mod schema;
+pub mod storage;
 pub mod ws;
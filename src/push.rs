@@ -0,0 +1,65 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PushMessage {
+  pub token: String,
+  pub title: String,
+  pub body: String,
+  pub room_id: Option<i64>,
+}
+
+#[derive(Debug)]
+pub struct PushError(pub String);
+
+impl fmt::Display for PushError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Push error: {}", self.0)
+  }
+}
+
+// Delivers push notifications to user devices. Implementations are called from
+// the lobby actor, so `send` must not block for long.
+pub trait PushSender: Send + Sync {
+  fn send(&self, messages: Vec<PushMessage>) -> Result<(), PushError>;
+}
+
+// Only logs the pushes, used until a push provider is configured
+pub struct LogPushSender;
+
+impl PushSender for LogPushSender {
+  fn send(&self, messages: Vec<PushMessage>) -> Result<(), PushError> {
+    for message in messages {
+      log::info!(
+        "push to {}: {} - {}",
+        message.token,
+        message.title,
+        message.body
+      );
+    }
+    Ok(())
+  }
+}
+
+// Keeps every sent push in memory so tests can assert on them
+#[derive(Clone, Default)]
+pub struct RecordingPushSender {
+  sent: Arc<Mutex<Vec<PushMessage>>>,
+}
+
+impl RecordingPushSender {
+  pub fn new() -> RecordingPushSender {
+    RecordingPushSender::default()
+  }
+
+  pub fn sent(&self) -> Vec<PushMessage> {
+    self.sent.lock().unwrap().clone()
+  }
+}
+
+impl PushSender for RecordingPushSender {
+  fn send(&self, messages: Vec<PushMessage>) -> Result<(), PushError> {
+    self.sent.lock().unwrap().extend(messages);
+    Ok(())
+  }
+}
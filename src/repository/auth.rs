@@ -79,3 +79,15 @@ pub fn delete_refresh_token(
   }
   Ok(())
 }
+
+// used when a user is suspended, so none of their sessions can be refreshed
+pub fn delete_all_refresh_tokens(
+  conn: &mut PgConnection,
+  requester_user_id: i64,
+) -> Result<usize, DieselError> {
+  let result = diesel::update(refresh_token)
+    .filter(deleted_at.is_null().and(user_id.eq(requester_user_id)))
+    .set(deleted_at.eq(new_naive_date()))
+    .execute(conn)?;
+  Ok(result)
+}
@@ -10,6 +10,8 @@ use crate::schema::category::dsl::*;
 pub struct InsertCategory {
   pub name: String,
   pub avatar: String,
+  pub parent_id: Option<i64>,
+  pub position: i32,
 }
 
 #[derive(Clone, Serialize, Deserialize, Queryable)]
@@ -20,16 +22,22 @@ pub struct Category {
   pub created_at: chrono::NaiveDateTime,
   pub updated_at: chrono::NaiveDateTime,
   pub deleted_at: Option<chrono::NaiveDateTime>,
+  pub parent_id: Option<i64>,
+  pub position: i32,
 }
 
 pub fn add_category(
   conn: &mut PgConnection,
   _name: String,
   _avatar: String,
+  _parent_id: Option<i64>,
+  _position: i32,
 ) -> Result<Category, DieselError> {
   let new_category = InsertCategory {
     name: _name,
     avatar: _avatar,
+    parent_id: _parent_id,
+    position: _position,
   };
   let resp = diesel::insert_into(category)
     .values(&new_category)
@@ -56,13 +64,97 @@ pub fn get_categories(conn: &mut PgConnection) -> Result<Vec<Category>, DieselEr
   }
 }
 
+// the children of the deleted category move up to its parent
 pub fn delete_category(conn: &mut PgConnection, _name: String) -> Result<(), DieselError> {
+  conn.transaction(|conn| {
+    let deleted = diesel::update(category)
+      .filter(name.eq(_name).and(deleted_at.is_null()))
+      .set(deleted_at.eq(chrono::Local::now().naive_local()))
+      .get_results::<Category>(conn)?;
+    if deleted.is_empty() {
+      return Err(DieselError::NotFound);
+    }
+    for deleted_category in deleted {
+      diesel::update(category)
+        .filter(parent_id.eq(deleted_category.id))
+        .set(parent_id.eq(deleted_category.parent_id))
+        .execute(conn)?;
+    }
+    Ok(())
+  })
+}
+
+// deleted categories included
+pub fn get_category_by_id(
+  conn: &mut PgConnection,
+  category_id: i64,
+) -> Result<Category, DieselError> {
+  let result = category
+    .filter(id.eq(category_id))
+    .first::<Category>(conn)?;
+  Ok(result)
+}
+
+pub fn move_category(
+  conn: &mut PgConnection,
+  category_id: i64,
+  _parent_id: Option<i64>,
+  _position: i32,
+) -> Result<Category, DieselError> {
   let result = diesel::update(category)
-    .filter(name.eq(_name))
-    .set(deleted_at.eq(chrono::Local::now().naive_local()))
-    .execute(conn);
-  if result.is_err() || result.unwrap() == 0 {
-    return Err(DieselError::NotFound);
+    .filter(id.eq(category_id).and(deleted_at.is_null()))
+    .set((
+      parent_id.eq(_parent_id),
+      position.eq(_position),
+      updated_at.eq(chrono::Local::now().naive_local()),
+    ))
+    .get_result::<Category>(conn)?;
+  Ok(result)
+}
+
+// the category followed by every live category under it
+pub fn get_descendant_ids(
+  conn: &mut PgConnection,
+  category_id: i64,
+) -> Result<Vec<i64>, DieselError> {
+  let links = category
+    .filter(deleted_at.is_null())
+    .select((id, parent_id))
+    .load::<(i64, Option<i64>)>(conn)?;
+  let mut ids = vec![category_id];
+  let mut next = 0;
+  while next < ids.len() {
+    let current = ids[next];
+    for (child_id, _) in links.iter().filter(|(_, parent)| *parent == Some(current)) {
+      if !ids.contains(child_id) {
+        ids.push(*child_id);
+      }
+    }
+    next += 1;
+  }
+  Ok(ids)
+}
+
+// the category followed by its parents up to the root
+pub fn get_ancestor_ids(
+  conn: &mut PgConnection,
+  category_id: i64,
+) -> Result<Vec<i64>, DieselError> {
+  let links = category
+    .select((id, parent_id))
+    .load::<(i64, Option<i64>)>(conn)?;
+  let mut ids = vec![category_id];
+  let mut current = category_id;
+  while let Some(Some(parent)) = links
+    .iter()
+    .find(|(child_id, _)| *child_id == current)
+    .map(|(_, parent)| *parent)
+  {
+    if ids.contains(&parent) {
+      break;
+    }
+    ids.push(parent);
+    current = parent;
   }
-  Ok(())
+  Ok(ids)
 }
@@ -0,0 +1,77 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::new_naive_date;
+use crate::schema::category_attribute as category_attribute_table;
+use crate::schema::category_attribute::dsl::*;
+
+pub const NUMBER_ATTRIBUTE_TYPE: &str = "Number";
+pub const TEXT_ATTRIBUTE_TYPE: &str = "Text";
+
+#[derive(Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = category_attribute_table)]
+pub struct InsertCategoryAttribute {
+  pub category_id: i64,
+  pub name: String,
+  pub attribute_type: String,
+  pub unit: Option<String>,
+  pub allowed_values: Vec<String>,
+  pub required: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, Queryable)]
+pub struct CategoryAttribute {
+  pub id: i64,
+  pub category_id: i64,
+  pub name: String,
+  pub attribute_type: String,
+  pub unit: Option<String>,
+  pub allowed_values: Vec<String>,
+  pub required: bool,
+  pub created_at: NaiveDateTime,
+  pub updated_at: NaiveDateTime,
+  pub deleted_at: Option<NaiveDateTime>,
+}
+
+pub fn add_category_attribute(
+  conn: &mut PgConnection,
+  new_attribute: &InsertCategoryAttribute,
+) -> Result<CategoryAttribute, DieselError> {
+  let resp = diesel::insert_into(category_attribute)
+    .values(new_attribute)
+    .get_result::<CategoryAttribute>(conn)?;
+  Ok(resp)
+}
+
+pub fn get_category_attributes(
+  conn: &mut PgConnection,
+  _category_id: i64,
+) -> Result<Vec<CategoryAttribute>, DieselError> {
+  let result = category_attribute
+    .filter(category_id.eq(_category_id).and(deleted_at.is_null()))
+    .order(id.asc())
+    .load::<CategoryAttribute>(conn)?;
+  Ok(result)
+}
+
+// values already stored for a deleted attribute stay on the items
+pub fn delete_category_attribute(
+  conn: &mut PgConnection,
+  _category_id: i64,
+  attribute_id: i64,
+) -> Result<(), DieselError> {
+  let result = diesel::update(category_attribute)
+    .filter(
+      id.eq(attribute_id)
+        .and(category_id.eq(_category_id))
+        .and(deleted_at.is_null()),
+    )
+    .set(deleted_at.eq(new_naive_date()))
+    .execute(conn)?;
+  if result == 0 {
+    return Err(DieselError::NotFound);
+  }
+  Ok(())
+}
@@ -0,0 +1,83 @@
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::new_naive_date;
+use crate::schema::device_token as device_token_table;
+use crate::schema::device_token::dsl::*;
+
+#[derive(Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = device_token_table)]
+pub struct InsertDeviceToken {
+  pub user_id: i64,
+  pub token: String,
+  pub platform: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Queryable)]
+pub struct DeviceToken {
+  pub id: i64,
+  pub user_id: i64,
+  pub token: String,
+  pub platform: String,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+  pub deleted_at: Option<chrono::NaiveDateTime>,
+}
+
+// a device belongs to the last user who signed in on it
+pub fn upsert_device_token(
+  conn: &mut PgConnection,
+  _user_id: i64,
+  _token: String,
+  _platform: String,
+) -> Result<DeviceToken, DieselError> {
+  let new_token = InsertDeviceToken {
+    user_id: _user_id,
+    token: _token,
+    platform: _platform,
+  };
+  let no_deleted_at: Option<chrono::NaiveDateTime> = None;
+  let resp = diesel::insert_into(device_token)
+    .values(&new_token)
+    .on_conflict(token)
+    .do_update()
+    .set((
+      user_id.eq(new_token.user_id),
+      platform.eq(new_token.platform.clone()),
+      deleted_at.eq(no_deleted_at),
+      updated_at.eq(new_naive_date()),
+    ))
+    .get_result::<DeviceToken>(conn)?;
+  Ok(resp)
+}
+
+pub fn delete_device_token(
+  conn: &mut PgConnection,
+  _user_id: i64,
+  _token: &str,
+) -> Result<(), DieselError> {
+  let result = diesel::update(device_token)
+    .filter(
+      user_id
+        .eq(_user_id)
+        .and(token.eq(_token))
+        .and(deleted_at.is_null()),
+    )
+    .set(deleted_at.eq(new_naive_date()))
+    .execute(conn);
+  if result.is_err() || result.unwrap() == 0 {
+    return Err(DieselError::NotFound);
+  }
+  Ok(())
+}
+
+pub fn get_device_tokens_by_user_id(
+  conn: &mut PgConnection,
+  _user_id: i64,
+) -> Result<Vec<DeviceToken>, DieselError> {
+  let result = device_token
+    .filter(user_id.eq(_user_id).and(deleted_at.is_null()))
+    .load::<DeviceToken>(conn)?;
+  Ok(result)
+}
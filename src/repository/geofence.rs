@@ -2,9 +2,17 @@ use diesel::prelude::*;
 use diesel::result::Error as DieselError;
 use serde::{Deserialize, Serialize};
 
+use crate::geo::BoundingBox;
+use crate::helpers::new_naive_date;
 use crate::schema::geofence as geofence_table;
 use crate::schema::geofence::dsl::*;
 
+// regions form a tree of Global > Country > City, the root has parent region 0
+pub const GLOBAL_GEOFENCE_TYPE: &str = "Global";
+pub const COUNTRY_GEOFENCE_TYPE: &str = "Country";
+pub const CITY_GEOFENCE_TYPE: &str = "City";
+pub const NO_PARENT_REGION_ID: i64 = 0;
+
 #[derive(Clone, Serialize, Deserialize, Insertable)]
 #[diesel(table_name = geofence_table)]
 pub struct InsertGeofence {
@@ -22,6 +30,11 @@ pub struct Geofence {
   pub created_at: chrono::NaiveDateTime,
   pub updated_at: chrono::NaiveDateTime,
   pub deleted_at: Option<chrono::NaiveDateTime>,
+  pub boundary: Option<String>,
+  pub min_latitude: Option<f64>,
+  pub min_longitude: Option<f64>,
+  pub max_latitude: Option<f64>,
+  pub max_longitude: Option<f64>,
 }
 
 pub fn add_geofence(
@@ -41,6 +54,17 @@ pub fn add_geofence(
   return Ok(resp);
 }
 
+// deleted geofences included
+pub fn get_geofence_by_id(
+  conn: &mut PgConnection,
+  geofence_id: i64,
+) -> Result<Geofence, DieselError> {
+  let result = geofence
+    .filter(id.eq(geofence_id))
+    .first::<Geofence>(conn)?;
+  Ok(result)
+}
+
 pub fn get_geofences(conn: &mut PgConnection) -> Result<Vec<Geofence>, DieselError> {
   let result = geofence.load(conn).optional()?;
   match result {
@@ -48,3 +72,121 @@ pub fn get_geofences(conn: &mut PgConnection) -> Result<Vec<Geofence>, DieselErr
     None => Ok(vec![]),
   }
 }
+
+pub fn get_live_children(
+  conn: &mut PgConnection,
+  geofence_id: i64,
+) -> Result<Vec<Geofence>, DieselError> {
+  let result = geofence
+    .filter(parent_region_id.eq(geofence_id).and(deleted_at.is_null()))
+    .order(id.asc())
+    .load::<Geofence>(conn)?;
+  Ok(result)
+}
+
+// The region, its parents up to the root and its live subregions. Retired parents
+// are kept so items of a retired region still match.
+pub fn get_region_scope_ids(
+  conn: &mut PgConnection,
+  geofence_id: i64,
+) -> Result<Vec<i64>, DieselError> {
+  let links = geofence
+    .select((id, parent_region_id, deleted_at.is_null()))
+    .load::<(i64, i64, bool)>(conn)?;
+  let mut ids = vec![geofence_id];
+  let mut current = geofence_id;
+  while let Some(parent) = links
+    .iter()
+    .find(|(child_id, _, _)| *child_id == current)
+    .map(|(_, parent, _)| *parent)
+  {
+    if parent == NO_PARENT_REGION_ID || ids.contains(&parent) {
+      break;
+    }
+    ids.push(parent);
+    current = parent;
+  }
+  let mut children = vec![geofence_id];
+  let mut next = 0;
+  while next < children.len() {
+    let current = children[next];
+    for (child_id, _, _) in links
+      .iter()
+      .filter(|(_, parent, is_live)| *parent == current && *is_live)
+    {
+      if !children.contains(child_id) && !ids.contains(child_id) {
+        children.push(*child_id);
+      }
+    }
+    next += 1;
+  }
+  ids.extend(children.into_iter().skip(1));
+  Ok(ids)
+}
+
+pub fn move_geofence(
+  conn: &mut PgConnection,
+  geofence_id: i64,
+  _parent_region_id: i64,
+) -> Result<Geofence, DieselError> {
+  let result = diesel::update(geofence)
+    .filter(id.eq(geofence_id).and(deleted_at.is_null()))
+    .set((
+      parent_region_id.eq(_parent_region_id),
+      updated_at.eq(new_naive_date()),
+    ))
+    .get_result::<Geofence>(conn)?;
+  Ok(result)
+}
+
+// items and saved searches keep pointing at a retired region
+pub fn retire_geofence(conn: &mut PgConnection, geofence_id: i64) -> Result<(), DieselError> {
+  let result = diesel::update(geofence)
+    .filter(id.eq(geofence_id).and(deleted_at.is_null()))
+    .set(deleted_at.eq(new_naive_date()))
+    .execute(conn)?;
+  if result == 0 {
+    return Err(DieselError::NotFound);
+  }
+  Ok(())
+}
+
+pub fn set_geofence_boundary(
+  conn: &mut PgConnection,
+  geofence_id: i64,
+  _boundary: String,
+  bounding_box: BoundingBox,
+) -> Result<Geofence, DieselError> {
+  let result = diesel::update(geofence)
+    .filter(id.eq(geofence_id).and(deleted_at.is_null()))
+    .set((
+      boundary.eq(Some(_boundary)),
+      min_latitude.eq(Some(bounding_box.min_latitude)),
+      min_longitude.eq(Some(bounding_box.min_longitude)),
+      max_latitude.eq(Some(bounding_box.max_latitude)),
+      max_longitude.eq(Some(bounding_box.max_longitude)),
+      updated_at.eq(new_naive_date()),
+    ))
+    .get_result::<Geofence>(conn)?;
+  Ok(result)
+}
+
+// live regions with a boundary whose bounding box holds the point
+pub fn get_geofences_around(
+  conn: &mut PgConnection,
+  latitude: f64,
+  longitude: f64,
+) -> Result<Vec<Geofence>, DieselError> {
+  let result = geofence
+    .filter(
+      deleted_at
+        .is_null()
+        .and(boundary.is_not_null())
+        .and(min_latitude.le(latitude))
+        .and(max_latitude.ge(latitude))
+        .and(min_longitude.le(longitude))
+        .and(max_longitude.ge(longitude)),
+    )
+    .load::<Geofence>(conn)?;
+  Ok(result)
+}
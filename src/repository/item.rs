@@ -3,8 +3,12 @@ use diesel::prelude::*;
 use diesel::result::Error as DieselError;
 use serde::{Deserialize, Serialize};
 
+use crate::geo::BoundingBox;
+use crate::helpers::new_naive_date;
+use crate::repository::category::get_descendant_ids;
 use crate::schema::item as item_table;
 use crate::schema::item::dsl::*;
+use crate::schema::item_attribute;
 use crate::schema::purchase as purchase_table;
 use crate::schema::purchase::dsl::{
   buyer_id as purchase_buyer_id, item_id as purchase_item_id, purchase,
@@ -14,6 +18,10 @@ use crate::schema::user_favorite::dsl::{
 };
 use crate::schema::user_favorite::is_favorite;
 
+pub const DRAFT_ITEM_STATUS: &str = "Draft";
+pub const ACTIVE_ITEM_STATUS: &str = "Active";
+pub const EXPIRED_ITEM_STATUS: &str = "Expired";
+
 #[derive(Clone, Serialize, Deserialize, Insertable)]
 #[diesel(table_name = item_table)]
 pub struct InsertItem {
@@ -27,6 +35,25 @@ pub struct InsertItem {
   pub weight: f64,
   pub category_id: i64,
   pub geofence_id: i64,
+  pub latitude: Option<f64>,
+  pub longitude: Option<f64>,
+}
+
+// fields left as None are not changed
+#[derive(Clone, Default, Serialize, Deserialize, AsChangeset)]
+#[diesel(table_name = item_table)]
+pub struct UpdateItem {
+  pub title: Option<String>,
+  pub description: Option<String>,
+  pub price: Option<i64>,
+  pub negotiable: Option<bool>,
+  pub size: Option<f64>,
+  pub weight: Option<f64>,
+  pub karat_id: Option<i64>,
+  pub category_id: Option<i64>,
+  pub geofence_id: Option<i64>,
+  pub latitude: Option<f64>,
+  pub longitude: Option<f64>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Queryable)]
@@ -50,6 +77,35 @@ pub struct Item {
   pub created_at: NaiveDateTime,
   pub updated_at: NaiveDateTime,
   pub deleted_at: Option<NaiveDateTime>,
+  pub hidden_by_moderator: bool,
+  pub bumped_at: NaiveDateTime,
+  pub expires_at: NaiveDateTime,
+  pub expiry_notified_at: Option<NaiveDateTime>,
+  pub latitude: Option<f64>,
+  pub longitude: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ItemFilter {
+  pub category_id: Option<i64>,
+  pub karat_id: Option<i64>,
+  pub geofence_id: Option<i64>,
+  pub min_price: Option<i64>,
+  pub max_price: Option<i64>,
+  pub attributes: Vec<AttributeFilter>,
+  // items without a location are left out when set
+  pub area: Option<BoundingBox>,
+  // any of the regions, on top of `geofence_id`
+  pub geofence_ids: Option<Vec<i64>>,
+}
+
+// an exact value, or a range for number attributes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AttributeFilter {
+  pub attribute_id: i64,
+  pub value: Option<String>,
+  pub min: Option<f64>,
+  pub max: Option<f64>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Queryable)]
@@ -81,6 +137,7 @@ pub fn insert_new_item(
   _karat_id: i64,
   _category_id: i64,
   _geofence_id: i64,
+  _location: Option<(f64, f64)>,
 ) -> Result<Item, DieselError> {
   let new_item = InsertItem {
     owner_id: _owner_id,
@@ -93,6 +150,8 @@ pub fn insert_new_item(
     karat_id: _karat_id,
     category_id: _category_id,
     geofence_id: _geofence_id,
+    latitude: _location.map(|(lat, _)| lat),
+    longitude: _location.map(|(_, lon)| lon),
   };
 
   let resp = diesel::insert_into(item)
@@ -128,6 +187,43 @@ pub fn update_item_status(
   Ok(())
 }
 
+pub fn update_item(
+  conn: &mut PgConnection,
+  item_id: i64,
+  user_id: i64,
+  changes: &UpdateItem,
+) -> Result<Item, DieselError> {
+  let result = diesel::update(item)
+    .filter(
+      id.eq(item_id)
+        .and(owner_id.eq(user_id))
+        .and(deleted_at.is_null()),
+    )
+    .set((changes, updated_at.eq(new_naive_date())))
+    .get_result::<Item>(conn)
+    .optional()?;
+  match result {
+    Some(val) => Ok(val),
+    None => Err(DieselError::NotFound),
+  }
+}
+
+// locks the row until the end of the transaction so concurrent edits are serialized
+pub fn get_item_by_id_for_update(
+  conn: &mut PgConnection,
+  item_id: i64,
+) -> Result<Item, DieselError> {
+  let result = item
+    .filter(id.eq(item_id).and(deleted_at.is_null()))
+    .for_update()
+    .first(conn)
+    .optional()?;
+  match result {
+    Some(val) => Ok(val),
+    None => Err(DieselError::NotFound),
+  }
+}
+
 pub fn get_item_by_id(conn: &mut PgConnection, item_id: i64) -> Result<Item, DieselError> {
   let result = item
     .filter(id.eq(item_id).and(deleted_at.is_null()))
@@ -139,11 +235,192 @@ pub fn get_item_by_id(conn: &mut PgConnection, item_id: i64) -> Result<Item, Die
   }
 }
 
-pub fn get_all_visible(conn: &mut PgConnection) -> Result<Vec<Item>, DieselError> {
+pub fn get_items_by_ids(
+  conn: &mut PgConnection,
+  item_ids: &[i64],
+) -> Result<Vec<Item>, DieselError> {
   let result = item
-    .filter(deleted_at.is_null().and(is_hideen.eq(false)))
-    .order(created_at.desc())
-    .load(conn)
+    .filter(id.eq_any(item_ids).and(deleted_at.is_null()))
+    .load::<Item>(conn)?;
+  Ok(result)
+}
+
+pub fn get_all_visible(
+  conn: &mut PgConnection,
+  filter: &ItemFilter,
+) -> Result<Vec<Item>, DieselError> {
+  let mut query = item
+    .filter(
+      deleted_at
+        .is_null()
+        .and(is_hideen.eq(false))
+        .and(item_status.ne(DRAFT_ITEM_STATUS))
+        .and(expires_at.gt(new_naive_date())),
+    )
+    .into_boxed();
+  // a category also matches the items of its subcategories
+  if let Some(value) = filter.category_id {
+    query = query.filter(category_id.eq_any(get_descendant_ids(conn, value)?));
+  }
+  if let Some(value) = filter.karat_id {
+    query = query.filter(karat_id.eq(value));
+  }
+  if let Some(value) = filter.geofence_id {
+    query = query.filter(geofence_id.eq(value));
+  }
+  if let Some(value) = filter.min_price {
+    query = query.filter(price.ge(value));
+  }
+  if let Some(value) = filter.max_price {
+    query = query.filter(price.le(value));
+  }
+  if let Some(ref values) = filter.geofence_ids {
+    query = query.filter(geofence_id.eq_any(values.clone()));
+  }
+  if let Some(area) = filter.area {
+    query = query.filter(
+      latitude
+        .between(area.min_latitude, area.max_latitude)
+        .and(longitude.between(area.min_longitude, area.max_longitude)),
+    );
+  }
+  for attribute in filter.attributes.iter() {
+    let mut matching = item_attribute::table
+      .filter(item_attribute::attribute_id.eq(attribute.attribute_id))
+      .select(item_attribute::item_id)
+      .into_boxed();
+    if let Some(ref value) = attribute.value {
+      matching = matching.filter(item_attribute::value.eq(value.clone()));
+    }
+    if let Some(value) = attribute.min {
+      matching = matching.filter(item_attribute::number_value.ge(value));
+    }
+    if let Some(value) = attribute.max {
+      matching = matching.filter(item_attribute::number_value.le(value));
+    }
+    query = query.filter(id.eq_any(matching));
+  }
+  // bumped items move back to the top of the feed
+  let result = query.order(bumped_at.desc()).load(conn).optional()?;
+  match result {
+    Some(val) => Ok(val),
+    None => Err(DieselError::NotFound),
+  }
+}
+
+// active items expiring before `deadline` whose owner was not told yet
+pub fn get_items_expiring_before(
+  conn: &mut PgConnection,
+  deadline: NaiveDateTime,
+) -> Result<Vec<Item>, DieselError> {
+  let result = item
+    .filter(
+      deleted_at
+        .is_null()
+        .and(item_status.eq(ACTIVE_ITEM_STATUS))
+        .and(expiry_notified_at.is_null())
+        .and(expires_at.le(deadline))
+        .and(expires_at.gt(new_naive_date())),
+    )
+    .load::<Item>(conn)?;
+  Ok(result)
+}
+
+pub fn set_expiry_notified(conn: &mut PgConnection, item_id: i64) -> Result<(), DieselError> {
+  let result = diesel::update(item)
+    .filter(id.eq(item_id))
+    .set(expiry_notified_at.eq(new_naive_date()))
+    .execute(conn);
+  if result.is_err() || result.unwrap() == 0 {
+    return Err(DieselError::NotFound);
+  }
+  Ok(())
+}
+
+// marks every active item past its expiry time as expired and returns them
+pub fn expire_items(conn: &mut PgConnection) -> Result<Vec<Item>, DieselError> {
+  let now = new_naive_date();
+  let result = diesel::update(item)
+    .filter(
+      deleted_at
+        .is_null()
+        .and(item_status.eq(ACTIVE_ITEM_STATUS))
+        .and(expires_at.le(now)),
+    )
+    .set((item_status.eq(EXPIRED_ITEM_STATUS), updated_at.eq(now)))
+    .get_results::<Item>(conn)?;
+  Ok(result)
+}
+
+// renewing reposts the item, so it is also moved to the top of the feed
+pub fn renew_item(
+  conn: &mut PgConnection,
+  item_id: i64,
+  user_id: i64,
+  new_expires_at: NaiveDateTime,
+) -> Result<Item, DieselError> {
+  let now = new_naive_date();
+  let no_expiry_notified_at: Option<NaiveDateTime> = None;
+  let result = diesel::update(item)
+    .filter(
+      id.eq(item_id)
+        .and(owner_id.eq(user_id))
+        .and(deleted_at.is_null())
+        .and(item_status.eq_any([ACTIVE_ITEM_STATUS, EXPIRED_ITEM_STATUS])),
+    )
+    .set((
+      item_status.eq(ACTIVE_ITEM_STATUS),
+      expires_at.eq(new_expires_at),
+      expiry_notified_at.eq(no_expiry_notified_at),
+      bumped_at.eq(now),
+      updated_at.eq(now),
+    ))
+    .get_result::<Item>(conn)
+    .optional()?;
+  match result {
+    Some(val) => Ok(val),
+    None => Err(DieselError::NotFound),
+  }
+}
+
+// publishing starts the listing lifetime and puts the item at the top of the feed
+pub fn publish_item(
+  conn: &mut PgConnection,
+  item_id: i64,
+  user_id: i64,
+  new_expires_at: NaiveDateTime,
+) -> Result<Item, DieselError> {
+  let now = new_naive_date();
+  let result = diesel::update(item)
+    .filter(
+      id.eq(item_id)
+        .and(owner_id.eq(user_id))
+        .and(deleted_at.is_null())
+        .and(item_status.eq(DRAFT_ITEM_STATUS)),
+    )
+    .set((
+      item_status.eq(ACTIVE_ITEM_STATUS),
+      expires_at.eq(new_expires_at),
+      bumped_at.eq(now),
+      updated_at.eq(now),
+    ))
+    .get_result::<Item>(conn)
+    .optional()?;
+  match result {
+    Some(val) => Ok(val),
+    None => Err(DieselError::NotFound),
+  }
+}
+
+pub fn bump_item(conn: &mut PgConnection, item_id: i64, user_id: i64) -> Result<Item, DieselError> {
+  let result = diesel::update(item)
+    .filter(
+      id.eq(item_id)
+        .and(owner_id.eq(user_id))
+        .and(deleted_at.is_null()),
+    )
+    .set(bumped_at.eq(new_naive_date()))
+    .get_result::<Item>(conn)
     .optional()?;
   match result {
     Some(val) => Ok(val),
@@ -162,6 +439,17 @@ pub fn increment_message_count(conn: &mut PgConnection, item_id: i64) -> Result<
   Ok(())
 }
 
+pub fn increment_seen_count(conn: &mut PgConnection, item_id: i64) -> Result<(), DieselError> {
+  let result = diesel::update(item)
+    .filter(deleted_at.is_null().and(id.eq(item_id)))
+    .set(seen_count.eq(seen_count + 1))
+    .execute(conn);
+  if result.is_err() || result.unwrap() == 0 {
+    return Err(DieselError::NotFound);
+  }
+  Ok(())
+}
+
 pub fn update_favorite_count(
   conn: &mut PgConnection,
   item_id: i64,
@@ -192,6 +480,18 @@ pub fn hide_unhide_item(
   Ok(())
 }
 
+// the owner can not unhide an item hidden by a moderator
+pub fn hide_item_by_moderator(conn: &mut PgConnection, item_id: i64) -> Result<(), DieselError> {
+  let result = diesel::update(item)
+    .filter(deleted_at.is_null().and(id.eq(item_id)))
+    .set((is_hideen.eq(true), hidden_by_moderator.eq(true)))
+    .execute(conn);
+  if result.is_err() || result.unwrap() == 0 {
+    return Err(DieselError::NotFound);
+  }
+  Ok(())
+}
+
 pub fn get_favorite_items(
   conn: &mut PgConnection,
   _user_id: i64,
@@ -0,0 +1,78 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::{Deserialize, Serialize};
+
+use super::category_attribute::CategoryAttribute;
+use crate::schema::category_attribute;
+use crate::schema::item_attribute as item_attribute_table;
+use crate::schema::item_attribute::dsl::*;
+
+#[derive(Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = item_attribute_table)]
+pub struct InsertItemAttribute {
+  pub item_id: i64,
+  pub attribute_id: i64,
+  pub value: String,
+  pub number_value: Option<f64>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Queryable)]
+pub struct ItemAttribute {
+  pub id: i64,
+  pub item_id: i64,
+  pub attribute_id: i64,
+  pub value: String,
+  pub number_value: Option<f64>,
+  pub created_at: NaiveDateTime,
+}
+
+// a validated value of an attribute, not yet stored for an item
+#[derive(Debug, Clone)]
+pub struct AttributeValue {
+  pub attribute_id: i64,
+  pub value: String,
+  pub number_value: Option<f64>,
+}
+
+// replaces every attribute value of the item
+pub fn set_item_attributes(
+  conn: &mut PgConnection,
+  _item_id: i64,
+  values: &[AttributeValue],
+) -> Result<Vec<ItemAttribute>, DieselError> {
+  delete_item_attributes(conn, _item_id)?;
+  let new_attributes: Vec<InsertItemAttribute> = values
+    .iter()
+    .map(|attribute| InsertItemAttribute {
+      item_id: _item_id,
+      attribute_id: attribute.attribute_id,
+      value: attribute.value.clone(),
+      number_value: attribute.number_value,
+    })
+    .collect();
+  let resp = diesel::insert_into(item_attribute)
+    .values(&new_attributes)
+    .get_results::<ItemAttribute>(conn)?;
+  Ok(resp)
+}
+
+pub fn delete_item_attributes(conn: &mut PgConnection, _item_id: i64) -> Result<(), DieselError> {
+  diesel::delete(item_attribute)
+    .filter(item_id.eq(_item_id))
+    .execute(conn)?;
+  Ok(())
+}
+
+// the values of the item together with the attribute they are for
+pub fn get_item_attributes(
+  conn: &mut PgConnection,
+  _item_id: i64,
+) -> Result<Vec<(ItemAttribute, CategoryAttribute)>, DieselError> {
+  let result = item_attribute
+    .inner_join(category_attribute::table)
+    .filter(item_id.eq(_item_id))
+    .order(category_attribute::id.asc())
+    .load::<(ItemAttribute, CategoryAttribute)>(conn)?;
+  Ok(result)
+}
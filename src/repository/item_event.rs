@@ -0,0 +1,87 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::item_event as item_event_table;
+use crate::schema::item_event::dsl::*;
+
+pub const VIEW_EVENT: &str = "View";
+pub const FAVORITE_EVENT: &str = "Favorite";
+pub const ROOM_OPENED_EVENT: &str = "RoomOpened";
+pub const OFFER_EVENT: &str = "Offer";
+pub const SALE_EVENT: &str = "Sale";
+
+#[derive(Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = item_event_table)]
+pub struct InsertItemEvent {
+  pub item_id: i64,
+  pub user_id: i64,
+  pub event_type: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Queryable)]
+pub struct ItemEvent {
+  pub id: i64,
+  pub item_id: i64,
+  // user who triggered the event
+  pub user_id: i64,
+  pub event_type: String,
+  pub created_at: NaiveDateTime,
+}
+
+pub fn add_item_event(
+  conn: &mut PgConnection,
+  _item_id: i64,
+  _user_id: i64,
+  _event_type: &str,
+) -> Result<ItemEvent, DieselError> {
+  let new_event = InsertItemEvent {
+    item_id: _item_id,
+    user_id: _user_id,
+    event_type: _event_type.to_owned(),
+  };
+  let resp = diesel::insert_into(item_event)
+    .values(&new_event)
+    .get_result::<ItemEvent>(conn)?;
+  Ok(resp)
+}
+
+pub fn get_events_for_items(
+  conn: &mut PgConnection,
+  item_ids: &[i64],
+  from: NaiveDateTime,
+  to: NaiveDateTime,
+) -> Result<Vec<ItemEvent>, DieselError> {
+  let result = item_event
+    .filter(
+      item_id
+        .eq_any(item_ids)
+        .and(created_at.ge(from))
+        .and(created_at.lt(to)),
+    )
+    .order(created_at.asc())
+    .load::<ItemEvent>(conn)?;
+  Ok(result)
+}
+
+// most recently viewed first, an item viewed several times is returned once
+pub fn get_viewed_item_ids(
+  conn: &mut PgConnection,
+  _user_id: i64,
+  limit: i64,
+) -> Result<Vec<i64>, DieselError> {
+  let result = item_event
+    .select(item_id)
+    .filter(user_id.eq(_user_id).and(event_type.eq(VIEW_EVENT)))
+    .order(id.desc())
+    .limit(limit)
+    .load::<i64>(conn)?;
+  let mut item_ids: Vec<i64> = Vec::with_capacity(result.len());
+  for value in result {
+    if !item_ids.contains(&value) {
+      item_ids.push(value);
+    }
+  }
+  Ok(item_ids)
+}
@@ -14,6 +14,8 @@ pub struct InsertImage {
   pub user_id: i64,
   pub uploaded_to_cloud: bool,
   pub is_cover: bool,
+  pub position: i32,
+  pub pending_cover: bool,
 }
 
 #[derive(Clone, Serialize, Deserialize, Queryable)]
@@ -28,6 +30,29 @@ pub struct ItemImage {
   pub created_at: NaiveDateTime,
   pub updated_at: NaiveDateTime,
   pub deleted_at: Option<NaiveDateTime>,
+  pub thumbnail_key: Option<String>,
+  pub medium_key: Option<String>,
+  pub processed_at: Option<NaiveDateTime>,
+  // the order of the images of an item, starting from 0
+  pub position: i32,
+  // asked to be the cover, it replaces the current one once uploaded
+  pub pending_cover: bool,
+}
+
+// Nothing of an image is served before processing removed the metadata of the
+// upload, images that fail to be processed are removed.
+impl ItemImage {
+  pub fn thumbnail(&self) -> Option<&str> {
+    self.thumbnail_key.as_deref()
+  }
+
+  pub fn medium(&self) -> Option<&str> {
+    self.medium_key.as_deref()
+  }
+
+  pub fn original(&self) -> Option<&str> {
+    self.processed_at.map(|_| self.key.as_str())
+  }
 }
 
 pub fn insert_new_image(
@@ -36,14 +61,17 @@ pub fn insert_new_image(
   iid: i64,
   object_name: String,
   uploaded: bool,
-  _is_cover: bool,
+  _pending_cover: bool,
+  _position: i32,
 ) -> Result<ItemImage, DieselError> {
   let new_image = InsertImage {
     key: object_name,
     item_id: iid,
     user_id: uid,
     uploaded_to_cloud: uploaded,
-    is_cover: _is_cover,
+    is_cover: false,
+    position: _position,
+    pending_cover: _pending_cover,
   };
 
   let resp = diesel::insert_into(item_image)
@@ -52,6 +80,26 @@ pub fn insert_new_image(
   return Ok(resp);
 }
 
+pub fn get_image_by_id(
+  conn: &mut PgConnection,
+  item_image_id: i64,
+) -> Result<ItemImage, DieselError> {
+  let result = item_image
+    .filter(id.eq(item_image_id).and(deleted_at.is_null()))
+    .first::<ItemImage>(conn)?;
+  Ok(result)
+}
+
+pub fn get_image_by_key(
+  conn: &mut PgConnection,
+  object_name: &str,
+) -> Result<ItemImage, DieselError> {
+  let result = item_image
+    .filter(key.eq(object_name).and(deleted_at.is_null()))
+    .first::<ItemImage>(conn)?;
+  Ok(result)
+}
+
 pub fn set_to_uploaded_to_cloud(
   conn: &mut PgConnection,
   item_image_id: i64,
@@ -66,9 +114,65 @@ pub fn set_to_uploaded_to_cloud(
   Ok(())
 }
 
+// The upload is confirmed in the same transaction the image becomes the cover, if
+// it was asked to be or the item has no cover yet.
+pub fn confirm_image_upload(
+  conn: &mut PgConnection,
+  item_image_id: i64,
+) -> Result<(), DieselError> {
+  conn.transaction::<_, DieselError, _>(|conn| {
+    set_to_uploaded_to_cloud(conn, item_image_id)?;
+    let image = get_image_by_id(conn, item_image_id)?;
+    let has_cover = match get_cover_pic_for_item(conn, image.item_id) {
+      Ok(_) => true,
+      Err(DieselError::NotFound) => false,
+      Err(e) => return Err(e),
+    };
+    if image.pending_cover || !has_cover {
+      set_cover_image(conn, image.item_id, image.id)?;
+    }
+    Ok(())
+  })
+}
+
+pub fn get_unprocessed_images(
+  conn: &mut PgConnection,
+  limit: i64,
+) -> Result<Vec<ItemImage>, DieselError> {
+  let result = item_image
+    .filter(
+      uploaded_to_cloud
+        .eq(true)
+        .and(processed_at.is_null())
+        .and(deleted_at.is_null()),
+    )
+    .order(created_at.asc())
+    .limit(limit)
+    .load::<ItemImage>(conn)?;
+  Ok(result)
+}
+
+pub fn set_image_variants(
+  conn: &mut PgConnection,
+  item_image_id: i64,
+  _thumbnail_key: String,
+  _medium_key: String,
+) -> Result<(), DieselError> {
+  diesel::update(item_image)
+    .filter(id.eq(item_image_id))
+    .set((
+      thumbnail_key.eq(_thumbnail_key),
+      medium_key.eq(_medium_key),
+      processed_at.eq(diesel::dsl::now),
+    ))
+    .execute(conn)?;
+  Ok(())
+}
+
 pub fn get_docs_for_item(conn: &mut PgConnection, iid: i64) -> Result<Vec<ItemImage>, DieselError> {
   let docs = item_image
     .filter(item_id.eq(iid).and(deleted_at.is_null()))
+    .order((position.asc(), id.asc()))
     .load(conn)
     .optional()?;
   match docs {
@@ -95,3 +199,118 @@ pub fn get_cover_pic_for_item(
     None => Err(DieselError::NotFound),
   }
 }
+
+// the position after the last image of the item
+pub fn get_next_image_position(conn: &mut PgConnection, iid: i64) -> Result<i32, DieselError> {
+  let last = item_image
+    .filter(item_id.eq(iid).and(deleted_at.is_null()))
+    .select(diesel::dsl::max(position))
+    .first::<Option<i32>>(conn)?;
+  Ok(last.map_or(0, |last| last + 1))
+}
+
+pub fn delete_image(conn: &mut PgConnection, item_image_id: i64) -> Result<ItemImage, DieselError> {
+  let result = diesel::update(item_image)
+    .filter(id.eq(item_image_id).and(deleted_at.is_null()))
+    .set(deleted_at.eq(diesel::dsl::now))
+    .get_result::<ItemImage>(conn)?;
+  Ok(result)
+}
+
+// the previous cover is unset first, only one live cover per item is allowed
+pub fn set_cover_image(
+  conn: &mut PgConnection,
+  iid: i64,
+  item_image_id: i64,
+) -> Result<(), DieselError> {
+  diesel::update(item_image)
+    .filter(item_id.eq(iid).and(is_cover.eq(true)))
+    .set(is_cover.eq(false))
+    .execute(conn)?;
+  let updated = diesel::update(item_image)
+    .filter(
+      id.eq(item_image_id)
+        .and(item_id.eq(iid))
+        .and(deleted_at.is_null()),
+    )
+    .set((is_cover.eq(true), pending_cover.eq(false)))
+    .execute(conn)?;
+  if updated == 0 {
+    return Err(DieselError::NotFound);
+  }
+  Ok(())
+}
+
+// the images get the position of their id in `image_ids`
+pub fn set_image_positions(
+  conn: &mut PgConnection,
+  iid: i64,
+  image_ids: &[i64],
+) -> Result<(), DieselError> {
+  for (index, item_image_id) in image_ids.iter().enumerate() {
+    diesel::update(item_image)
+      .filter(
+        id.eq(item_image_id)
+          .and(item_id.eq(iid))
+          .and(deleted_at.is_null()),
+      )
+      .set(position.eq(index as i32))
+      .execute(conn)?;
+  }
+  Ok(())
+}
+
+// images whose upload was never confirmed
+pub fn get_pending_images_created_before(
+  conn: &mut PgConnection,
+  before: NaiveDateTime,
+) -> Result<Vec<ItemImage>, DieselError> {
+  let result = item_image
+    .filter(
+      uploaded_to_cloud
+        .eq(false)
+        .and(created_at.lt(before))
+        .and(deleted_at.is_null()),
+    )
+    .load::<ItemImage>(conn)?;
+  Ok(result)
+}
+
+// every object name used by a live image, its variants included
+pub fn get_live_image_keys(conn: &mut PgConnection) -> Result<Vec<String>, DieselError> {
+  let result = item_image
+    .filter(deleted_at.is_null())
+    .select((key, thumbnail_key, medium_key))
+    .load::<(String, Option<String>, Option<String>)>(conn)?;
+  Ok(
+    result
+      .into_iter()
+      .flat_map(|(object_name, thumbnail, medium)| [Some(object_name), thumbnail, medium])
+      .flatten()
+      .collect(),
+  )
+}
+
+// Soft deletes the image and hands the cover over to the next uploaded image.
+// The object names of the image are returned, removing them is up to the caller.
+pub fn remove_item_image(
+  conn: &mut PgConnection,
+  image: ItemImage,
+) -> Result<Vec<String>, DieselError> {
+  conn.transaction::<_, DieselError, _>(|conn| {
+    delete_image(conn, image.id)?;
+    if image.is_cover {
+      let docs = get_docs_for_item(conn, image.item_id)?;
+      if let Some(next) = docs.iter().find(|doc| doc.uploaded_to_cloud) {
+        set_cover_image(conn, image.item_id, next.id)?;
+      }
+    }
+    Ok(())
+  })?;
+  Ok(
+    [Some(image.key), image.thumbnail_key, image.medium_key]
+      .into_iter()
+      .flatten()
+      .collect(),
+  )
+}
@@ -0,0 +1,53 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::item_price_history as item_price_history_table;
+use crate::schema::item_price_history::dsl::*;
+
+#[derive(Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = item_price_history_table)]
+pub struct InsertItemPriceChange {
+  pub item_id: i64,
+  pub old_price: i64,
+  pub new_price: i64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Queryable)]
+pub struct ItemPriceChange {
+  pub id: i64,
+  pub item_id: i64,
+  pub old_price: i64,
+  pub new_price: i64,
+  pub created_at: NaiveDateTime,
+}
+
+pub fn add_price_change(
+  conn: &mut PgConnection,
+  _item_id: i64,
+  _old_price: i64,
+  _new_price: i64,
+) -> Result<ItemPriceChange, DieselError> {
+  let new_change = InsertItemPriceChange {
+    item_id: _item_id,
+    old_price: _old_price,
+    new_price: _new_price,
+  };
+  let resp = diesel::insert_into(item_price_history)
+    .values(&new_change)
+    .get_result::<ItemPriceChange>(conn)?;
+  Ok(resp)
+}
+
+// oldest first
+pub fn get_price_history(
+  conn: &mut PgConnection,
+  _item_id: i64,
+) -> Result<Vec<ItemPriceChange>, DieselError> {
+  let result = item_price_history
+    .filter(item_id.eq(_item_id))
+    .order(id.asc())
+    .load::<ItemPriceChange>(conn)?;
+  Ok(result)
+}
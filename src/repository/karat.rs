@@ -41,6 +41,12 @@ pub fn add_karat(
   return Ok(resp);
 }
 
+// deleted karats included
+pub fn get_karat_by_id(conn: &mut PgConnection, karat_id: i64) -> Result<Karat, DieselError> {
+  let result = karat.filter(id.eq(karat_id)).first::<Karat>(conn)?;
+  Ok(result)
+}
+
 pub fn get_by_name(conn: &mut PgConnection, _name: String) -> Result<Option<Karat>, DieselError> {
   let result = karat
     .filter(name.eq(_name))
@@ -103,3 +103,26 @@ pub fn get_last_message_by_room_id(
     None => Err(DieselError::NotFound),
   }
 }
+
+pub fn get_message_by_id(conn: &mut PgConnection, message_id: i64) -> Result<Message, DieselError> {
+  let result = message
+    .filter(id.eq(message_id).and(deleted_at.is_null()))
+    .first(conn)
+    .optional()?;
+  match result {
+    Some(val) => Ok(val),
+    None => Err(DieselError::NotFound),
+  }
+}
+
+pub fn delete_message(conn: &mut PgConnection, message_id: i64) -> Result<(), DieselError> {
+  let now = new_naive_date();
+  let result = diesel::update(message)
+    .filter(id.eq(message_id).and(deleted_at.is_null()))
+    .set((deleted_at.eq(now), updated_at.eq(now)))
+    .execute(conn);
+  if result.is_err() || result.unwrap() == 0 {
+    return Err(DieselError::NotFound);
+  }
+  Ok(())
+}
@@ -1,12 +1,23 @@
 pub mod auth;
 pub mod category;
+pub mod category_attribute;
 pub mod db;
+pub mod device_token;
 pub mod geofence;
 pub mod item;
+pub mod item_attribute;
+pub mod item_event;
 pub mod item_image;
+pub mod item_price_history;
 pub mod karat;
 pub mod message;
+pub mod moderation_action;
+pub mod notification;
+pub mod report;
 pub mod room;
 pub mod room_member;
+pub mod saved_search;
+pub mod translation;
 pub mod user;
+pub mod user_block;
 pub mod user_favorite;
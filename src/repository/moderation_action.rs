@@ -0,0 +1,58 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::moderation_action as moderation_action_table;
+use crate::schema::moderation_action::dsl::*;
+
+pub const ASSIGN_ACTION: &str = "Assign";
+pub const HIDE_ITEM_ACTION: &str = "HideItem";
+pub const SUSPEND_USER_ACTION: &str = "SuspendUser";
+pub const DELETE_MESSAGE_ACTION: &str = "DeleteMessage";
+pub const RESOLVE_ACTION: &str = "Resolve";
+pub const DISMISS_ACTION: &str = "Dismiss";
+
+#[derive(Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = moderation_action_table)]
+pub struct InsertModerationAction {
+  pub report_id: Option<i64>,
+  pub moderator_id: i64,
+  pub action: String,
+  pub target_type: String,
+  pub target_id: i64,
+  pub note: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Queryable)]
+pub struct ModerationAction {
+  pub id: i64,
+  pub report_id: Option<i64>,
+  pub moderator_id: i64,
+  pub action: String,
+  pub target_type: String,
+  pub target_id: i64,
+  pub note: String,
+  pub created_at: NaiveDateTime,
+}
+
+pub fn add_moderation_action(
+  conn: &mut PgConnection,
+  new_action: &InsertModerationAction,
+) -> Result<ModerationAction, DieselError> {
+  let resp = diesel::insert_into(moderation_action)
+    .values(new_action)
+    .get_result::<ModerationAction>(conn)?;
+  Ok(resp)
+}
+
+pub fn get_actions_for_report(
+  conn: &mut PgConnection,
+  _report_id: i64,
+) -> Result<Vec<ModerationAction>, DieselError> {
+  let result = moderation_action
+    .filter(report_id.eq(_report_id))
+    .order(id.asc())
+    .load::<ModerationAction>(conn)?;
+  Ok(result)
+}
@@ -0,0 +1,146 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::new_naive_date;
+use crate::schema::notification as notification_table;
+use crate::schema::notification::dsl::*;
+
+pub const SAVED_SEARCH_MATCH_NOTIFICATION: &str = "SavedSearchMatch";
+pub const NEW_MESSAGE_NOTIFICATION: &str = "NewMessage";
+pub const NEW_ROOM_NOTIFICATION: &str = "NewRoom";
+pub const ITEM_FAVORITED_NOTIFICATION: &str = "ItemFavorited";
+pub const ITEM_PURCHASED_NOTIFICATION: &str = "ItemPurchased";
+pub const ITEM_STATUS_CHANGED_NOTIFICATION: &str = "ItemStatusChanged";
+pub const ITEM_PRICE_DROPPED_NOTIFICATION: &str = "ItemPriceDropped";
+pub const ITEM_EXPIRING_NOTIFICATION: &str = "ItemExpiring";
+pub const ITEM_EXPIRED_NOTIFICATION: &str = "ItemExpired";
+
+#[derive(Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = notification_table)]
+pub struct InsertNotification {
+  pub user_id: i64,
+  pub notification_type: String,
+  pub title: String,
+  pub body: String,
+  pub item_id: Option<i64>,
+  pub room_id: Option<i64>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Queryable)]
+pub struct Notification {
+  pub id: i64,
+  // receiver of the notification
+  pub user_id: i64,
+  pub notification_type: String,
+  pub title: String,
+  pub body: String,
+  pub item_id: Option<i64>,
+  pub room_id: Option<i64>,
+  pub is_read: bool,
+  pub created_at: NaiveDateTime,
+  pub read_at: Option<NaiveDateTime>,
+}
+
+pub fn add_notification(
+  conn: &mut PgConnection,
+  new_notification: &InsertNotification,
+) -> Result<Notification, DieselError> {
+  let resp = diesel::insert_into(notification)
+    .values(new_notification)
+    .get_result::<Notification>(conn)?;
+  Ok(resp)
+}
+
+// newest first, `before_id` is the id of the last notification of the previous page
+pub fn get_notifications_by_user_id(
+  conn: &mut PgConnection,
+  _user_id: i64,
+  before_id: Option<i64>,
+  limit: i64,
+) -> Result<Vec<Notification>, DieselError> {
+  let mut query = notification.filter(user_id.eq(_user_id)).into_boxed();
+  if let Some(before_id) = before_id {
+    query = query.filter(id.lt(before_id));
+  }
+  let result = query
+    .order(id.desc())
+    .limit(limit)
+    .load::<Notification>(conn)?;
+  Ok(result)
+}
+
+pub fn count_unread_notifications(
+  conn: &mut PgConnection,
+  _user_id: i64,
+) -> Result<i64, DieselError> {
+  let result = notification
+    .filter(user_id.eq(_user_id).and(is_read.eq(false)))
+    .count()
+    .get_result::<i64>(conn)?;
+  Ok(result)
+}
+
+pub fn get_unread_room_notification(
+  conn: &mut PgConnection,
+  _user_id: i64,
+  _room_id: i64,
+  _notification_type: &str,
+) -> Result<Option<Notification>, DieselError> {
+  let result = notification
+    .filter(
+      user_id
+        .eq(_user_id)
+        .and(room_id.eq(_room_id))
+        .and(notification_type.eq(_notification_type))
+        .and(is_read.eq(false)),
+    )
+    .first::<Notification>(conn)
+    .optional()?;
+  Ok(result)
+}
+
+pub fn mark_notification_as_read(
+  conn: &mut PgConnection,
+  notification_id: i64,
+  _user_id: i64,
+) -> Result<(), DieselError> {
+  let result = diesel::update(notification)
+    .filter(id.eq(notification_id).and(user_id.eq(_user_id)))
+    .set((is_read.eq(true), read_at.eq(new_naive_date())))
+    .execute(conn);
+  if result.is_err() || result.unwrap() == 0 {
+    return Err(DieselError::NotFound);
+  }
+  Ok(())
+}
+
+pub fn mark_all_notifications_as_read(
+  conn: &mut PgConnection,
+  _user_id: i64,
+) -> Result<usize, DieselError> {
+  let result = diesel::update(notification)
+    .filter(user_id.eq(_user_id).and(is_read.eq(false)))
+    .set((is_read.eq(true), read_at.eq(new_naive_date())))
+    .execute(conn)?;
+  Ok(result)
+}
+
+// opening a room reads every notification about it
+pub fn mark_room_notifications_as_read(
+  conn: &mut PgConnection,
+  _user_id: i64,
+  _room_id: i64,
+) -> Result<usize, DieselError> {
+  let result = diesel::update(notification)
+    .filter(
+      user_id
+        .eq(_user_id)
+        .and(room_id.eq(_room_id))
+        .and(is_read.eq(false)),
+    )
+    .set((is_read.eq(true), read_at.eq(new_naive_date())))
+    .execute(conn)?;
+  Ok(result)
+}
@@ -0,0 +1,140 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::new_naive_date;
+use crate::schema::report as report_table;
+use crate::schema::report::dsl::*;
+
+pub const OPEN_REPORT_STATUS: &str = "Open";
+pub const ASSIGNED_REPORT_STATUS: &str = "Assigned";
+pub const RESOLVED_REPORT_STATUS: &str = "Resolved";
+pub const DISMISSED_REPORT_STATUS: &str = "Dismissed";
+
+#[derive(Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = report_table)]
+pub struct InsertReport {
+  pub reporter_id: i64,
+  pub target_type: String,
+  pub target_id: i64,
+  pub reason: String,
+  pub description: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Queryable)]
+pub struct Report {
+  pub id: i64,
+  pub reporter_id: i64,
+  pub target_type: String,
+  pub target_id: i64,
+  pub reason: String,
+  pub description: String,
+  pub report_status: String,
+  pub assignee_id: Option<i64>,
+  pub created_at: NaiveDateTime,
+  pub updated_at: NaiveDateTime,
+  pub resolved_at: Option<NaiveDateTime>,
+}
+
+pub fn add_report(
+  conn: &mut PgConnection,
+  new_report: &InsertReport,
+) -> Result<Report, DieselError> {
+  let resp = diesel::insert_into(report)
+    .values(new_report)
+    .get_result::<Report>(conn)?;
+  Ok(resp)
+}
+
+// a user can have only one unresolved report per target
+pub fn get_unresolved_report(
+  conn: &mut PgConnection,
+  _reporter_id: i64,
+  _target_type: &str,
+  _target_id: i64,
+) -> Result<Option<Report>, DieselError> {
+  let result = report
+    .filter(
+      reporter_id
+        .eq(_reporter_id)
+        .and(target_type.eq(_target_type))
+        .and(target_id.eq(_target_id))
+        .and(report_status.eq_any([OPEN_REPORT_STATUS, ASSIGNED_REPORT_STATUS])),
+    )
+    .first::<Report>(conn)
+    .optional()?;
+  Ok(result)
+}
+
+pub fn get_report_by_id(conn: &mut PgConnection, report_id: i64) -> Result<Report, DieselError> {
+  let result = report
+    .filter(id.eq(report_id))
+    .first::<Report>(conn)
+    .optional()?;
+  match result {
+    Some(val) => Ok(val),
+    None => Err(DieselError::NotFound),
+  }
+}
+
+// oldest first, so the queue is worked in the order reports came in
+pub fn get_reports(
+  conn: &mut PgConnection,
+  statuses: &[&str],
+  _assignee_id: Option<i64>,
+) -> Result<Vec<Report>, DieselError> {
+  let mut query = report.filter(report_status.eq_any(statuses)).into_boxed();
+  if let Some(value) = _assignee_id {
+    query = query.filter(assignee_id.eq(value));
+  }
+  let result = query.order(id.asc()).load::<Report>(conn)?;
+  Ok(result)
+}
+
+pub fn assign_report(
+  conn: &mut PgConnection,
+  report_id: i64,
+  _assignee_id: i64,
+) -> Result<Report, DieselError> {
+  let result = diesel::update(report)
+    .filter(
+      id.eq(report_id)
+        .and(report_status.eq_any([OPEN_REPORT_STATUS, ASSIGNED_REPORT_STATUS])),
+    )
+    .set((
+      assignee_id.eq(_assignee_id),
+      report_status.eq(ASSIGNED_REPORT_STATUS),
+      updated_at.eq(new_naive_date()),
+    ))
+    .get_result::<Report>(conn)
+    .optional()?;
+  match result {
+    Some(val) => Ok(val),
+    None => Err(DieselError::NotFound),
+  }
+}
+
+pub fn close_report(
+  conn: &mut PgConnection,
+  report_id: i64,
+  new_status: &str,
+) -> Result<Report, DieselError> {
+  let now = new_naive_date();
+  let result = diesel::update(report)
+    .filter(
+      id.eq(report_id)
+        .and(report_status.eq_any([OPEN_REPORT_STATUS, ASSIGNED_REPORT_STATUS])),
+    )
+    .set((
+      report_status.eq(new_status),
+      updated_at.eq(now),
+      resolved_at.eq(now),
+    ))
+    .get_result::<Report>(conn)
+    .optional()?;
+  match result {
+    Some(val) => Ok(val),
+    None => Err(DieselError::NotFound),
+  }
+}
@@ -33,6 +33,7 @@ pub struct RoomMember {
   pub created_at: chrono::NaiveDateTime,
   pub last_joined_at: chrono::NaiveDateTime,
   pub deleted_at: Option<chrono::NaiveDateTime>,
+  pub is_muted: bool,
 }
 
 #[derive(Clone, Serialize, Deserialize, Queryable)]
@@ -96,6 +97,27 @@ pub fn set_last_joined_at(
   Ok(resp)
 }
 
+pub fn set_muted(
+  conn: &mut PgConnection,
+  mid: &i64,
+  rid: &i64,
+  muted: bool,
+) -> Result<(), DieselError> {
+  let result = diesel::update(room_member)
+    .filter(
+      member_id
+        .eq(mid)
+        .and(room_id.eq(rid))
+        .and(deleted_at.is_null()),
+    )
+    .set(is_muted.eq(muted))
+    .execute(conn);
+  if result.is_err() || result.unwrap() == 0 {
+    return Err(DieselError::NotFound);
+  }
+  Ok(())
+}
+
 pub fn get_room_member(
   conn: &mut PgConnection,
   mid: &i64,
@@ -116,6 +138,16 @@ pub fn get_room_member(
   }
 }
 
+pub fn get_room_members(
+  conn: &mut PgConnection,
+  rid: &i64,
+) -> Result<Vec<RoomMember>, DieselError> {
+  let result = room_member
+    .filter(room_id.eq(rid).and(deleted_at.is_null()))
+    .load::<RoomMember>(conn)?;
+  Ok(result)
+}
+
 pub fn get_all_buyers_for_item(
   conn: &mut PgConnection,
   _item_id: i64,
@@ -0,0 +1,118 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::new_naive_date;
+use crate::repository::category::get_ancestor_ids;
+use crate::repository::item::{Item, ItemFilter};
+use crate::schema::saved_search as saved_search_table;
+use crate::schema::saved_search::dsl::*;
+
+#[derive(Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = saved_search_table)]
+pub struct InsertSavedSearch {
+  pub user_id: i64,
+  pub name: String,
+  pub category_id: Option<i64>,
+  pub karat_id: Option<i64>,
+  pub geofence_id: Option<i64>,
+  pub min_price: Option<i64>,
+  pub max_price: Option<i64>,
+  pub push_enabled: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, Queryable)]
+pub struct SavedSearch {
+  pub id: i64,
+  pub user_id: i64,
+  pub name: String,
+  pub category_id: Option<i64>,
+  pub karat_id: Option<i64>,
+  pub geofence_id: Option<i64>,
+  pub min_price: Option<i64>,
+  pub max_price: Option<i64>,
+  pub push_enabled: bool,
+  pub created_at: NaiveDateTime,
+  pub updated_at: NaiveDateTime,
+  pub deleted_at: Option<NaiveDateTime>,
+}
+
+pub fn add_saved_search(
+  conn: &mut PgConnection,
+  _user_id: i64,
+  _name: String,
+  filter: &ItemFilter,
+  _push_enabled: bool,
+) -> Result<SavedSearch, DieselError> {
+  let new_search = InsertSavedSearch {
+    user_id: _user_id,
+    name: _name,
+    category_id: filter.category_id,
+    karat_id: filter.karat_id,
+    geofence_id: filter.geofence_id,
+    min_price: filter.min_price,
+    max_price: filter.max_price,
+    push_enabled: _push_enabled,
+  };
+  let resp = diesel::insert_into(saved_search)
+    .values(&new_search)
+    .get_result::<SavedSearch>(conn)?;
+  Ok(resp)
+}
+
+pub fn get_saved_searches_by_user_id(
+  conn: &mut PgConnection,
+  _user_id: i64,
+) -> Result<Vec<SavedSearch>, DieselError> {
+  let result = saved_search
+    .filter(user_id.eq(_user_id).and(deleted_at.is_null()))
+    .order(created_at.desc())
+    .load::<SavedSearch>(conn)?;
+  Ok(result)
+}
+
+pub fn delete_saved_search(
+  conn: &mut PgConnection,
+  search_id: i64,
+  _user_id: i64,
+) -> Result<(), DieselError> {
+  let result = diesel::update(saved_search)
+    .filter(
+      id.eq(search_id)
+        .and(user_id.eq(_user_id))
+        .and(deleted_at.is_null()),
+    )
+    .set(deleted_at.eq(new_naive_date()))
+    .execute(conn);
+  if result.is_err() || result.unwrap() == 0 {
+    return Err(DieselError::NotFound);
+  }
+  Ok(())
+}
+
+// searches of other users whose every set filter accepts the given item, a search
+// for a category also matches items of its subcategories
+pub fn get_matching_saved_searches(
+  conn: &mut PgConnection,
+  new_item: &Item,
+) -> Result<Vec<SavedSearch>, DieselError> {
+  let category_ids = get_ancestor_ids(conn, new_item.category_id)?;
+  let result = saved_search
+    .filter(
+      deleted_at
+        .is_null()
+        .and(user_id.ne(new_item.owner_id))
+        .and(category_id.is_null().or(category_id.eq_any(category_ids)))
+        .and(karat_id.is_null().or(karat_id.eq(new_item.karat_id)))
+        .and(
+          geofence_id
+            .is_null()
+            .or(geofence_id.eq(new_item.geofence_id)),
+        )
+        .and(min_price.is_null().or(min_price.le(new_item.price)))
+        .and(max_price.is_null().or(max_price.ge(new_item.price))),
+    )
+    .load::<SavedSearch>(conn)?;
+  Ok(result)
+}
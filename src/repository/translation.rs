@@ -0,0 +1,58 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::new_naive_date;
+use crate::schema::translation as translation_table;
+use crate::schema::translation::dsl::*;
+
+pub const CATEGORY_ENTITY: &str = "Category";
+pub const KARAT_ENTITY: &str = "Karat";
+pub const GEOFENCE_ENTITY: &str = "Geofence";
+
+#[derive(Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = translation_table)]
+pub struct InsertTranslation {
+  pub entity_type: String,
+  pub entity_id: i64,
+  pub locale: String,
+  pub name: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Queryable)]
+pub struct Translation {
+  pub id: i64,
+  pub entity_type: String,
+  pub entity_id: i64,
+  pub locale: String,
+  pub name: String,
+  pub created_at: NaiveDateTime,
+  pub updated_at: NaiveDateTime,
+}
+
+pub fn upsert_translation(
+  conn: &mut PgConnection,
+  new_translation: &InsertTranslation,
+) -> Result<Translation, DieselError> {
+  let resp = diesel::insert_into(translation)
+    .values(new_translation)
+    .on_conflict((entity_type, entity_id, locale))
+    .do_update()
+    .set((
+      name.eq(new_translation.name.clone()),
+      updated_at.eq(new_naive_date()),
+    ))
+    .get_result::<Translation>(conn)?;
+  Ok(resp)
+}
+
+pub fn get_translations_for_locale(
+  conn: &mut PgConnection,
+  _locale: &str,
+) -> Result<Vec<Translation>, DieselError> {
+  let result = translation
+    .filter(locale.eq(_locale))
+    .load::<Translation>(conn)?;
+  Ok(result)
+}
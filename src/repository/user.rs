@@ -5,6 +5,10 @@ use serde::{Deserialize, Serialize};
 use crate::schema::users as user_table;
 use crate::schema::users::dsl::*;
 
+pub const USER_ROLE: &str = "User";
+pub const MODERATOR_ROLE: &str = "Moderator";
+pub const ADMIN_ROLE: &str = "Admin";
+
 #[derive(Clone, Serialize, Deserialize, Insertable)]
 #[diesel(table_name = user_table)]
 pub struct InsertUser {
@@ -22,6 +26,19 @@ pub struct User {
   pub cover_image: Option<String>,
   pub created_at: chrono::NaiveDateTime,
   pub updated_at: chrono::NaiveDateTime,
+  pub role: String,
+  pub suspended_at: Option<chrono::NaiveDateTime>,
+  pub home_region_id: Option<i64>,
+}
+
+impl User {
+  pub fn is_moderator(&self) -> bool {
+    self.role == MODERATOR_ROLE || self.role == ADMIN_ROLE
+  }
+
+  pub fn is_suspended(&self) -> bool {
+    self.suspended_at.is_some()
+  }
 }
 
 pub fn insert_new_user(
@@ -72,12 +89,14 @@ pub fn update_profile(
   user_id: i64,
   new_cover_image: Option<String>,
   new_name: Option<String>,
+  new_home_region_id: Option<i64>,
 ) -> Result<(), DieselError> {
   let result = diesel::update(users)
     .filter(id.eq(user_id))
     .set((
       new_cover_image.map(|value| cover_image.eq(value)),
       new_name.map(|value| name.eq(value)),
+      new_home_region_id.map(|value| home_region_id.eq(value)),
       updated_at.eq(chrono::Local::now().naive_local()),
     ))
     .execute(conn);
@@ -101,3 +120,23 @@ pub fn delete_cover_image(conn: &mut PgConnection, user_id: i64) -> Result<(), D
   }
   Ok(())
 }
+
+pub fn get_cover_image_keys(conn: &mut PgConnection) -> Result<Vec<String>, DieselError> {
+  let result = users
+    .filter(cover_image.is_not_null())
+    .select(cover_image)
+    .load::<Option<String>>(conn)?;
+  Ok(result.into_iter().flatten().collect())
+}
+
+pub fn suspend_user(conn: &mut PgConnection, user_id: i64) -> Result<(), DieselError> {
+  let now = chrono::Local::now().naive_local();
+  let result = diesel::update(users)
+    .filter(id.eq(user_id).and(suspended_at.is_null()))
+    .set((suspended_at.eq(now), updated_at.eq(now)))
+    .execute(conn);
+  if result.is_err() || result.unwrap() == 0 {
+    return Err(DieselError::NotFound);
+  }
+  Ok(())
+}
@@ -0,0 +1,122 @@
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::new_naive_date;
+use crate::schema::user_block as user_block_table;
+use crate::schema::user_block::dsl::*;
+
+#[derive(Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = user_block_table)]
+pub struct InsertUserBlock {
+  pub blocker_id: i64,
+  pub blocked_id: i64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Queryable)]
+pub struct UserBlock {
+  pub id: i64,
+  pub blocker_id: i64,
+  pub blocked_id: i64,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+  pub deleted_at: Option<chrono::NaiveDateTime>,
+}
+
+pub fn block_user(
+  conn: &mut PgConnection,
+  _blocker_id: i64,
+  _blocked_id: i64,
+) -> Result<UserBlock, DieselError> {
+  let new_block = InsertUserBlock {
+    blocker_id: _blocker_id,
+    blocked_id: _blocked_id,
+  };
+  let no_deleted_at: Option<chrono::NaiveDateTime> = None;
+  let now = new_naive_date();
+  let resp = diesel::insert_into(user_block)
+    .values(&new_block)
+    .on_conflict((blocker_id, blocked_id))
+    .do_update()
+    .set((
+      deleted_at.eq(no_deleted_at),
+      created_at.eq(now),
+      updated_at.eq(now),
+    ))
+    .get_result::<UserBlock>(conn)?;
+  Ok(resp)
+}
+
+pub fn unblock_user(
+  conn: &mut PgConnection,
+  _blocker_id: i64,
+  _blocked_id: i64,
+) -> Result<(), DieselError> {
+  let result = diesel::update(user_block)
+    .filter(
+      blocker_id
+        .eq(_blocker_id)
+        .and(blocked_id.eq(_blocked_id))
+        .and(deleted_at.is_null()),
+    )
+    .set(deleted_at.eq(new_naive_date()))
+    .execute(conn);
+  if result.is_err() || result.unwrap() == 0 {
+    return Err(DieselError::NotFound);
+  }
+  Ok(())
+}
+
+pub fn get_blocks_by_blocker_id(
+  conn: &mut PgConnection,
+  _blocker_id: i64,
+) -> Result<Vec<UserBlock>, DieselError> {
+  let result = user_block
+    .filter(blocker_id.eq(_blocker_id).and(deleted_at.is_null()))
+    .order(created_at.desc())
+    .load::<UserBlock>(conn)?;
+  Ok(result)
+}
+
+pub fn get_blocked_user_ids(
+  conn: &mut PgConnection,
+  _blocker_id: i64,
+) -> Result<Vec<i64>, DieselError> {
+  let result = user_block
+    .select(blocked_id)
+    .filter(blocker_id.eq(_blocker_id).and(deleted_at.is_null()))
+    .load::<i64>(conn)?;
+  Ok(result)
+}
+
+// users who do not want to receive anything from `_blocked_id`
+pub fn get_blocker_ids(conn: &mut PgConnection, _blocked_id: i64) -> Result<Vec<i64>, DieselError> {
+  let result = user_block
+    .select(blocker_id)
+    .filter(blocked_id.eq(_blocked_id).and(deleted_at.is_null()))
+    .load::<i64>(conn)?;
+  Ok(result)
+}
+
+// true if any of the two users blocked the other one
+pub fn is_blocked_between(
+  conn: &mut PgConnection,
+  first_user_id: i64,
+  second_user_id: i64,
+) -> Result<bool, DieselError> {
+  let count = user_block
+    .filter(
+      blocker_id
+        .eq(first_user_id)
+        .and(blocked_id.eq(second_user_id))
+        .or(
+          blocker_id
+            .eq(second_user_id)
+            .and(blocked_id.eq(first_user_id)),
+        ),
+    )
+    .filter(deleted_at.is_null())
+    .count()
+    .get_result::<i64>(conn)?;
+  Ok(count > 0)
+}
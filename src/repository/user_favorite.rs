@@ -77,3 +77,35 @@ pub fn get_favorite_item_by_user_id_and_item_id(
     .first::<UserFavorite>(conn)?;
   return Ok(result);
 }
+
+pub fn get_favorites_for_item(
+  conn: &mut PgConnection,
+  _item_id: i64,
+) -> Result<Vec<UserFavorite>, DieselError> {
+  let result = user_favorite
+    .filter(item_id.eq(_item_id).and(is_favorite.eq(true)))
+    .load::<UserFavorite>(conn)?;
+  Ok(result)
+}
+
+// how often items were favorited by the users who favorited any of `_item_ids`
+pub fn get_co_favorite_counts(
+  conn: &mut PgConnection,
+  _item_ids: &[i64],
+) -> Result<Vec<(i64, i64)>, DieselError> {
+  let favoriter_ids = user_favorite
+    .select(user_id)
+    .filter(item_id.eq_any(_item_ids).and(is_favorite.eq(true)))
+    .load::<i64>(conn)?;
+  let result = user_favorite
+    .filter(
+      user_id
+        .eq_any(favoriter_ids)
+        .and(is_favorite.eq(true))
+        .and(item_id.ne_all(_item_ids)),
+    )
+    .group_by(item_id)
+    .select((item_id, diesel::dsl::count_star()))
+    .load::<(i64, i64)>(conn)?;
+  Ok(result)
+}
@@ -0,0 +1,122 @@
+use actix_web::{get, web, Error, HttpMessage, HttpRequest, HttpResponse};
+use chrono::{NaiveDateTime, Utc};
+use diesel::PgConnection;
+use log::warn;
+
+use super::models::{
+  AnalyticsCounts, AnalyticsPoint, ItemAnalytics, SellerAnalyticsRequest, SellerAnalyticsResponse,
+};
+use super::DbPool;
+use super::{route_error_handler, RouteError};
+use crate::repository::item::get_items_by_user_id;
+use crate::repository::item_event::{
+  add_item_event, get_events_for_items, FAVORITE_EVENT, OFFER_EVENT, ROOM_OPENED_EVENT, SALE_EVENT,
+  VIEW_EVENT,
+};
+
+const SECONDS_IN_DAY: i64 = 86400;
+const DEFAULT_ANALYTICS_RANGE_DAYS: i64 = 30;
+const MAX_ANALYTICS_RANGE_DAYS: i64 = 366;
+
+// Analytics must never break the action being tracked, so failures are only logged
+pub fn record_item_event(conn: &mut PgConnection, item_id: i64, user_id: i64, event_type: &str) {
+  if let Err(e) = add_item_event(conn, item_id, user_id, event_type) {
+    warn!(
+      "failed to record {} event for item {}: {}",
+      event_type, item_id, e
+    );
+  }
+}
+
+#[get("/users/analytics")]
+pub async fn get_seller_analytics(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  query: web::Query<SellerAnalyticsRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+
+  let to = query.to.unwrap_or_else(|| Utc::now().timestamp());
+  let from = query
+    .from
+    .unwrap_or(to - DEFAULT_ANALYTICS_RANGE_DAYS * SECONDS_IN_DAY);
+
+  let resp = web::block(move || -> Result<SellerAnalyticsResponse, RouteError> {
+    if from >= to {
+      return Err(RouteError::BadRequest("from must be before to".to_string()));
+    }
+    if to - from > MAX_ANALYTICS_RANGE_DAYS * SECONDS_IN_DAY {
+      return Err(RouteError::BadRequest(format!(
+        "date range can not exceed {} days",
+        MAX_ANALYTICS_RANGE_DAYS
+      )));
+    }
+    let from_date = to_naive_date(from)?;
+    let to_date = to_naive_date(to)?;
+
+    if let Ok(mut conn) = pool.get() {
+      let items = get_items_by_user_id(&mut conn, user_id)?;
+      let item_ids: Vec<i64> = items.iter().map(|item| item.id).collect();
+      let events = get_events_for_items(&mut conn, &item_ids, from_date, to_date)?;
+
+      // series are bucketed by day, starting from the day `from` falls into
+      let first_day = from - from.rem_euclid(SECONDS_IN_DAY);
+      let days = ((to - first_day + SECONDS_IN_DAY - 1) / SECONDS_IN_DAY) as usize;
+
+      let mut resp = SellerAnalyticsResponse {
+        from,
+        to,
+        total: AnalyticsCounts::default(),
+        series: new_series(first_day, days),
+        items: vec![],
+      };
+      for item in items {
+        let mut item_analytics = ItemAnalytics {
+          item_id: item.id,
+          title: item.title,
+          total: AnalyticsCounts::default(),
+          series: new_series(first_day, days),
+        };
+        for event in events.iter().filter(|event| event.item_id == item.id) {
+          let day = ((event.created_at.timestamp() - first_day) / SECONDS_IN_DAY) as usize;
+          count_event(&mut item_analytics.total, &event.event_type);
+          count_event(&mut item_analytics.series[day].counts, &event.event_type);
+          count_event(&mut resp.total, &event.event_type);
+          count_event(&mut resp.series[day].counts, &event.event_type);
+        }
+        resp.items.push(item_analytics);
+      }
+      return Ok(resp);
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+fn to_naive_date(timestamp: i64) -> Result<NaiveDateTime, RouteError> {
+  NaiveDateTime::from_timestamp_opt(timestamp, 0)
+    .ok_or_else(|| RouteError::BadRequest(format!("invalid timestamp: {}", timestamp)))
+}
+
+fn new_series(first_day: i64, days: usize) -> Vec<AnalyticsPoint> {
+  (0..days as i64)
+    .map(|day| AnalyticsPoint {
+      date: first_day + day * SECONDS_IN_DAY,
+      counts: AnalyticsCounts::default(),
+    })
+    .collect()
+}
+
+fn count_event(counts: &mut AnalyticsCounts, event_type: &str) {
+  match event_type {
+    VIEW_EVENT => counts.views += 1,
+    FAVORITE_EVENT => counts.favorites += 1,
+    ROOM_OPENED_EVENT => counts.rooms_opened += 1,
+    OFFER_EVENT => counts.offers += 1,
+    SALE_EVENT => counts.sales += 1,
+    _ => warn!("unknown item event type: {}", event_type),
+  }
+}
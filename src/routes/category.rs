@@ -1,17 +1,20 @@
-use actix_web::{delete, get, post, web, Error, HttpResponse};
+use actix_web::{delete, get, post, web, Error, HttpMessage, HttpRequest, HttpResponse};
 use diesel::{
   prelude::*,
   r2d2::{self, ConnectionManager},
 };
 
-use super::models::CreateCategoryRequest;
+use super::locale::{get_locale, Translations};
+use super::models::{CategoryNode, CreateCategoryRequest, MoveCategoryRequest};
 use super::DbPool;
-use super::{route_error_handler, RouteError};
+use super::{route_error_handler, verify_admin, RouteError};
 
 use crate::repository::category::{
   add_category, delete_category as repo_delete_category, get_by_name,
-  get_categories as repo_get_categories,
+  get_categories as repo_get_categories, get_category_by_id, get_descendant_ids,
+  move_category as repo_move_category, Category,
 };
+use crate::repository::translation::CATEGORY_ENTITY;
 
 #[post("/categories/create")]
 pub async fn create_category(
@@ -21,9 +24,14 @@ pub async fn create_category(
   let pool_cloned: web::Data<r2d2::Pool<ConnectionManager<PgConnection>>> = pool.clone();
   let name = form.name.to_owned();
   let avatar = form.avatar.to_owned();
+  let parent_id = form.parent_id;
+  let position = form.position.unwrap_or(0);
   web::block(move || {
     if let Ok(mut conn) = pool_cloned.get() {
-      add_category(&mut conn, name, avatar)?;
+      if let Some(parent_id) = parent_id {
+        verify_live_category(&mut conn, parent_id)?;
+      }
+      add_category(&mut conn, name, avatar, parent_id, position)?;
       return Ok(());
     }
     return Err(RouteError::PoolingErr);
@@ -54,12 +62,21 @@ pub async fn get_category(
   Ok(HttpResponse::Ok().json(resp))
 }
 
+// the live categories as a tree, siblings ordered by position
 #[get("/categories")]
-pub async fn get_categories(pool: web::Data<DbPool>) -> Result<HttpResponse, Error> {
+pub async fn get_categories(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+  let locale = get_locale(&req);
   let resp = web::block(move || {
     if let Ok(mut conn) = pool.get() {
-      let resp = repo_get_categories(&mut conn)?;
-      return Ok(resp);
+      let translations = Translations::load(&mut conn, locale)?;
+      let categories: Vec<Category> = repo_get_categories(&mut conn)?
+        .into_iter()
+        .filter(|category| category.deleted_at.is_none())
+        .collect();
+      return Ok(to_category_nodes(&categories, None, &translations));
     }
     return Err(RouteError::PoolingErr);
   })
@@ -84,3 +101,68 @@ pub async fn delete_category(
   .map_err(|e| route_error_handler(e))?;
   Ok(HttpResponse::Ok().body("OK"))
 }
+
+#[post("/categories/{name}/move")]
+pub async fn move_category(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  name: web::Path<String>,
+  form: web::Json<MoveCategoryRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let form = form.into_inner();
+  web::block(move || -> Result<(), RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      verify_admin(&mut conn, user_id)?;
+      let category = match get_by_name(&mut conn, name.to_string())? {
+        Some(category) if category.deleted_at.is_none() => category,
+        _ => return Err(RouteError::InvalidCategory),
+      };
+      if let Some(parent_id) = form.parent_id {
+        verify_live_category(&mut conn, parent_id)?;
+        // a category can not move under itself or its own subcategories
+        if get_descendant_ids(&mut conn, category.id)?.contains(&parent_id) {
+          return Err(RouteError::BadRequest(
+            "can not move a category under itself".to_string(),
+          ));
+        }
+      }
+      repo_move_category(&mut conn, category.id, form.parent_id, form.position)?;
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+fn verify_live_category(conn: &mut PgConnection, category_id: i64) -> Result<(), RouteError> {
+  if get_category_by_id(conn, category_id)?.deleted_at.is_some() {
+    return Err(RouteError::InvalidCategory);
+  }
+  Ok(())
+}
+
+fn to_category_nodes(
+  categories: &[Category],
+  parent_id: Option<i64>,
+  translations: &Translations,
+) -> Vec<CategoryNode> {
+  let mut children: Vec<&Category> = categories
+    .iter()
+    .filter(|category| category.parent_id == parent_id)
+    .collect();
+  children.sort_by_key(|category| (category.position, category.id));
+  children
+    .into_iter()
+    .map(|category| CategoryNode {
+      id: category.id,
+      name: category.name.clone(),
+      localized_name: translations.name(CATEGORY_ENTITY, category.id, &category.name),
+      avatar: category.avatar.clone(),
+      position: category.position,
+      children: to_category_nodes(categories, Some(category.id), translations),
+    })
+    .collect()
+}
@@ -0,0 +1,320 @@
+use actix_web::{get, post, web, web::Path, Error, HttpMessage, HttpRequest, HttpResponse};
+use diesel::prelude::*;
+use std::collections::HashSet;
+
+use super::models::{
+  CategoryAttributeResponse, CategoryAttributes, CreateCategoryAttributeRequest,
+  ItemAttributeRequest,
+};
+use super::DbPool;
+use super::{route_error_handler, verify_admin, RouteError};
+use crate::repository::category_attribute::{
+  add_category_attribute, delete_category_attribute as repo_delete_category_attribute,
+  get_category_attributes as repo_get_category_attributes, CategoryAttribute,
+  InsertCategoryAttribute, NUMBER_ATTRIBUTE_TYPE, TEXT_ATTRIBUTE_TYPE,
+};
+use crate::repository::item::{AttributeFilter, Item};
+use crate::repository::item_attribute::{get_item_attributes, AttributeValue};
+
+#[post("/categories/{category_id}/attributes/create")]
+pub async fn create_category_attribute(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  category_id: Path<i64>,
+  form: web::Json<CreateCategoryAttributeRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let category_id = category_id.into_inner();
+  let form = form.into_inner();
+  let resp = web::block(move || -> Result<CategoryAttributeResponse, RouteError> {
+    let name = form.name.trim().to_string();
+    if name.is_empty() {
+      return Err(RouteError::BadRequest(
+        "attribute name is empty".to_string(),
+      ));
+    }
+    if form.attribute_type != NUMBER_ATTRIBUTE_TYPE && form.attribute_type != TEXT_ATTRIBUTE_TYPE {
+      return Err(RouteError::BadRequest(format!(
+        "invalid attribute type: {}",
+        form.attribute_type
+      )));
+    }
+    let allowed_values: Vec<String> = form
+      .allowed_values
+      .unwrap_or_default()
+      .iter()
+      .map(|value| value.trim().to_string())
+      .collect();
+    if form.attribute_type == NUMBER_ATTRIBUTE_TYPE
+      && allowed_values
+        .iter()
+        .any(|value| value.parse::<f64>().is_err())
+    {
+      return Err(RouteError::BadRequest(
+        "allowed values of a number attribute must be numbers".to_string(),
+      ));
+    }
+    if let Ok(mut conn) = pool.get() {
+      verify_admin(&mut conn, user_id)?;
+      let attribute = add_category_attribute(
+        &mut conn,
+        &InsertCategoryAttribute {
+          category_id,
+          name,
+          attribute_type: form.attribute_type,
+          unit: form.unit,
+          allowed_values,
+          required: form.required.unwrap_or(false),
+        },
+      )?;
+      return Ok(to_category_attribute_response(attribute));
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+#[get("/categories/{category_id}/attributes")]
+pub async fn get_category_attributes(
+  pool: web::Data<DbPool>,
+  category_id: Path<i64>,
+) -> Result<HttpResponse, Error> {
+  let category_id = category_id.into_inner();
+  let resp = web::block(move || -> Result<CategoryAttributes, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      let attributes = repo_get_category_attributes(&mut conn, category_id)?;
+      return Ok(CategoryAttributes {
+        attributes: attributes
+          .into_iter()
+          .map(to_category_attribute_response)
+          .collect(),
+      });
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+#[post("/categories/{category_id}/attributes/{attribute_id}/delete")]
+pub async fn delete_category_attribute(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  path: Path<(i64, i64)>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let (category_id, attribute_id) = path.into_inner();
+  web::block(move || -> Result<(), RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      verify_admin(&mut conn, user_id)?;
+      repo_delete_category_attribute(&mut conn, category_id, attribute_id)?;
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+fn to_category_attribute_response(attribute: CategoryAttribute) -> CategoryAttributeResponse {
+  CategoryAttributeResponse {
+    id: attribute.id,
+    category_id: attribute.category_id,
+    name: attribute.name,
+    attribute_type: attribute.attribute_type,
+    unit: attribute.unit,
+    allowed_values: attribute.allowed_values,
+    required: attribute.required,
+  }
+}
+
+// checks the values against the attributes of the category. Required attributes are
+// not checked here since drafts may leave them out, see `get_missing_attributes`.
+pub fn validate_item_attributes(
+  conn: &mut PgConnection,
+  category_id: i64,
+  values: &[ItemAttributeRequest],
+) -> Result<Vec<AttributeValue>, RouteError> {
+  let attributes = repo_get_category_attributes(conn, category_id)?;
+  let mut seen_ids: HashSet<i64> = HashSet::new();
+  let mut resp = Vec::with_capacity(values.len());
+  for value in values {
+    let attribute = match attributes
+      .iter()
+      .find(|attribute| attribute.id == value.attribute_id)
+    {
+      Some(attribute) => attribute,
+      None => {
+        return Err(RouteError::BadRequest(format!(
+          "attribute {} does not belong to category {}",
+          value.attribute_id, category_id
+        )))
+      }
+    };
+    if !seen_ids.insert(attribute.id) {
+      return Err(RouteError::BadRequest(format!(
+        "{} is set more than once",
+        attribute.name
+      )));
+    }
+    let trimmed = value.value.trim();
+    if trimmed.is_empty() {
+      return Err(RouteError::BadRequest(format!(
+        "{} is empty",
+        attribute.name
+      )));
+    }
+    let number_value = if attribute.attribute_type == NUMBER_ATTRIBUTE_TYPE {
+      match trimmed.parse::<f64>() {
+        Ok(number) if number.is_finite() => Some(number),
+        _ => {
+          return Err(RouteError::BadRequest(format!(
+            "{} must be a number",
+            attribute.name
+          )))
+        }
+      }
+    } else {
+      None
+    };
+    if !attribute.allowed_values.is_empty() && !is_allowed(attribute, trimmed, number_value) {
+      return Err(RouteError::BadRequest(format!(
+        "{} must be one of: {}",
+        attribute.name,
+        attribute.allowed_values.join(", ")
+      )));
+    }
+    resp.push(AttributeValue {
+      attribute_id: attribute.id,
+      value: trimmed.to_string(),
+      number_value,
+    });
+  }
+  Ok(resp)
+}
+
+// numbers are compared by value so "17" matches an allowed "17.0"
+fn is_allowed(attribute: &CategoryAttribute, value: &str, number_value: Option<f64>) -> bool {
+  attribute
+    .allowed_values
+    .iter()
+    .any(|allowed| match number_value {
+      Some(number) => allowed
+        .parse::<f64>()
+        .is_ok_and(|allowed| allowed == number),
+      None => allowed == value,
+    })
+}
+
+// names of the required attributes of the item category the item has no value for
+pub fn get_missing_attributes(
+  conn: &mut PgConnection,
+  item: &Item,
+) -> Result<Vec<String>, RouteError> {
+  let set_ids: HashSet<i64> = get_item_attributes(conn, item.id)?
+    .into_iter()
+    .map(|(value, _)| value.attribute_id)
+    .collect();
+  Ok(
+    repo_get_category_attributes(conn, item.category_id)?
+      .into_iter()
+      .filter(|attribute| attribute.required && !set_ids.contains(&attribute.id))
+      .map(|attribute| attribute.name)
+      .collect(),
+  )
+}
+
+// parses the `attributes` search parameter, see `SearchItemsRequest`
+pub fn parse_attribute_filters(query: &str) -> Result<Vec<AttributeFilter>, RouteError> {
+  let invalid = |part: &str| RouteError::BadRequest(format!("invalid attribute filter: {}", part));
+  let mut filters = vec![];
+  for part in query
+    .split(',')
+    .map(str::trim)
+    .filter(|part| !part.is_empty())
+  {
+    let (attribute_id, value) = part.split_once(':').ok_or_else(|| invalid(part))?;
+    let attribute_id: i64 = attribute_id.trim().parse().map_err(|_| invalid(part))?;
+    let value = value.trim();
+    let mut filter = AttributeFilter {
+      attribute_id,
+      ..Default::default()
+    };
+    match value.split_once("..") {
+      Some((min, max)) => {
+        let parse_bound = |bound: &str| -> Result<Option<f64>, RouteError> {
+          let bound = bound.trim();
+          if bound.is_empty() {
+            return Ok(None);
+          }
+          bound.parse::<f64>().map(Some).map_err(|_| invalid(part))
+        };
+        filter.min = parse_bound(min)?;
+        filter.max = parse_bound(max)?;
+      }
+      None if !value.is_empty() => filter.value = Some(value.to_string()),
+      None => return Err(invalid(part)),
+    }
+    filters.push(filter);
+  }
+  Ok(filters)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn exact_values() {
+    let filters = parse_attribute_filters("3:red, 7: 18k gold ").unwrap();
+    assert_eq!(filters.len(), 2);
+    assert_eq!(filters[0].attribute_id, 3);
+    assert_eq!(filters[0].value.as_deref(), Some("red"));
+    assert_eq!((filters[0].min, filters[0].max), (None, None));
+    assert_eq!(filters[1].attribute_id, 7);
+    assert_eq!(filters[1].value.as_deref(), Some("18k gold"));
+  }
+
+  #[test]
+  fn ranges() {
+    let filters = parse_attribute_filters("5:1.5..10").unwrap();
+    assert_eq!(filters[0].attribute_id, 5);
+    assert_eq!(filters[0].value, None);
+    assert_eq!((filters[0].min, filters[0].max), (Some(1.5), Some(10.0)));
+  }
+
+  #[test]
+  fn open_bounds() {
+    let filters = parse_attribute_filters("5:2..,6:..8,7:..").unwrap();
+    assert_eq!((filters[0].min, filters[0].max), (Some(2.0), None));
+    assert_eq!((filters[1].min, filters[1].max), (None, Some(8.0)));
+    assert_eq!((filters[2].min, filters[2].max), (None, None));
+  }
+
+  #[test]
+  fn empty_parts_are_skipped() {
+    assert!(parse_attribute_filters("").unwrap().is_empty());
+    assert_eq!(parse_attribute_filters("1:a,,").unwrap().len(), 1);
+  }
+
+  #[test]
+  fn invalid_filters() {
+    for query in ["1", "a:red", "1:", "1:x..2", "1:1..y"] {
+      assert!(
+        matches!(
+          parse_attribute_filters(query),
+          Err(RouteError::BadRequest(_))
+        ),
+        "{} should be refused",
+        query
+      );
+    }
+  }
+}
@@ -0,0 +1,55 @@
+use actix_web::{post, web, Error, HttpMessage, HttpRequest, HttpResponse};
+
+use super::models::{DevicePlatform, RegisterDeviceRequest, UnregisterDeviceRequest};
+use super::DbPool;
+use super::{route_error_handler, RouteError};
+use crate::repository::device_token::{delete_device_token, upsert_device_token};
+
+#[post("/devices/register")]
+pub async fn register_device(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  form: web::Json<RegisterDeviceRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let token = form.token.trim().to_string();
+  let platform = match form.platform {
+    DevicePlatform::Android => "Android",
+    DevicePlatform::Ios => "Ios",
+  };
+  web::block(move || {
+    if token.is_empty() {
+      return Err(RouteError::BadRequest("device token is empty".to_string()));
+    }
+    if let Ok(mut conn) = pool.get() {
+      upsert_device_token(&mut conn, user_id, token, platform.to_string())?;
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+#[post("/devices/unregister")]
+pub async fn unregister_device(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  form: web::Json<UnregisterDeviceRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let token = form.token.to_owned();
+  web::block(move || {
+    if let Ok(mut conn) = pool.get() {
+      delete_device_token(&mut conn, user_id, &token)?;
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().body("OK"))
+}
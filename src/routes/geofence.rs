@@ -1,15 +1,47 @@
-use actix_web::{get, web, Error, HttpResponse};
+use actix_web::{get, post, web, web::Path, Error, HttpMessage, HttpRequest, HttpResponse};
+use diesel::prelude::*;
 
+use log::warn;
+
+use super::locale::{get_locale, Translations};
+use super::models::{
+  CreateGeofenceRequest, GeofenceNode, Location, MoveGeofenceRequest, RegionResponse,
+  ResolveRegionRequest,
+};
 use super::DbPool;
-use super::{route_error_handler, RouteError};
-use crate::repository::geofence::get_geofences as repo_get_geofences;
+use super::{route_error_handler, verify_admin, RouteError};
+use crate::geo::{is_valid_coordinate, Boundary};
+use crate::repository::geofence::{
+  add_geofence, get_geofence_by_id, get_geofences as repo_get_geofences, get_geofences_around,
+  get_live_children, get_region_scope_ids, move_geofence as repo_move_geofence,
+  retire_geofence as repo_retire_geofence, set_geofence_boundary, Geofence, CITY_GEOFENCE_TYPE,
+  COUNTRY_GEOFENCE_TYPE, GLOBAL_GEOFENCE_TYPE, NO_PARENT_REGION_ID,
+};
+use crate::repository::translation::GEOFENCE_ENTITY;
+
+pub const HOME_FEED_SCOPE: &str = "home";
+pub const PARENT_FEED_SCOPE: &str = "parent";
+pub const ALL_FEED_SCOPE: &str = "all";
 
+// the live regions as a tree, Global > Country > City
 #[get("/geofences")]
-pub async fn get_geofences(pool: web::Data<DbPool>) -> Result<HttpResponse, Error> {
+pub async fn get_geofences(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+  let locale = get_locale(&req);
   let resp = web::block(move || {
     if let Ok(mut conn) = pool.get() {
-      let resp = repo_get_geofences(&mut conn)?;
-      return Ok(resp);
+      let translations = Translations::load(&mut conn, locale)?;
+      let geofences: Vec<Geofence> = repo_get_geofences(&mut conn)?
+        .into_iter()
+        .filter(|geofence| geofence.deleted_at.is_none())
+        .collect();
+      return Ok(to_geofence_nodes(
+        &geofences,
+        NO_PARENT_REGION_ID,
+        &translations,
+      ));
     }
     return Err(RouteError::PoolingErr);
   })
@@ -17,3 +49,340 @@ pub async fn get_geofences(pool: web::Data<DbPool>) -> Result<HttpResponse, Erro
   .map_err(|e| route_error_handler(e))?;
   Ok(HttpResponse::Ok().json(resp))
 }
+
+#[post("/geofences/create")]
+pub async fn create_geofence(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  form: web::Json<CreateGeofenceRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let form = form.into_inner();
+  web::block(move || -> Result<(), RouteError> {
+    let name = form.name.trim().to_string();
+    if name.is_empty() {
+      return Err(RouteError::BadRequest("region name is empty".to_string()));
+    }
+    if let Ok(mut conn) = pool.get() {
+      verify_admin(&mut conn, user_id)?;
+      verify_parent_region(&mut conn, &form.geofence_type, form.parent_region_id)?;
+      add_geofence(&mut conn, name, form.geofence_type, form.parent_region_id)?;
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+#[post("/geofences/{geofence_id}/move")]
+pub async fn move_geofence(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  geofence_id: Path<i64>,
+  form: web::Json<MoveGeofenceRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let geofence_id = geofence_id.into_inner();
+  web::block(move || -> Result<(), RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      verify_admin(&mut conn, user_id)?;
+      let region = get_live_region(&mut conn, geofence_id)?;
+      verify_parent_region(&mut conn, &region.geofence_type, form.parent_region_id)?;
+      repo_move_geofence(&mut conn, geofence_id, form.parent_region_id)?;
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+// regions with live children have to be emptied first
+#[post("/geofences/{geofence_id}/retire")]
+pub async fn retire_geofence(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  geofence_id: Path<i64>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let geofence_id = geofence_id.into_inner();
+  web::block(move || -> Result<(), RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      verify_admin(&mut conn, user_id)?;
+      let region = get_live_region(&mut conn, geofence_id)?;
+      if region.geofence_type == GLOBAL_GEOFENCE_TYPE {
+        return Err(RouteError::BadRequest(
+          "can not retire the global region".to_string(),
+        ));
+      }
+      if !get_live_children(&mut conn, geofence_id)?.is_empty() {
+        return Err(RouteError::BadRequest(
+          "region still has live subregions".to_string(),
+        ));
+      }
+      repo_retire_geofence(&mut conn, geofence_id)?;
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+// the body is a GeoJSON Polygon or MultiPolygon, or a Feature or FeatureCollection of them
+#[post("/geofences/{geofence_id}/boundary")]
+pub async fn set_boundary(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  geofence_id: Path<i64>,
+  body: String,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let geofence_id = geofence_id.into_inner();
+  web::block(move || -> Result<(), RouteError> {
+    let boundary = Boundary::parse(&body).map_err(|e| RouteError::BadRequest(e.to_string()))?;
+    if let Ok(mut conn) = pool.get() {
+      verify_admin(&mut conn, user_id)?;
+      get_live_region(&mut conn, geofence_id)?;
+      set_geofence_boundary(
+        &mut conn,
+        geofence_id,
+        boundary.to_geojson().to_string(),
+        boundary.bounding_box(),
+      )?;
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+#[get("/geofences/resolve")]
+pub async fn resolve_geofence(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  query: web::Query<ResolveRegionRequest>,
+) -> Result<HttpResponse, Error> {
+  let locale = get_locale(&req);
+  let resp = web::block(move || -> Result<RegionResponse, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      let translations = Translations::load(&mut conn, locale)?;
+      let region = resolve_region(&mut conn, query.latitude, query.longitude)?;
+      return Ok(RegionResponse {
+        id: region.id,
+        localized_name: translations.name(GEOFENCE_ENTITY, region.id, &region.name),
+        name: region.name,
+        geofence_type: region.geofence_type,
+      });
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+// The most specific live region whose boundary contains the point, cities before
+// countries and smaller regions before larger ones. Points outside every boundary
+// are in the global region.
+pub fn resolve_region(
+  conn: &mut PgConnection,
+  latitude: f64,
+  longitude: f64,
+) -> Result<Geofence, RouteError> {
+  if !is_valid_coordinate(latitude, longitude) {
+    return Err(RouteError::BadRequest(format!(
+      "invalid coordinate: {}, {}",
+      latitude, longitude
+    )));
+  }
+  let mut best: Option<(Geofence, f64)> = None;
+  for region in get_geofences_around(conn, latitude, longitude)? {
+    let boundary = match Boundary::parse(region.boundary.as_deref().unwrap_or_default()) {
+      Ok(boundary) => boundary,
+      Err(e) => {
+        warn!("invalid boundary of region {}: {}", region.id, e);
+        continue;
+      }
+    };
+    if !boundary.contains(latitude, longitude) {
+      continue;
+    }
+    let area = boundary.bounding_box().area();
+    let is_better = match best {
+      Some((ref current, current_area)) => {
+        let (rank, current_rank) = (
+          region_rank(&region.geofence_type),
+          region_rank(&current.geofence_type),
+        );
+        rank > current_rank || (rank == current_rank && area < current_area)
+      }
+      None => true,
+    };
+    if is_better {
+      best = Some((region, area));
+    }
+  }
+  if let Some((region, _)) = best {
+    return Ok(region);
+  }
+  repo_get_geofences(conn)?
+    .into_iter()
+    .find(|region| region.deleted_at.is_none() && region.geofence_type == GLOBAL_GEOFENCE_TYPE)
+    .ok_or(RouteError::DbError(diesel::result::Error::NotFound))
+}
+
+// The region an item is listed in, the given geofence or the one resolved from the
+// location. None when neither is set.
+pub fn resolve_item_region(
+  conn: &mut PgConnection,
+  geofence_id: Option<i64>,
+  location: Option<&Location>,
+) -> Result<Option<i64>, RouteError> {
+  let geofence_id = match (geofence_id, location) {
+    (Some(geofence_id), _) => geofence_id,
+    (None, Some(location)) => {
+      let region = resolve_region(conn, location.latitude, location.longitude)?;
+      // the resolver falls back to the country or the global region
+      if !get_live_children(conn, region.id)?.is_empty() {
+        return Err(RouteError::BadRequest(
+          "no city region covers this location".to_string(),
+        ));
+      }
+      region.id
+    }
+    (None, None) => return Ok(None),
+  };
+  validate_item_region(conn, geofence_id)?;
+  Ok(Some(geofence_id))
+}
+
+fn region_rank(geofence_type: &str) -> u8 {
+  match geofence_type {
+    CITY_GEOFENCE_TYPE => 2,
+    COUNTRY_GEOFENCE_TYPE => 1,
+    _ => 0,
+  }
+}
+
+// items are listed in the most specific region, a live region without subregions
+pub fn validate_item_region(conn: &mut PgConnection, geofence_id: i64) -> Result<(), RouteError> {
+  let region = get_geofence_by_id(conn, geofence_id)?;
+  if region.deleted_at.is_some() {
+    return Err(RouteError::BadRequest(format!(
+      "region {} is retired",
+      geofence_id
+    )));
+  }
+  if !get_live_children(conn, geofence_id)?.is_empty() {
+    return Err(RouteError::BadRequest(format!(
+      "region {} is not a leaf region",
+      geofence_id
+    )));
+  }
+  Ok(())
+}
+
+// any live region below the global one
+pub fn validate_home_region(conn: &mut PgConnection, geofence_id: i64) -> Result<(), RouteError> {
+  let region = get_live_region(conn, geofence_id)?;
+  if region.geofence_type == GLOBAL_GEOFENCE_TYPE {
+    return Err(RouteError::BadRequest(
+      "the global region can not be a home region".to_string(),
+    ));
+  }
+  Ok(())
+}
+
+// The regions of the feed around a home region, see `SearchItemsRequest::scope`. None
+// when the feed is not scoped.
+pub fn get_feed_region_ids(
+  conn: &mut PgConnection,
+  home_region_id: Option<i64>,
+  scope: &str,
+) -> Result<Option<Vec<i64>>, RouteError> {
+  if ![HOME_FEED_SCOPE, PARENT_FEED_SCOPE, ALL_FEED_SCOPE].contains(&scope) {
+    return Err(RouteError::BadRequest(format!(
+      "invalid feed scope: {}",
+      scope
+    )));
+  }
+  let home_region_id = match home_region_id {
+    Some(home_region_id) if scope != ALL_FEED_SCOPE => home_region_id,
+    _ => return Ok(None),
+  };
+  let region = get_geofence_by_id(conn, home_region_id)?;
+  let scope_region_id =
+    if scope == PARENT_FEED_SCOPE && region.parent_region_id != NO_PARENT_REGION_ID {
+      region.parent_region_id
+    } else {
+      home_region_id
+    };
+  Ok(Some(get_region_scope_ids(conn, scope_region_id)?))
+}
+
+fn get_live_region(conn: &mut PgConnection, geofence_id: i64) -> Result<Geofence, RouteError> {
+  let region = get_geofence_by_id(conn, geofence_id)?;
+  if region.deleted_at.is_some() {
+    return Err(RouteError::BadRequest(format!(
+      "region {} is retired",
+      geofence_id
+    )));
+  }
+  Ok(region)
+}
+
+// countries are under the global region and cities under a country
+fn verify_parent_region(
+  conn: &mut PgConnection,
+  geofence_type: &str,
+  parent_region_id: i64,
+) -> Result<(), RouteError> {
+  let parent_type = match geofence_type {
+    COUNTRY_GEOFENCE_TYPE => GLOBAL_GEOFENCE_TYPE,
+    CITY_GEOFENCE_TYPE => COUNTRY_GEOFENCE_TYPE,
+    _ => {
+      return Err(RouteError::BadRequest(format!(
+        "invalid region type: {}",
+        geofence_type
+      )))
+    }
+  };
+  let parent = get_live_region(conn, parent_region_id)?;
+  if parent.geofence_type != parent_type {
+    return Err(RouteError::BadRequest(format!(
+      "a {} must be in a {} region",
+      geofence_type, parent_type
+    )));
+  }
+  Ok(())
+}
+
+fn to_geofence_nodes(
+  geofences: &[Geofence],
+  parent_region_id: i64,
+  translations: &Translations,
+) -> Vec<GeofenceNode> {
+  geofences
+    .iter()
+    .filter(|geofence| geofence.parent_region_id == parent_region_id)
+    .map(|geofence| GeofenceNode {
+      id: geofence.id,
+      name: geofence.name.clone(),
+      localized_name: translations.name(GEOFENCE_ENTITY, geofence.id, &geofence.name),
+      geofence_type: geofence.geofence_type.clone(),
+      children: to_geofence_nodes(geofences, geofence.id, translations),
+    })
+    .collect()
+}
@@ -1,33 +1,68 @@
+use actix::Addr;
 use actix_web::web::Json;
 use actix_web::{get, post, web, web::Path, Error, HttpMessage, HttpRequest, HttpResponse};
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use std::collections::HashSet;
 
-use s3::bucket::Bucket;
-
+use super::analytics::record_item_event;
+use super::category_attribute::{
+  get_missing_attributes, parse_attribute_filters, validate_item_attributes,
+};
+use super::geofence::{get_feed_region_ids, resolve_item_region, HOME_FEED_SCOPE};
+use super::locale::{get_locale, Translations};
 use super::models::{
-  Buyer, Buyers, CreateItemRequest, CreateItemResponse, CreatePurchaseRequest, GetItemResponse,
-  GetItemsResponse, HideUnhideItemRequest, ItemOwner, ItemResponse, ItemStatus,
-  UpdateItemStatusRequest,
+  Buyer, Buyers, CatalogEntry, CreateItemRequest, CreateItemResponse, CreatePurchaseRequest,
+  GetItemResponse, GetItemsResponse, HideUnhideItemRequest, ItemAttributeResponse,
+  ItemListingResponse, ItemOwner, ItemResponse, ItemStatus, Location, PriceChange,
+  SearchItemsRequest, UpdateItemRequest, UpdateItemStatusRequest,
 };
+use super::notification::try_notify_user;
+use super::saved_search::notify_saved_search_matches;
 use super::DbPool;
 use super::{route_error_handler, RouteError};
 
+use crate::repository::category::get_category_by_id;
+use crate::repository::geofence::get_geofence_by_id;
+use crate::repository::item_attribute::{
+  delete_item_attributes, get_item_attributes, set_item_attributes,
+};
+use crate::repository::item_event::{FAVORITE_EVENT, OFFER_EVENT, SALE_EVENT, VIEW_EVENT};
 use crate::repository::item_image::get_docs_for_item;
+use crate::repository::item_price_history::{add_price_change, get_price_history};
+use crate::repository::karat::get_karat_by_id;
+use crate::repository::notification::{
+  InsertNotification, ITEM_FAVORITED_NOTIFICATION, ITEM_PRICE_DROPPED_NOTIFICATION,
+  ITEM_PURCHASED_NOTIFICATION, ITEM_STATUS_CHANGED_NOTIFICATION,
+};
 use crate::repository::user_favorite::{
-  add_item_favorite, get_favorite_item_by_user_id_and_item_id, update_item_favorite_status,
+  add_item_favorite, get_favorite_item_by_user_id_and_item_id, get_favorites_for_item,
+  update_item_favorite_status,
 };
 
+use crate::geo::{distance_km, fuzz_location, is_valid_coordinate, BoundingBox};
+use crate::helpers::new_naive_date;
+use crate::jobs::listing_expiry::ListingConfig;
 use crate::repository::item::{
-  create_purchase as repo_create_purchase, get_all_visible, get_item_by_id, hide_unhide_item,
-  insert_new_item, update_favorite_count, update_item_status, get_purchase_for_item,
+  bump_item as repo_bump_item, create_purchase as repo_create_purchase, get_all_visible,
+  get_item_by_id, get_item_by_id_for_update, get_purchase_for_item, hide_unhide_item,
+  increment_seen_count, insert_new_item, publish_item as repo_publish_item,
+  renew_item as repo_renew_item, update_favorite_count, update_item as repo_update_item,
+  update_item_status, Item, ItemFilter, UpdateItem, ACTIVE_ITEM_STATUS, DRAFT_ITEM_STATUS,
 };
 use crate::repository::room_member::get_all_buyers_for_item;
+use crate::repository::translation::{CATEGORY_ENTITY, GEOFENCE_ENTITY, KARAT_ENTITY};
 use crate::repository::user::{self, get_user_by_id};
+use crate::repository::user_block::get_blocked_user_ids;
 use crate::schema::item::owner_id;
+use crate::storage::cdn::MediaUrls;
+use crate::ws::lobby::Lobby;
 
 use log::warn;
 
 const GET_IMAGE_EXPIRATION_SECONDS: u32 = 200;
-pub const CLOUD_FRONT_DISTRIBUTION_DOMAIN_NAME: &str = "d1a8cs8n1a8sq9.cloudfront.net";
+const DEFAULT_SEARCH_RADIUS_KM: f64 = 25.0;
+const MAX_SEARCH_RADIUS_KM: f64 = 200.0;
 
 #[post("/items/create")]
 pub async fn create_item(
@@ -47,19 +82,34 @@ pub async fn create_item(
     if let Ok(mut conn) = pool.get() {
       // verify user exists
       user::get_user_by_id(&mut conn, user_id)?;
-      let new_item = insert_new_item(
+      let geofence_id = resolve_item_region(&mut conn, form.geofence_id, form.location.as_ref())?
+        .ok_or_else(|| {
+        RouteError::BadRequest("a geofence id or a location is required".to_string())
+      })?;
+      let location = to_item_location(form.location.as_ref())?;
+      let attributes = validate_item_attributes(
         &mut conn,
-        user_id,
-        title,
-        description,
-        price,
-        form.negotiable,
-        form.size,
-        form.weight,
-        form.karat_id,
         form.category_id,
-        form.geofence_id,
+        form.attributes.as_deref().unwrap_or_default(),
       )?;
+      let new_item = conn.transaction::<_, DieselError, _>(|conn| {
+        let new_item = insert_new_item(
+          conn,
+          user_id,
+          title,
+          description,
+          price,
+          form.negotiable,
+          form.size,
+          form.weight,
+          form.karat_id,
+          form.category_id,
+          geofence_id,
+          location,
+        )?;
+        set_item_attributes(conn, new_item.id, &attributes)?;
+        Ok(new_item)
+      })?;
       return Ok(new_item);
     }
     return Err(RouteError::PoolingErr);
@@ -76,27 +126,72 @@ pub async fn create_item(
 #[get("/items")]
 pub async fn get_items(
   pool: web::Data<DbPool>,
+  media_urls: web::Data<MediaUrls>,
   req: HttpRequest,
-  _bucket: web::Data<Bucket>,
+  query: web::Query<SearchItemsRequest>,
 ) -> Result<HttpResponse, Error> {
   let ext = req.extensions();
   let user_id: i64 = ext.get::<i64>().unwrap().to_owned();
+  let query = query.into_inner();
 
   let items = web::block(move || -> Result<GetItemsResponse, RouteError> {
+    let near = parse_search_position(&query)?;
+    let mut filter = ItemFilter {
+      category_id: query.category_id,
+      karat_id: query.karat_id,
+      geofence_id: query.geofence_id,
+      min_price: query.min_price,
+      max_price: query.max_price,
+      attributes: parse_attribute_filters(query.attributes.as_deref().unwrap_or_default())?,
+      area: near.map(|(latitude, longitude, radius_km)| {
+        BoundingBox::around(latitude, longitude, radius_km)
+      }),
+      geofence_ids: None,
+    };
     if let Ok(mut conn) = pool.get() {
+      // an explicit region or position replaces the home region
+      if filter.geofence_id.is_none() && near.is_none() {
+        let user = get_user_by_id(&mut conn, user_id)?;
+        filter.geofence_ids = get_feed_region_ids(
+          &mut conn,
+          user.home_region_id,
+          query.scope.as_deref().unwrap_or(HOME_FEED_SCOPE),
+        )?;
+      }
       // verify user exists the user
       let mut resp = GetItemsResponse { items: vec![] };
-      let items = get_all_visible(&mut conn)?;
-      for item in items {
-        if item.owner_id == user_id {
+      let blocked_user_ids: HashSet<i64> = get_blocked_user_ids(&mut conn, user_id)?
+        .into_iter()
+        .collect();
+      let mut items: Vec<(Item, Option<f64>)> = get_all_visible(&mut conn, &filter)?
+        .into_iter()
+        .map(|item| {
+          let distance = match (near, item.latitude, item.longitude) {
+            (Some((latitude, longitude, _)), Some(item_latitude), Some(item_longitude)) => Some(
+              distance_km(latitude, longitude, item_latitude, item_longitude),
+            ),
+            _ => None,
+          };
+          (item, distance)
+        })
+        .collect();
+      // the bounding box also matches its corners, outside of the radius
+      if let Some((_, _, radius_km)) = near {
+        items.retain(|(_, distance)| distance.is_some_and(|distance| distance <= radius_km));
+        items.sort_by(|(_, a), (_, b)| a.unwrap_or_default().total_cmp(&b.unwrap_or_default()));
+      }
+      for (item, distance_km) in items {
+        if item.owner_id == user_id || blocked_user_ids.contains(&item.owner_id) {
           continue;
         }
         let docs = get_docs_for_item(&mut conn, item.id)?;
         for doc in docs {
           if doc.is_cover && doc.uploaded_to_cloud {
             let item_status = match item.item_status.as_str() {
+              "Draft" => ItemStatus::Draft,
               "Active" => ItemStatus::Active,
               "Sold" => ItemStatus::Sold,
+              "Expired" => ItemStatus::Expired,
               _ => ItemStatus::Reserved,
             };
             resp.items.push(GetItemResponse {
@@ -110,11 +205,8 @@ pub async fn get_items(
               item_status,
               owner_id: item.owner_id,
               created_at: item.created_at.timestamp(),
-              // TODO: create presigned url for cloudfront
-              thumbnail: format!(
-                "https://{}/{}",
-                CLOUD_FRONT_DISTRIBUTION_DOMAIN_NAME, doc.key,
-              ),
+              thumbnail: media_urls.optional_url(doc.thumbnail())?,
+              distance_km,
             });
 
             // We will return only after getting the cover
@@ -135,21 +227,31 @@ pub async fn get_items(
 #[get("/items/{item_id}")]
 pub async fn get_item(
   pool: web::Data<DbPool>,
+  media_urls: web::Data<MediaUrls>,
   item_id: Path<i64>,
   req: HttpRequest,
 ) -> Result<HttpResponse, Error> {
   let ext = req.extensions();
   let user_id: i64 = ext.get::<i64>().unwrap().to_owned();
+  let locale = get_locale(&req);
   let item_response = web::block(move || -> Result<ItemResponse, RouteError> {
     if let Ok(mut conn) = pool.get() {
       // verify user exists
       let item = get_item_by_id(&mut conn, item_id.into_inner())?;
+      // drafts are only visible to their owner
+      if item.item_status == DRAFT_ITEM_STATUS && user_id != item.owner_id {
+        return Err(RouteError::DbError(DieselError::NotFound));
+      }
       if item.is_hidden && user_id != item.owner_id {
         return Err(RouteError::BadRequest(format!(
           "item is hidden, item id: {}",
           item.id
         )));
       }
+      if user_id != item.owner_id {
+        increment_seen_count(&mut conn, item.id)?;
+        record_item_event(&mut conn, item.id, user_id, VIEW_EVENT);
+      }
       let item_owner = get_user_by_id(&mut conn, item.owner_id)?;
       let user_favorite = get_favorite_item_by_user_id_and_item_id(&mut conn, user_id, item.id);
       let mut is_user_favorite = false;
@@ -157,10 +259,13 @@ pub async fn get_item(
         is_user_favorite = user_favorite.unwrap().is_favorite;
       }
       let item_status = match item.item_status.as_str() {
+        "Draft" => ItemStatus::Draft,
         "Active" => ItemStatus::Active,
         "Sold" => ItemStatus::Sold,
+        "Expired" => ItemStatus::Expired,
         _ => ItemStatus::Reserved,
       };
+      let translations = Translations::load(&mut conn, locale)?;
       let buyer_id = match get_purchase_for_item(&mut conn, item.id) {
         Ok(purchase) => Some(purchase.buyer_id),
         Err(_) => None,
@@ -184,26 +289,66 @@ pub async fn get_item(
         item_status: item_status,
         is_hidden: item.is_hidden,
         negotiable: item.negotiable,
-        location: None,
+        location: match (item.latitude, item.longitude) {
+          (Some(latitude), Some(longitude)) => Some(Location {
+            latitude,
+            longitude,
+          }),
+          _ => None,
+        },
         created_at: item.created_at.timestamp(),
         images: vec![],
+        medium_images: vec![],
         buyer_id,
+        price_history: get_price_history(&mut conn, item.id)?
+          .into_iter()
+          .map(|change| PriceChange {
+            old_price: change.old_price,
+            new_price: change.new_price,
+            changed_at: change.created_at.timestamp(),
+          })
+          .collect(),
+        attributes: get_item_attributes(&mut conn, item.id)?
+          .into_iter()
+          .map(|(value, attribute)| ItemAttributeResponse {
+            attribute_id: attribute.id,
+            name: attribute.name,
+            value: value.value,
+            unit: attribute.unit,
+          })
+          .collect(),
+        category: to_catalog_entry(
+          &translations,
+          CATEGORY_ENTITY,
+          item.category_id,
+          &get_category_by_id(&mut conn, item.category_id)?.name,
+        ),
+        karat: to_catalog_entry(
+          &translations,
+          KARAT_ENTITY,
+          item.karat_id,
+          &get_karat_by_id(&mut conn, item.karat_id)?.name,
+        ),
+        region: to_catalog_entry(
+          &translations,
+          GEOFENCE_ENTITY,
+          item.geofence_id,
+          &get_geofence_by_id(&mut conn, item.geofence_id)?.name,
+        ),
       };
       let docs = get_docs_for_item(&mut conn, item.id)?;
-      if docs.len() == 0 {
+      if docs.len() == 0 && item.item_status != DRAFT_ITEM_STATUS {
         warn!("No cover image for item: {}", item.id);
         return Err(RouteError::NoCoverImage);
       }
       for doc in docs {
-        if doc.uploaded_to_cloud {
-          resp.images.push(format!(
-            "https://{}/{}",
-            CLOUD_FRONT_DISTRIBUTION_DOMAIN_NAME, doc.key
-          ));
+        if let (Some(original), Some(medium)) = (doc.original(), doc.medium()) {
+          resp.images.push(media_urls.url(original)?);
+          resp.medium_images.push(media_urls.url(medium)?);
         }
       }
       // TODO: get the user image
-      resp.owner.avatar = resp.images[0].to_owned();
+      resp.owner.avatar = resp.images.first().cloned().unwrap_or_default();
       return Ok(resp);
     }
     return Err(RouteError::PoolingErr);
@@ -214,9 +359,146 @@ pub async fn get_item(
   Ok(HttpResponse::Ok().json(item_response))
 }
 
+#[post("/items/{item_id}/update")]
+pub async fn update_item(
+  pool: web::Data<DbPool>,
+  srv: web::Data<Addr<Lobby>>,
+  req: HttpRequest,
+  item_id: Path<i64>,
+  form: Json<UpdateItemRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let _item_id = item_id.into_inner();
+  let form = form.into_inner();
+  web::block(move || -> Result<(), RouteError> {
+    validate_update_item_request(&form)?;
+    if let Ok(mut conn) = pool.get() {
+      let item = get_item_by_id(&mut conn, _item_id)?;
+      if item.owner_id != user_id {
+        return Err(RouteError::Unauthorized);
+      }
+      let geofence_id = resolve_item_region(&mut conn, form.geofence_id, form.location.as_ref())?;
+      let location = to_item_location(form.location.as_ref())?;
+      let attributes = match form.attributes {
+        Some(ref values) => Some(validate_item_attributes(
+          &mut conn,
+          form.category_id.unwrap_or(item.category_id),
+          values,
+        )?),
+        None => None,
+      };
+      let changes = UpdateItem {
+        title: form.title,
+        description: form.description,
+        price: form.price,
+        negotiable: form.negotiable,
+        size: form.size,
+        weight: form.weight,
+        karat_id: form.karat_id,
+        category_id: form.category_id,
+        geofence_id,
+        latitude: location.map(|(latitude, _)| latitude),
+        longitude: location.map(|(_, longitude)| longitude),
+      };
+      let (old_price, updated_item) = conn.transaction::<_, RouteError, _>(|conn| {
+        // the price the history starts from must be the one this update replaces
+        let old_price = get_item_by_id_for_update(conn, _item_id)?.price;
+        let updated_item = repo_update_item(conn, _item_id, user_id, &changes)?;
+        if updated_item.price != old_price {
+          add_price_change(conn, item.id, old_price, updated_item.price)?;
+        }
+        match attributes {
+          Some(ref attributes) => {
+            set_item_attributes(conn, item.id, attributes)?;
+          }
+          // the values were for the attributes of the old category
+          None if updated_item.category_id != item.category_id => {
+            delete_item_attributes(conn, item.id)?;
+          }
+          None => {}
+        }
+        // published items must keep passing the checks of publishing
+        if updated_item.item_status != DRAFT_ITEM_STATUS {
+          let mut missing_fields: Vec<String> = get_missing_fields(&updated_item)
+            .into_iter()
+            .map(String::from)
+            .collect();
+          missing_fields.extend(get_missing_attributes(conn, &updated_item)?);
+          if !missing_fields.is_empty() {
+            return Err(RouteError::BadRequest(format!(
+              "missing required fields: {}",
+              missing_fields.join(", ")
+            )));
+          }
+        }
+        Ok((old_price, updated_item))
+      })?;
+      if updated_item.price < old_price {
+        notify_price_drop(&mut conn, &srv, &updated_item, old_price);
+      }
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+// values no item can have, drafts included, published items are checked against
+// the rules of publishing once updated
+fn validate_update_item_request(form: &UpdateItemRequest) -> Result<(), RouteError> {
+  if form
+    .title
+    .as_ref()
+    .is_some_and(|title| title.trim().is_empty())
+  {
+    return Err(RouteError::BadRequest("title can not be empty".to_string()));
+  }
+  if form.price.is_some_and(|price| price < 0) {
+    return Err(RouteError::BadRequest(
+      "price can not be negative".to_string(),
+    ));
+  }
+  if form.weight.is_some_and(|weight| weight < 0.0) {
+    return Err(RouteError::BadRequest(
+      "weight can not be negative".to_string(),
+    ));
+  }
+  Ok(())
+}
+
+// everyone who favorited the item hears about a lower price, the update is
+// already committed so failures are only logged
+fn notify_price_drop(conn: &mut PgConnection, srv: &Addr<Lobby>, item: &Item, old_price: i64) {
+  let favorites = match get_favorites_for_item(conn, item.id) {
+    Ok(favorites) => favorites,
+    Err(e) => {
+      warn!("failed to load favorites of item {}: {}", item.id, e);
+      return;
+    }
+  };
+  for favorite in favorites {
+    if favorite.user_id == item.owner_id {
+      continue;
+    }
+    let new_notification = InsertNotification {
+      user_id: favorite.user_id,
+      notification_type: ITEM_PRICE_DROPPED_NOTIFICATION.to_string(),
+      title: format!("{} is now {}", item.title, item.price),
+      body: format!("Price dropped from {} to {}", old_price, item.price),
+      item_id: Some(item.id),
+      room_id: None,
+    };
+    try_notify_user(conn, srv, &new_notification);
+  }
+}
+
 #[post("/items/{item_id}/status")]
 pub async fn new_item_status(
   pool: web::Data<DbPool>,
+  srv: web::Data<Addr<Lobby>>,
   item_id: Path<i64>,
   req: HttpRequest,
   form: Json<UpdateItemStatusRequest>,
@@ -227,12 +509,47 @@ pub async fn new_item_status(
   let new_item_status = match form.new_item_status {
     ItemStatus::Active => "Active",
     ItemStatus::Reserved => "Reserved",
-    _ => "Sold",
+    ItemStatus::Sold => "Sold",
+    ItemStatus::Expired => {
+      return Err(actix_web::error::ErrorBadRequest(
+        "items expire on their own, renew the item instead",
+      ))
+    }
+    ItemStatus::Draft => {
+      return Err(actix_web::error::ErrorBadRequest(
+        "published items can not become drafts again",
+      ))
+    }
   };
   web::block(move || -> Result<(), RouteError> {
     if let Ok(mut conn) = pool.get() {
-      // verify user exists the user
+      if get_item_by_id(&mut conn, _item_id)?.item_status == DRAFT_ITEM_STATUS {
+        return Err(RouteError::BadRequest(
+          "draft items have to be published first".to_string(),
+        ));
+      }
       update_item_status(&mut conn, _item_id, user_id, new_item_status.to_string())?;
+      // reserving an item for a buyer is how sellers accept an offer
+      if new_item_status == "Reserved" {
+        record_item_event(&mut conn, _item_id, user_id, OFFER_EVENT);
+      }
+
+      // let everyone who favorited the item know about the change
+      let item = get_item_by_id(&mut conn, _item_id)?;
+      for favorite in get_favorites_for_item(&mut conn, item.id)? {
+        if favorite.user_id == item.owner_id {
+          continue;
+        }
+        let new_notification = InsertNotification {
+          user_id: favorite.user_id,
+          notification_type: ITEM_STATUS_CHANGED_NOTIFICATION.to_string(),
+          title: format!("{} is now {}", item.title, new_item_status),
+          body: item.description.clone(),
+          item_id: Some(item.id),
+          room_id: None,
+        };
+        try_notify_user(&mut conn, &srv, &new_notification);
+      }
       return Ok(());
     }
     return Err(RouteError::PoolingErr);
@@ -246,13 +563,23 @@ pub async fn new_item_status(
 #[post("/items/{item_id}/hide")]
 pub async fn hide_or_unhide_item(
   pool: web::Data<DbPool>,
+  req: HttpRequest,
   item_id: Path<i64>,
   form: Json<HideUnhideItemRequest>,
 ) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
   let _item_id = item_id.into_inner();
   web::block(move || -> Result<(), RouteError> {
     if let Ok(mut conn) = pool.get() {
-      // verify user exists the user
+      let item = get_item_by_id(&mut conn, _item_id)?;
+      if item.owner_id != user_id {
+        return Err(RouteError::Unauthorized);
+      }
+      if item.hidden_by_moderator && !form.is_hidden {
+        return Err(RouteError::BadRequest(
+          "item was hidden by a moderator".to_string(),
+        ));
+      }
       hide_unhide_item(&mut conn, _item_id, form.is_hidden)?;
       return Ok(());
     }
@@ -264,9 +591,219 @@ pub async fn hide_or_unhide_item(
   Ok(HttpResponse::Ok().body("OK"))
 }
 
+#[post("/items/{item_id}/publish")]
+pub async fn publish_item(
+  pool: web::Data<DbPool>,
+  srv: web::Data<Addr<Lobby>>,
+  listing_config: web::Data<ListingConfig>,
+  req: HttpRequest,
+  item_id: Path<i64>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let _item_id = item_id.into_inner();
+  let resp = web::block(move || -> Result<ItemListingResponse, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      let item = get_item_by_id(&mut conn, _item_id)?;
+      if item.owner_id != user_id {
+        return Err(RouteError::Unauthorized);
+      }
+      if item.item_status != DRAFT_ITEM_STATUS {
+        return Err(RouteError::BadRequest(
+          "item is already published".to_string(),
+        ));
+      }
+      let mut missing_fields: Vec<String> = get_missing_fields(&item)
+        .into_iter()
+        .map(String::from)
+        .collect();
+      missing_fields.extend(get_missing_attributes(&mut conn, &item)?);
+      if !missing_fields.is_empty() {
+        return Err(RouteError::BadRequest(format!(
+          "missing required fields: {}",
+          missing_fields.join(", ")
+        )));
+      }
+      let docs = get_docs_for_item(&mut conn, item.id)?;
+      if !docs.iter().any(|doc| doc.is_cover && doc.uploaded_to_cloud) {
+        return Err(RouteError::NoCoverImage);
+      }
+      let item = repo_publish_item(
+        &mut conn,
+        _item_id,
+        user_id,
+        new_naive_date() + listing_config.lifetime,
+      )?;
+      notify_saved_search_matches(&mut conn, &srv, &item);
+      return Ok(ItemListingResponse {
+        id: item.id,
+        bumped_at: item.bumped_at.timestamp(),
+        expires_at: item.expires_at.timestamp(),
+      });
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+fn to_catalog_entry(
+  translations: &Translations,
+  entity_type: &str,
+  entity_id: i64,
+  name: &str,
+) -> CatalogEntry {
+  CatalogEntry {
+    id: entity_id,
+    name: translations.name(entity_type, entity_id, name),
+  }
+}
+
+// only the fuzzed location of an item is stored
+fn to_item_location(location: Option<&Location>) -> Result<Option<(f64, f64)>, RouteError> {
+  match location {
+    Some(location) if !is_valid_coordinate(location.latitude, location.longitude) => {
+      Err(RouteError::BadRequest(format!(
+        "invalid coordinate: {}, {}",
+        location.latitude, location.longitude
+      )))
+    }
+    Some(location) => Ok(Some(fuzz_location(location.latitude, location.longitude))),
+    None => Ok(None),
+  }
+}
+
+// the position and radius of a "near me" search, both coordinates have to be given
+fn parse_search_position(
+  query: &SearchItemsRequest,
+) -> Result<Option<(f64, f64, f64)>, RouteError> {
+  let (latitude, longitude) = match (query.latitude, query.longitude) {
+    (Some(latitude), Some(longitude)) => (latitude, longitude),
+    (None, None) if query.radius_km.is_none() => return Ok(None),
+    _ => {
+      return Err(RouteError::BadRequest(
+        "latitude and longitude are required to search around a position".to_string(),
+      ))
+    }
+  };
+  if !is_valid_coordinate(latitude, longitude) {
+    return Err(RouteError::BadRequest(format!(
+      "invalid coordinate: {}, {}",
+      latitude, longitude
+    )));
+  }
+  let radius_km = query.radius_km.unwrap_or(DEFAULT_SEARCH_RADIUS_KM);
+  if !(radius_km > 0.0 && radius_km <= MAX_SEARCH_RADIUS_KM) {
+    return Err(RouteError::BadRequest(format!(
+      "radius must be between 0 and {} km",
+      MAX_SEARCH_RADIUS_KM
+    )));
+  }
+  Ok(Some((latitude, longitude, radius_km)))
+}
+
+fn get_missing_fields(item: &Item) -> Vec<&'static str> {
+  let mut missing_fields = vec![];
+  if item.title.trim().is_empty() {
+    missing_fields.push("title");
+  }
+  if item.description.trim().is_empty() {
+    missing_fields.push("description");
+  }
+  if item.price <= 0 {
+    missing_fields.push("price");
+  }
+  if item.weight <= 0.0 {
+    missing_fields.push("weight");
+  }
+  missing_fields
+}
+
+#[post("/items/{item_id}/renew")]
+pub async fn renew_item(
+  pool: web::Data<DbPool>,
+  listing_config: web::Data<ListingConfig>,
+  req: HttpRequest,
+  item_id: Path<i64>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let _item_id = item_id.into_inner();
+  let resp = web::block(move || -> Result<ItemListingResponse, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      let item = get_item_by_id(&mut conn, _item_id)?;
+      if item.owner_id != user_id {
+        return Err(RouteError::Unauthorized);
+      }
+      // only expired items and the ones the owner was warned about can be renewed
+      let now = new_naive_date();
+      if item.expires_at > now + listing_config.expiry_notice {
+        return Err(RouteError::BadRequest(
+          "item does not expire soon".to_string(),
+        ));
+      }
+      let item = repo_renew_item(&mut conn, _item_id, user_id, now + listing_config.lifetime)?;
+      return Ok(ItemListingResponse {
+        id: item.id,
+        bumped_at: item.bumped_at.timestamp(),
+        expires_at: item.expires_at.timestamp(),
+      });
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+#[post("/items/{item_id}/bump")]
+pub async fn bump_item(
+  pool: web::Data<DbPool>,
+  listing_config: web::Data<ListingConfig>,
+  req: HttpRequest,
+  item_id: Path<i64>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let _item_id = item_id.into_inner();
+  let resp = web::block(move || -> Result<ItemListingResponse, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      let item = get_item_by_id(&mut conn, _item_id)?;
+      if item.owner_id != user_id {
+        return Err(RouteError::Unauthorized);
+      }
+      let now = new_naive_date();
+      if item.item_status != ACTIVE_ITEM_STATUS || item.expires_at <= now {
+        return Err(RouteError::BadRequest(
+          "only active items can be bumped".to_string(),
+        ));
+      }
+      let next_bump_at = item.bumped_at + listing_config.bump_interval;
+      if next_bump_at > now {
+        return Err(RouteError::BadRequest(format!(
+          "item can be bumped again in {} minutes",
+          (next_bump_at - now).num_minutes() + 1
+        )));
+      }
+      let item = repo_bump_item(&mut conn, _item_id, user_id)?;
+      return Ok(ItemListingResponse {
+        id: item.id,
+        bumped_at: item.bumped_at.timestamp(),
+        expires_at: item.expires_at.timestamp(),
+      });
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
 #[post("/items/{item_id}/favorite")]
 pub async fn update_favorite_status(
   pool: web::Data<DbPool>,
+  srv: web::Data<Addr<Lobby>>,
   req: HttpRequest,
   item_id: Path<i64>,
 ) -> Result<HttpResponse, Error> {
@@ -274,6 +811,11 @@ pub async fn update_favorite_status(
   let user_id: i64 = ext.get::<i64>().unwrap().to_owned();
   web::block(move || {
     if let Ok(mut conn) = pool.get() {
+      let item = get_item_by_id(&mut conn, *item_id)?;
+      // drafts are only visible to their owner
+      if item.item_status == DRAFT_ITEM_STATUS && user_id != item.owner_id {
+        return Err(RouteError::DbError(DieselError::NotFound));
+      }
       let user_favorite =
         get_favorite_item_by_user_id_and_item_id(&mut conn, user_id, item_id.to_owned());
       let mut is_favorite = true;
@@ -288,6 +830,21 @@ pub async fn update_favorite_status(
       }
       let count = if is_favorite { 1 } else { -1 };
       update_favorite_count(&mut conn, *item_id, count)?;
+      if is_favorite {
+        record_item_event(&mut conn, *item_id, user_id, FAVORITE_EVENT);
+
+        if item.owner_id != user_id {
+          let new_notification = InsertNotification {
+            user_id: item.owner_id,
+            notification_type: ITEM_FAVORITED_NOTIFICATION.to_string(),
+            title: "Someone liked your item".to_string(),
+            body: item.title,
+            item_id: Some(item.id),
+            room_id: None,
+          };
+          try_notify_user(&mut conn, &srv, &new_notification);
+        }
+      }
       return Ok(());
     }
     return Err(RouteError::PoolingErr);
@@ -301,6 +858,7 @@ pub async fn update_favorite_status(
 #[get("/items/{item_id}/buyers")]
 pub async fn get_item_buyers(
   pool: web::Data<DbPool>,
+  media_urls: web::Data<MediaUrls>,
   req: HttpRequest,
   item_id: Path<i64>,
 ) -> Result<HttpResponse, Error> {
@@ -311,15 +869,10 @@ pub async fn get_item_buyers(
       let buyers = get_all_buyers_for_item(&mut conn, item_id.to_owned(), user_id)?;
       let mut resp = vec![];
       for buyer in buyers {
-        let avatar = if buyer.user_image != None {
-          Some(format!(
-            "https://{}/{}",
-            CLOUD_FRONT_DISTRIBUTION_DOMAIN_NAME,
-            buyer.user_image.unwrap()
-          ))
-        } else {
-          None
-        };
+        let avatar = buyer
+          .user_image
+          .map(|user_image| media_urls.url(&user_image))
+          .transpose()?;
         resp.push(Buyer {
           id: buyer.id,
           name: buyer.user_name,
@@ -339,6 +892,7 @@ pub async fn get_item_buyers(
 #[post("/items/{item_id}/purchase")]
 pub async fn create_purchase(
   pool: web::Data<DbPool>,
+  srv: web::Data<Addr<Lobby>>,
   req: HttpRequest,
   item_id: Path<i64>,
   form: Json<CreatePurchaseRequest>,
@@ -349,6 +903,18 @@ pub async fn create_purchase(
   web::block(move || {
     if let Ok(mut conn) = pool.get() {
       repo_create_purchase(&mut conn, buyer_id, user_id, item_id.to_owned())?;
+      record_item_event(&mut conn, *item_id, buyer_id, SALE_EVENT);
+
+      let item = get_item_by_id(&mut conn, *item_id)?;
+      let new_notification = InsertNotification {
+        user_id: buyer_id,
+        notification_type: ITEM_PURCHASED_NOTIFICATION.to_string(),
+        title: "Purchase confirmed".to_string(),
+        body: item.title,
+        item_id: Some(item.id),
+        room_id: None,
+      };
+      try_notify_user(&mut conn, &srv, &new_notification);
       return Ok(());
     }
     return Err(RouteError::PoolingErr);
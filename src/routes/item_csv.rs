@@ -0,0 +1,82 @@
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{get, post, web, Error, HttpMessage, HttpRequest, HttpResponse};
+
+use super::models::{ImportItemsResponse, ImportRowError};
+use super::DbPool;
+use super::{route_error_handler, RouteError};
+use crate::item_csv::{
+  export_items as csv_export_items, import_items as csv_import_items, CsvError,
+};
+
+// the body is the csv file, imported items are drafts the user publishes one by one
+#[post("/items/import")]
+pub async fn import_items(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  body: String,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let resp = web::block(move || -> Result<Result<Vec<i64>, CsvError>, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      return match csv_import_items(&mut conn, user_id, body.as_bytes()) {
+        Ok(items) => Ok(Ok(items.into_iter().map(|item| item.id).collect())),
+        Err(CsvError::DbError(e)) => Err(RouteError::DbError(e)),
+        Err(e) => Ok(Err(e)),
+      };
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  match resp {
+    Ok(item_ids) => Ok(HttpResponse::Ok().json(ImportItemsResponse {
+      item_ids,
+      errors: vec![],
+    })),
+    Err(CsvError::InvalidRows(errors)) => Ok(
+      HttpResponse::BadRequest().json(ImportItemsResponse {
+        item_ids: vec![],
+        errors: errors
+          .into_iter()
+          .map(|error| ImportRowError {
+            row: error.row,
+            message: error.message,
+          })
+          .collect(),
+      }),
+    ),
+    Err(e) => Err(route_error_handler(RouteError::BadRequest(e.to_string()))),
+  }
+}
+
+#[get("/users/items/export")]
+pub async fn export_items(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let resp = web::block(move || -> Result<Vec<u8>, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      let mut output = vec![];
+      return match csv_export_items(&mut conn, user_id, &mut output) {
+        Ok(()) => Ok(output),
+        Err(CsvError::DbError(e)) => Err(RouteError::DbError(e)),
+        Err(_) => Err(RouteError::InternalErr),
+      };
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(
+    HttpResponse::Ok()
+      .content_type("text/csv")
+      .insert_header(ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters: vec![DispositionParam::Filename("items.csv".to_string())],
+      })
+      .body(resp),
+  )
+}
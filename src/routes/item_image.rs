@@ -1,23 +1,50 @@
-use actix_web::{post, web, Error, HttpMessage, HttpRequest, HttpResponse};
-use s3::bucket::Bucket;
+use actix_web::{delete, post, web, Error, HttpMessage, HttpRequest, HttpResponse};
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use log::warn;
 
 use super::models::{
   CreateItemImagesRequest, CreateItemImagesResponse, ItemImage,
-  ItemImagesUpdateStatusToUploadedRequest,
+  ItemImagesUpdateStatusToUploadedRequest, ReorderItemImagesRequest, StorageEventRequest,
 };
 use super::DbPool;
 use super::{route_error_handler, RouteError};
-use crate::helpers::get_timestamp_as_nano;
-use crate::repository::item::get_item_by_id;
-use crate::repository::item_image::{insert_new_image, set_to_uploaded_to_cloud};
+use crate::helpers::{get_env_or, get_timestamp_as_nano};
+use crate::repository::item::{get_item_by_id, DRAFT_ITEM_STATUS};
+use crate::repository::item_image::{
+  confirm_image_upload, get_docs_for_item, get_image_by_id, get_image_by_key,
+  get_next_image_position, insert_new_image, remove_item_image, set_cover_image,
+  set_image_positions, ItemImage as ItemImageRow,
+};
 use crate::repository::user::get_user_by_id;
+use crate::storage::{delete_objects, ObjectStorage, UploadConstraints};
+
+const IMAGE_UPLOAD_EXPIRATION_SECONDS: u32 = 15 * 60;
+pub const MAX_IMAGE_SIZE_BYTES: u64 = 10 * 1024 * 1024;
+pub const MAX_IMAGES_PER_ITEM: usize = 10;
+const MAX_FILE_NAME_LENGTH: usize = 64;
+pub const ALLOWED_IMAGE_CONTENT_TYPES: [&str; 3] = ["image/jpeg", "image/png", "image/webp"];
+const STORAGE_EVENT_SECRET_HEADER: &str = "X-Storage-Event-Secret";
+
+#[derive(Debug, Clone)]
+pub struct StorageEventConfig {
+  // storage events are refused when not set
+  pub secret: Option<String>,
+}
 
-const IMAGE_UPLOAD_EXPIRATION_SECONDS: u32 = 4000;
+impl StorageEventConfig {
+  pub fn from_env() -> StorageEventConfig {
+    let secret = get_env_or("STORAGE_EVENT_SECRET", "");
+    StorageEventConfig {
+      secret: (!secret.is_empty()).then_some(secret),
+    }
+  }
+}
 
 #[post("/images/item/create")]
 pub async fn create_upload_presigned_url(
   pool: web::Data<DbPool>,
-  bucket: web::Data<Bucket>,
+  storage: web::Data<dyn ObjectStorage>,
   form: web::Json<CreateItemImagesRequest>,
   req: HttpRequest,
 ) -> Result<HttpResponse, Error> {
@@ -37,26 +64,42 @@ pub async fn create_upload_presigned_url(
         return Err(RouteError::Unauthorized);
       }
 
-      // TODO: validate item does not exist already in the item_image table
+      let image_count = get_docs_for_item(&mut conn, item.id)?.len();
+      if image_count + form.images.len() > MAX_IMAGES_PER_ITEM {
+        return Err(RouteError::BadRequest(format!(
+          "an item can have at most {} images",
+          MAX_IMAGES_PER_ITEM
+        )));
+      }
+      // every image is checked before any is created
+      let constraints = form
+        .images
+        .iter()
+        .map(|image| image_upload_constraints(&image.content_type))
+        .collect::<Result<Vec<_>, _>>()?;
+
+      // the first image asked to be the cover replaces the current one once it is
+      // uploaded, until then the item keeps its cover
+      let cover_index = form
+        .images
+        .iter()
+        .position(|image| image.is_cover.unwrap_or(false));
+      let mut next_position = get_next_image_position(&mut conn, item.id)?;
 
       // insert images
       let mut resp: Vec<ItemImage> = vec![];
-      for image in form.images.to_owned().into_iter() {
+      for (index, image) in form.images.to_owned().into_iter().enumerate() {
         let object_name = format!(
           "images/{0}/{1}-{2}",
           user.id,
           get_timestamp_as_nano(),
-          image.name
+          sanitize_file_name(&image.name, &image.content_type)
         );
 
         // create the presigned url
-        match bucket.presign_put(
-          format!("{0}", &object_name),
-          IMAGE_UPLOAD_EXPIRATION_SECONDS,
-          None,
-        ) {
-          Ok(url) => {
-            let is_cover = image.is_cover.unwrap_or(false);
+        match storage.presign_upload(&object_name, &constraints[index]) {
+          Ok(upload) => {
+            let is_cover = cover_index == Some(index);
             let item_image = insert_new_image(
               &mut conn,
               user_id,
@@ -64,10 +107,14 @@ pub async fn create_upload_presigned_url(
               object_name.clone(),
               false,
               is_cover,
+              next_position,
             )?;
+            next_position += 1;
             resp.push(ItemImage {
               key: object_name,
-              url: url,
+              url: upload.url,
+              method: upload.method,
+              fields: upload.fields,
               is_cover: is_cover,
               name: image.name,
               id: item_image.id,
@@ -90,9 +137,11 @@ pub async fn create_upload_presigned_url(
   Ok(HttpResponse::Ok().json(CreateItemImagesResponse { images: resp }))
 }
 
+// the images are only marked as uploaded once every object is found in the storage
 #[post("/images/item/uploaded")]
 pub async fn update_status(
   pool: web::Data<DbPool>,
+  storage: web::Data<dyn ObjectStorage>,
   req: HttpRequest,
   form: web::Json<ItemImagesUpdateStatusToUploadedRequest>,
 ) -> Result<HttpResponse, Error> {
@@ -104,9 +153,19 @@ pub async fn update_status(
       // verify user exists the user
       get_user_by_id(&mut conn, user_id)?;
 
-      for image_id in form.ids.to_owned().into_iter() {
-        set_to_uploaded_to_cloud(&mut conn, image_id)?;
+      for image_id in form.ids.iter() {
+        let image = get_image_by_id(&mut conn, *image_id)?;
+        if image.user_id != user_id {
+          return Err(RouteError::Unauthorized);
+        }
+        verify_uploaded_object(storage.as_ref(), &image)?;
       }
+      conn.transaction::<_, DieselError, _>(|conn| {
+        for image_id in form.ids.iter() {
+          confirm_image_upload(conn, *image_id)?;
+        }
+        Ok(())
+      })?;
       return Ok(());
     }
     return Err(RouteError::PoolingErr);
@@ -115,3 +174,338 @@ pub async fn update_status(
   .map_err(|e| route_error_handler(e))?;
   Ok(HttpResponse::Ok().body("OK"))
 }
+
+// the image is hidden at once, its objects are removed from the storage afterwards
+#[delete("/images/item/{image_id}")]
+pub async fn delete_item_image(
+  pool: web::Data<DbPool>,
+  storage: web::Data<dyn ObjectStorage>,
+  req: HttpRequest,
+  image_id: web::Path<i64>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  web::block(move || {
+    if let Ok(mut conn) = pool.get() {
+      let image = get_image_by_id(&mut conn, image_id.into_inner())?;
+      if image.user_id != user_id {
+        return Err(RouteError::Unauthorized);
+      }
+      let item = get_item_by_id(&mut conn, image.item_id)?;
+      let docs = get_docs_for_item(&mut conn, item.id)?;
+      if docs.len() == 1 && item.item_status != DRAFT_ITEM_STATUS {
+        return Err(RouteError::BadRequest(
+          "a listed item needs at least one image".to_string(),
+        ));
+      }
+      let object_names = remove_item_image(&mut conn, image)?;
+      delete_objects(storage.as_ref(), &object_names);
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+// the cover is switched in one transaction so the item always has exactly one
+#[post("/images/item/{image_id}/cover")]
+pub async fn set_item_cover_image(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  image_id: web::Path<i64>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  web::block(move || {
+    if let Ok(mut conn) = pool.get() {
+      let image = get_image_by_id(&mut conn, image_id.into_inner())?;
+      if image.user_id != user_id {
+        return Err(RouteError::Unauthorized);
+      }
+      if !image.uploaded_to_cloud {
+        return Err(RouteError::BadRequest(format!(
+          "image {} is not uploaded",
+          image.id
+        )));
+      }
+      conn
+        .transaction::<_, DieselError, _>(|conn| set_cover_image(conn, image.item_id, image.id))?;
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+// `ids` lists every image of the item in the new order
+#[post("/images/item/reorder")]
+pub async fn reorder_item_images(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  form: web::Json<ReorderItemImagesRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  web::block(move || {
+    if let Ok(mut conn) = pool.get() {
+      let item = get_item_by_id(&mut conn, form.item_id)?;
+      if item.owner_id != user_id {
+        return Err(RouteError::Unauthorized);
+      }
+      let mut image_ids: Vec<i64> = get_docs_for_item(&mut conn, item.id)?
+        .iter()
+        .map(|doc| doc.id)
+        .collect();
+      let mut requested_ids = form.ids.clone();
+      image_ids.sort_unstable();
+      requested_ids.sort_unstable();
+      if image_ids != requested_ids {
+        return Err(RouteError::BadRequest(
+          "ids must list every image of the item once".to_string(),
+        ));
+      }
+      conn
+        .transaction::<_, DieselError, _>(|conn| set_image_positions(conn, item.id, &form.ids))?;
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+// S3 event notifications of created objects, an alternative to the client calling
+// `/images/item/uploaded`. Objects that are not item images are ignored.
+#[post("/images/storage/events")]
+pub async fn receive_storage_events(
+  pool: web::Data<DbPool>,
+  storage: web::Data<dyn ObjectStorage>,
+  config: web::Data<StorageEventConfig>,
+  req: HttpRequest,
+  form: web::Json<StorageEventRequest>,
+) -> Result<HttpResponse, Error> {
+  let secret = req
+    .headers()
+    .get(STORAGE_EVENT_SECRET_HEADER)
+    .and_then(|value| value.to_str().ok());
+  if config.secret.is_none() || config.secret.as_deref() != secret {
+    return Err(route_error_handler(RouteError::Unauthorized));
+  }
+  let form = form.into_inner();
+  web::block(move || -> Result<(), RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      for record in form.records.iter() {
+        if !record.event_name.starts_with("ObjectCreated") {
+          continue;
+        }
+        let object_name = decode_event_key(&record.s3.object.key);
+        let image = match get_image_by_key(&mut conn, &object_name) {
+          Ok(image) => image,
+          Err(DieselError::NotFound) => continue,
+          Err(e) => return Err(e.into()),
+        };
+        // the event only says that something was written
+        match verify_uploaded_object(storage.as_ref(), &image) {
+          Ok(()) => confirm_image_upload(&mut conn, image.id)?,
+          Err(RouteError::BadRequest(message)) => {
+            warn!("rejected upload of image {}: {}", image.id, message);
+          }
+          Err(e) => return Err(e),
+        }
+      }
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+// the limits of an image upload, unsupported content types are refused
+pub fn image_upload_constraints(content_type: &str) -> Result<UploadConstraints, RouteError> {
+  if !ALLOWED_IMAGE_CONTENT_TYPES.contains(&content_type) {
+    return Err(RouteError::BadRequest(format!(
+      "unsupported content type: {}",
+      content_type
+    )));
+  }
+  Ok(UploadConstraints {
+    content_type: content_type.to_string(),
+    max_size: MAX_IMAGE_SIZE_BYTES,
+    expiry_secs: IMAGE_UPLOAD_EXPIRATION_SECONDS,
+  })
+}
+
+// the extension objects of an allowed content type are stored with
+pub fn image_extension(content_type: &str) -> &'static str {
+  match content_type {
+    "image/png" => "png",
+    "image/webp" => "webp",
+    _ => "jpg",
+  }
+}
+
+// A name that is safe in an object key: the base name of the client's file in
+// lowercase ASCII, other characters replaced by `-`, with the extension of the
+// content type.
+fn sanitize_file_name(name: &str, content_type: &str) -> String {
+  let base_name = name.rsplit(['/', '\\']).next().unwrap_or_default();
+  let stem = match base_name.rsplit_once('.') {
+    Some((stem, _)) if !stem.is_empty() => stem,
+    _ => base_name,
+  };
+  let mut sanitized = String::new();
+  for c in stem.chars().flat_map(char::to_lowercase) {
+    if c.is_ascii_alphanumeric() || c == '_' {
+      sanitized.push(c);
+    } else if !sanitized.is_empty() && !sanitized.ends_with('-') {
+      sanitized.push('-');
+    }
+    if sanitized.len() >= MAX_FILE_NAME_LENGTH {
+      break;
+    }
+  }
+  let sanitized = sanitized.trim_end_matches('-');
+  format!(
+    "{}.{}",
+    if sanitized.is_empty() {
+      "image"
+    } else {
+      sanitized
+    },
+    image_extension(content_type)
+  )
+}
+
+// checks that the object of the image exists and is an image within the size limit
+fn verify_uploaded_object(
+  storage: &dyn ObjectStorage,
+  image: &ItemImageRow,
+) -> Result<(), RouteError> {
+  let object = storage
+    .head(&image.key)?
+    .ok_or_else(|| RouteError::BadRequest(format!("image {} is not uploaded", image.id)))?;
+  if object.size == 0 || object.size > MAX_IMAGE_SIZE_BYTES {
+    return Err(RouteError::BadRequest(format!(
+      "image {} must be at most {} bytes",
+      image.id, MAX_IMAGE_SIZE_BYTES
+    )));
+  }
+  let content_type = object.content_type.unwrap_or_default();
+  if !ALLOWED_IMAGE_CONTENT_TYPES.contains(&content_type.as_str()) {
+    return Err(RouteError::BadRequest(format!(
+      "image {} has an unsupported content type: {}",
+      image.id, content_type
+    )));
+  }
+  Ok(())
+}
+
+// keys of S3 events are URL encoded with spaces as `+`
+fn decode_event_key(key: &str) -> String {
+  let bytes = key.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut index = 0;
+  while index < bytes.len() {
+    match bytes[index] {
+      b'+' => decoded.push(b' '),
+      b'%' if index + 2 < bytes.len() => {
+        let hex = std::str::from_utf8(&bytes[index + 1..index + 3]).unwrap_or_default();
+        // from_str_radix would also take a sign
+        match u8::from_str_radix(hex, 16) {
+          Ok(byte) if hex.bytes().all(|c| c.is_ascii_hexdigit()) => {
+            decoded.push(byte);
+            index += 2;
+          }
+          _ => decoded.push(b'%'),
+        }
+      }
+      byte => decoded.push(byte),
+    }
+    index += 1;
+  }
+  String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn decode_plus_as_space() {
+    assert_eq!(
+      decode_event_key("images/1/my+ring.jpg"),
+      "images/1/my ring.jpg"
+    );
+  }
+
+  #[test]
+  fn decode_percent_escapes() {
+    assert_eq!(
+      decode_event_key("images/1/a%2Bb%20c.jpg"),
+      "images/1/a+b c.jpg"
+    );
+    assert_eq!(decode_event_key("%d1%84%d0%be%d1%82%d0%be"), "фото");
+  }
+
+  #[test]
+  fn decode_keeps_truncated_escapes() {
+    assert_eq!(decode_event_key("ring%"), "ring%");
+    assert_eq!(decode_event_key("ring%2"), "ring%2");
+    assert_eq!(decode_event_key("ring%zz"), "ring%zz");
+    assert_eq!(decode_event_key("ring%+1"), "ring% 1");
+  }
+
+  #[test]
+  fn sanitize_keeps_the_base_name() {
+    assert_eq!(
+      sanitize_file_name("../../etc/passwd", "image/png"),
+      "passwd.png"
+    );
+    assert_eq!(
+      sanitize_file_name("C:\\Photos\\Ring 1.JPG", "image/jpeg"),
+      "ring-1.jpg"
+    );
+    assert_eq!(
+      sanitize_file_name("ring.tar.gz", "image/webp"),
+      "ring-tar.webp"
+    );
+  }
+
+  #[test]
+  fn sanitize_replaces_other_characters() {
+    assert_eq!(
+      sanitize_file_name("  gold ring!!.jpg", "image/jpeg"),
+      "gold-ring.jpg"
+    );
+    assert_eq!(
+      sanitize_file_name("кольцо_gold.png", "image/png"),
+      "_gold.png"
+    );
+    assert_eq!(sanitize_file_name("фото.jpg", "image/jpeg"), "image.jpg");
+  }
+
+  #[test]
+  fn sanitize_empty_names() {
+    assert_eq!(sanitize_file_name("", "image/jpeg"), "image.jpg");
+    assert_eq!(sanitize_file_name(".jpg", "image/png"), "jpg.png");
+    assert_eq!(sanitize_file_name("photos/", "image/jpeg"), "image.jpg");
+  }
+
+  #[test]
+  fn sanitize_caps_the_length() {
+    let name = format!("{}.jpg", "a".repeat(200));
+    assert_eq!(
+      sanitize_file_name(&name, "image/jpeg"),
+      format!("{}.jpg", "a".repeat(MAX_FILE_NAME_LENGTH))
+    );
+    let name = format!("{}-b.jpg", "a".repeat(MAX_FILE_NAME_LENGTH - 1));
+    assert_eq!(
+      sanitize_file_name(&name, "image/jpeg"),
+      format!("{}.jpg", "a".repeat(MAX_FILE_NAME_LENGTH - 1))
+    );
+  }
+}
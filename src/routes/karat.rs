@@ -1,10 +1,13 @@
-use actix_web::{delete, get, web, Error, HttpResponse};
+use actix_web::{delete, get, web, Error, HttpMessage, HttpRequest, HttpResponse};
 
+use super::locale::{get_locale, Translations};
+use super::models::KaratResponse;
 use super::DbPool;
-use super::{route_error_handler, RouteError};
+use super::{route_error_handler, verify_admin, RouteError};
 use crate::repository::karat::{
   delete_karat as repo_delete_karat, get_by_name, get_karats as repo_get_karats,
 };
+use crate::repository::translation::KARAT_ENTITY;
 
 #[get("/karats/{name}")]
 pub async fn get_karat(
@@ -27,10 +30,22 @@ pub async fn get_karat(
 }
 
 #[get("/karats")]
-pub async fn get_karats(pool: web::Data<DbPool>) -> Result<HttpResponse, Error> {
+pub async fn get_karats(pool: web::Data<DbPool>, req: HttpRequest) -> Result<HttpResponse, Error> {
+  let locale = get_locale(&req);
   let resp = web::block(move || {
     if let Ok(mut conn) = pool.get() {
-      let resp = repo_get_karats(&mut conn)?;
+      let translations = Translations::load(&mut conn, locale)?;
+      let resp: Vec<KaratResponse> = repo_get_karats(&mut conn)?
+        .into_iter()
+        .filter(|karat| karat.deleted_at.is_none())
+        .map(|karat| KaratResponse {
+          id: karat.id,
+          localized_name: translations.name(KARAT_ENTITY, karat.id, &karat.name),
+          name: karat.name,
+          description: karat.description,
+          gold_purity: karat.gold_purity,
+        })
+        .collect();
       return Ok(resp);
     }
     return Err(RouteError::PoolingErr);
@@ -43,10 +58,13 @@ pub async fn get_karats(pool: web::Data<DbPool>) -> Result<HttpResponse, Error>
 #[delete("/karats/{name}")]
 pub async fn delete_karat(
   pool: web::Data<DbPool>,
+  req: HttpRequest,
   name: web::Path<String>,
 ) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
   web::block(move || {
     if let Ok(mut conn) = pool.get() {
+      verify_admin(&mut conn, user_id)?;
       repo_delete_karat(&mut conn, name.to_string())?;
       return Ok(());
     }
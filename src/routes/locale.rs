@@ -0,0 +1,189 @@
+use actix_web::http::header::ACCEPT_LANGUAGE;
+use actix_web::{post, web, Error, HttpMessage, HttpRequest, HttpResponse};
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use std::collections::HashMap;
+
+use super::models::SetTranslationRequest;
+use super::DbPool;
+use super::{route_error_handler, verify_admin, RouteError};
+use crate::repository::category::get_category_by_id;
+use crate::repository::geofence::get_geofence_by_id;
+use crate::repository::karat::get_karat_by_id;
+use crate::repository::translation::{
+  get_translations_for_locale, upsert_translation, InsertTranslation, CATEGORY_ENTITY,
+  GEOFENCE_ENTITY, KARAT_ENTITY,
+};
+
+pub const DEFAULT_LOCALE: &str = "en";
+pub const SUPPORTED_LOCALES: [&str; 3] = ["tg", "ru", "en"];
+
+// the supported locale the client prefers the most, from a header like
+// `Accept-Language: ru-RU,ru;q=0.9,en;q=0.8`
+pub fn get_locale(req: &HttpRequest) -> &'static str {
+  let header = match req
+    .headers()
+    .get(ACCEPT_LANGUAGE)
+    .and_then(|value| value.to_str().ok())
+  {
+    Some(header) => header,
+    None => return DEFAULT_LOCALE,
+  };
+  let mut best: Option<(&'static str, f32)> = None;
+  for range in header.split(',') {
+    let mut parts = range.split(';').map(str::trim);
+    let language = parts
+      .next()
+      .unwrap_or_default()
+      .split('-')
+      .next()
+      .unwrap_or_default()
+      .to_lowercase();
+    let quality = parts
+      .find_map(|part| part.strip_prefix("q="))
+      .and_then(|quality| quality.parse::<f32>().ok())
+      .unwrap_or(1.0);
+    let locale = match SUPPORTED_LOCALES.iter().find(|locale| **locale == language) {
+      Some(locale) => *locale,
+      None => continue,
+    };
+    if quality > 0.0 && best.is_none_or(|(_, best_quality)| quality > best_quality) {
+      best = Some((locale, quality));
+    }
+  }
+  best.map_or(DEFAULT_LOCALE, |(locale, _)| locale)
+}
+
+// catalog names of one locale, the stored English name is used when missing
+pub struct Translations {
+  names: HashMap<(String, i64), String>,
+}
+
+impl Translations {
+  pub fn load(conn: &mut PgConnection, locale: &str) -> Result<Translations, DieselError> {
+    if locale == DEFAULT_LOCALE {
+      return Ok(Translations {
+        names: HashMap::new(),
+      });
+    }
+    let names = get_translations_for_locale(conn, locale)?
+      .into_iter()
+      .map(|translation| {
+        (
+          (translation.entity_type, translation.entity_id),
+          translation.name,
+        )
+      })
+      .collect();
+    Ok(Translations { names })
+  }
+
+  pub fn name(&self, entity_type: &str, entity_id: i64, name: &str) -> String {
+    self
+      .names
+      .get(&(entity_type.to_string(), entity_id))
+      .cloned()
+      .unwrap_or_else(|| name.to_string())
+  }
+}
+
+// English names are the stored ones, only the other locales are translated
+#[post("/translations/set")]
+pub async fn set_translation(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  form: web::Json<SetTranslationRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let form = form.into_inner();
+  web::block(move || -> Result<(), RouteError> {
+    if form.locale == DEFAULT_LOCALE || !SUPPORTED_LOCALES.contains(&form.locale.as_str()) {
+      return Err(RouteError::BadRequest(format!(
+        "unsupported locale: {}",
+        form.locale
+      )));
+    }
+    let name = form.name.trim().to_string();
+    if name.is_empty() {
+      return Err(RouteError::BadRequest("name is empty".to_string()));
+    }
+    if let Ok(mut conn) = pool.get() {
+      verify_admin(&mut conn, user_id)?;
+      match form.entity_type.as_str() {
+        CATEGORY_ENTITY => get_category_by_id(&mut conn, form.entity_id).map(|_| ())?,
+        KARAT_ENTITY => get_karat_by_id(&mut conn, form.entity_id).map(|_| ())?,
+        GEOFENCE_ENTITY => get_geofence_by_id(&mut conn, form.entity_id).map(|_| ())?,
+        _ => {
+          return Err(RouteError::BadRequest(format!(
+            "invalid entity type: {}",
+            form.entity_type
+          )))
+        }
+      }
+      upsert_translation(
+        &mut conn,
+        &InsertTranslation {
+          entity_type: form.entity_type,
+          entity_id: form.entity_id,
+          locale: form.locale,
+          name,
+        },
+      )?;
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use actix_web::test::TestRequest;
+
+  fn locale_of(header: &str) -> &'static str {
+    get_locale(
+      &TestRequest::default()
+        .insert_header((ACCEPT_LANGUAGE, header))
+        .to_http_request(),
+    )
+  }
+
+  #[test]
+  fn default_without_header() {
+    assert_eq!(
+      get_locale(&TestRequest::default().to_http_request()),
+      DEFAULT_LOCALE
+    );
+  }
+
+  #[test]
+  fn highest_quality_wins() {
+    assert_eq!(locale_of("en;q=0.5,ru;q=0.9"), "ru");
+    assert_eq!(locale_of("ru;q=0.8, tg"), "tg");
+    // the first of equally preferred languages
+    assert_eq!(locale_of("ru,en"), "ru");
+  }
+
+  #[test]
+  fn zero_quality_is_refused() {
+    assert_eq!(locale_of("ru;q=0,tg;q=0.1"), "tg");
+    assert_eq!(locale_of("ru;q=0"), DEFAULT_LOCALE);
+  }
+
+  #[test]
+  fn regional_tags() {
+    assert_eq!(locale_of("ru-RU,ru;q=0.9,en;q=0.8"), "ru");
+    assert_eq!(locale_of("TG-tj"), "tg");
+  }
+
+  #[test]
+  fn unsupported_languages() {
+    assert_eq!(locale_of("de-DE,fr;q=0.9"), DEFAULT_LOCALE);
+    assert_eq!(locale_of("de,ru;q=0.2"), "ru");
+    assert_eq!(locale_of("*"), DEFAULT_LOCALE);
+  }
+}
@@ -5,15 +5,30 @@ use diesel::{
 };
 use std::fmt;
 
+use crate::repository::user::{get_user_by_id, ADMIN_ROLE};
+use crate::storage::StorageError;
+
+pub mod analytics;
 pub mod auth;
 pub mod category;
+pub mod category_attribute;
+pub mod device;
 pub mod geofence;
 pub mod heartbeat;
 pub mod item;
+pub mod item_csv;
 pub mod item_image;
 pub mod karat;
+pub mod locale;
 pub mod models;
+pub mod moderation;
+pub mod notification;
+pub mod recommendation;
+pub mod report;
 pub mod room;
+pub mod saved_search;
+pub mod storage;
+pub mod user_block;
 pub mod users;
 
 pub type DbPool = r2d2::Pool<ConnectionManager<PgConnection>>;
@@ -21,6 +36,7 @@ pub type DbPool = r2d2::Pool<ConnectionManager<PgConnection>>;
 #[derive(Debug)]
 pub enum RouteError {
   DbError(DieselError),
+  StorageErr(StorageError),
   CreateJwtErr,
   Unauthorized,
   BadRequest(String),
@@ -29,6 +45,8 @@ pub enum RouteError {
   NoCoverImage,
   InvalidPassword,
   InvalidCategory,
+  UserSuspended,
+  UserBlocked,
 }
 
 unsafe impl Send for RouteError {}
@@ -39,10 +57,17 @@ impl From<DieselError> for RouteError {
   }
 }
 
+impl From<StorageError> for RouteError {
+  fn from(err: StorageError) -> RouteError {
+    RouteError::StorageErr(err)
+  }
+}
+
 impl fmt::Display for RouteError {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     match self {
       RouteError::DbError(ref e) => e.fmt(f),
+      RouteError::StorageErr(ref e) => e.fmt(f),
       RouteError::CreateJwtErr => write!(f, "Error creating jwt"),
       RouteError::Unauthorized => write!(f, "Unauthorized"),
       RouteError::InternalErr => write!(f, "Internal Server Error"),
@@ -50,6 +75,8 @@ impl fmt::Display for RouteError {
       RouteError::NoCoverImage => write!(f, "No cover image"),
       RouteError::InvalidPassword => write!(f, "Invalid password"),
       RouteError::InvalidCategory => write!(f, "Invalid category"),
+      RouteError::UserSuspended => write!(f, "User is suspended"),
+      RouteError::UserBlocked => write!(f, "User is blocked"),
       RouteError::BadRequest(mes) => write!(f, "Bad Request: {:?}", mes.as_str()),
     }
   }
@@ -67,12 +94,26 @@ fn route_error_handler(e: RouteError) -> actix_web::error::Error {
     }
     RouteError::InvalidPassword => actix_web::error::ErrorBadRequest("Invalid password"),
     RouteError::InvalidCategory => actix_web::error::ErrorBadRequest("Invalid category"),
+    RouteError::UserSuspended => actix_web::error::ErrorForbidden("User is suspended"),
+    RouteError::UserBlocked => actix_web::error::ErrorForbidden("User is blocked"),
     RouteError::BadRequest(mes) => {
       actix_web::error::ErrorBadRequest(format!("Bad request: {:?}", mes))
     }
+    RouteError::StorageErr(_) => {
+      actix_web::error::ErrorInternalServerError("Internal Server Error")
+    }
     RouteError::DbError(e) => match e {
       DieselError::NotFound => actix_web::error::ErrorNotFound("Not found"),
       e => actix_web::error::ErrorInternalServerError(format!("Internal Server Error: {}", e)),
     },
   }
 }
+
+// catalog changes are limited to admins
+fn verify_admin(conn: &mut PgConnection, user_id: i64) -> Result<(), RouteError> {
+  let user = get_user_by_id(conn, user_id)?;
+  if user.role != ADMIN_ROLE || user.is_suspended() {
+    return Err(RouteError::Unauthorized);
+  }
+  Ok(())
+}
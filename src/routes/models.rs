@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 type Timestamp = i64;
 
@@ -27,6 +28,7 @@ pub struct GetUserResponse {
   pub name: String,
   pub phone_number: String,
   pub avatar: String,
+  pub home_region_id: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -102,6 +104,7 @@ pub struct CreateItemImagesRequest {
 #[serde(rename_all(deserialize = "camelCase"))]
 pub struct CreateItemImageRequest {
   pub name: String,
+  pub content_type: String,
   pub is_cover: Option<bool>,
 }
 
@@ -117,6 +120,10 @@ pub struct ItemImage {
   pub id: i64,
   pub key: String,
   pub url: String,
+  // POST with `fields` as the form before the file, or PUT
+  pub method: String,
+  pub fields: HashMap<String, String>,
+  // becomes the cover once the upload is confirmed
   pub is_cover: bool,
   pub name: String,
 }
@@ -132,7 +139,35 @@ pub struct CreateItemRequest {
   pub weight: f64,
   pub karat_id: i64,
   pub category_id: i64,
-  pub geofence_id: i64,
+  // the region is resolved from the location when no geofence id is given
+  pub geofence_id: Option<i64>,
+  // stored approximately, see `geo::fuzz_location`
+  pub location: Option<Location>,
+  pub attributes: Option<Vec<ItemAttributeRequest>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct ItemAttributeRequest {
+  pub attribute_id: i64,
+  pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct UpdateItemRequest {
+  pub title: Option<String>,
+  pub description: Option<String>,
+  pub negotiable: Option<bool>,
+  pub price: Option<i64>,
+  pub size: Option<f64>,
+  pub weight: Option<f64>,
+  pub karat_id: Option<i64>,
+  pub category_id: Option<i64>,
+  pub geofence_id: Option<i64>,
+  pub location: Option<Location>,
+  // replaces every attribute of the item when set
+  pub attributes: Option<Vec<ItemAttributeRequest>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -148,6 +183,38 @@ pub struct ItemImagesUpdateStatusToUploadedRequest {
   pub ids: Vec<i64>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct ReorderItemImagesRequest {
+  pub item_id: i64,
+  pub ids: Vec<i64>,
+}
+
+// an S3 event notification, only the created object keys are used
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageEventRequest {
+  #[serde(rename = "Records", default)]
+  pub records: Vec<StorageEventRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageEventRecord {
+  #[serde(rename = "eventName", default)]
+  pub event_name: String,
+  pub s3: StorageEventEntity,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageEventEntity {
+  pub object: StorageEventObject,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageEventObject {
+  // URL encoded
+  pub key: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all(serialize = "camelCase"))]
 pub struct GetItemsResponse {
@@ -168,6 +235,8 @@ pub struct GetItemResponse {
   pub item_status: ItemStatus,
   pub created_at: Timestamp,
   pub thumbnail: String,
+  // set when searching around a position
+  pub distance_km: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -186,9 +255,41 @@ pub struct ItemResponse {
   pub seen_count: i32,
   pub is_user_favorite: bool,
   pub images: Vec<String>,
+  // the medium variant of each of `images`, in the same order
+  pub medium_images: Vec<String>,
   pub location: Option<Location>,
   pub created_at: Timestamp,
   pub buyer_id: Option<i64>,
+  pub price_history: Vec<PriceChange>,
+  pub attributes: Vec<ItemAttributeResponse>,
+  pub category: CatalogEntry,
+  pub karat: CatalogEntry,
+  pub region: CatalogEntry,
+}
+
+// a category, karat or region with its name in the requested locale
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct CatalogEntry {
+  pub id: i64,
+  pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct ItemAttributeResponse {
+  pub attribute_id: i64,
+  pub name: String,
+  pub value: String,
+  pub unit: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct PriceChange {
+  pub old_price: i64,
+  pub new_price: i64,
+  pub changed_at: Timestamp,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -226,13 +327,16 @@ pub struct UserItem {
   pub is_hidden: bool,
   pub created_at: Timestamp,
   pub updated_at: Timestamp,
+  pub expires_at: Timestamp,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ItemStatus {
+  Draft,
   Active,
   Sold,
   Reserved,
+  Expired,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -252,20 +356,50 @@ pub struct HideUnhideItemRequest {
 pub struct CreateCategoryRequest {
   pub name: String,
   pub avatar: String,
+  pub parent_id: Option<i64>,
+  pub position: Option<i32>,
+}
+
+// a missing parent moves the category to the root
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct MoveCategoryRequest {
+  pub parent_id: Option<i64>,
+  pub position: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct CategoryNode {
+  pub id: i64,
+  pub name: String,
+  pub localized_name: String,
+  pub avatar: String,
+  pub position: i32,
+  pub children: Vec<CategoryNode>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all(serialize = "camelCase"))]
 pub struct CreatePresignedUrlResponse {
   pub url: String,
+  pub method: String,
+  pub fields: HashMap<String, String>,
   pub image_name: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct CoverImageUploadRequest {
+  pub content_type: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all(deserialize = "camelCase"))]
 pub struct UpdateProfileRequest {
   pub image: Option<String>,
   pub name: Option<String>,
+  pub home_region_id: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -288,3 +422,398 @@ pub struct Buyers {
 pub struct CreatePurchaseRequest {
   pub buyer_id: i64,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct SellerAnalyticsRequest {
+  pub from: Option<Timestamp>,
+  pub to: Option<Timestamp>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct AnalyticsCounts {
+  pub views: i64,
+  pub favorites: i64,
+  pub rooms_opened: i64,
+  pub offers: i64,
+  pub sales: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct AnalyticsPoint {
+  // start of the day the counts belong to
+  pub date: Timestamp,
+  pub counts: AnalyticsCounts,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct ItemAnalytics {
+  pub item_id: i64,
+  pub title: String,
+  pub total: AnalyticsCounts,
+  pub series: Vec<AnalyticsPoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct SellerAnalyticsResponse {
+  pub from: Timestamp,
+  pub to: Timestamp,
+  pub total: AnalyticsCounts,
+  pub series: Vec<AnalyticsPoint>,
+  pub items: Vec<ItemAnalytics>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct SearchItemsRequest {
+  pub category_id: Option<i64>,
+  pub karat_id: Option<i64>,
+  pub geofence_id: Option<i64>,
+  pub min_price: Option<i64>,
+  pub max_price: Option<i64>,
+  // comma separated `attributeId:value` or `attributeId:min..max`, either bound is optional
+  pub attributes: Option<String>,
+  // items within the radius of the position, nearest first
+  pub latitude: Option<f64>,
+  pub longitude: Option<f64>,
+  pub radius_km: Option<f64>,
+  // without a region or a position the feed is scoped to the home region of the user,
+  // `parent` widens it to the parent region and `all` lists every region
+  pub scope: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct CreateSavedSearchRequest {
+  pub name: String,
+  pub category_id: Option<i64>,
+  pub karat_id: Option<i64>,
+  pub geofence_id: Option<i64>,
+  pub min_price: Option<i64>,
+  pub max_price: Option<i64>,
+  pub push_enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct SavedSearch {
+  pub id: i64,
+  pub name: String,
+  pub category_id: Option<i64>,
+  pub karat_id: Option<i64>,
+  pub geofence_id: Option<i64>,
+  pub min_price: Option<i64>,
+  pub max_price: Option<i64>,
+  pub push_enabled: bool,
+  pub created_at: Timestamp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct SavedSearches {
+  pub searches: Vec<SavedSearch>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct GetNotificationsRequest {
+  pub before_id: Option<i64>,
+  pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct NotificationResponse {
+  pub id: i64,
+  pub notification_type: String,
+  pub title: String,
+  pub body: String,
+  pub item_id: Option<i64>,
+  pub room_id: Option<i64>,
+  pub is_read: bool,
+  pub created_at: Timestamp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct NotificationsResponse {
+  pub notifications: Vec<NotificationResponse>,
+  pub unread_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct UnreadNotificationsCount {
+  pub unread_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum DevicePlatform {
+  Android,
+  Ios,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct RegisterDeviceRequest {
+  pub token: String,
+  pub platform: DevicePlatform,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct UnregisterDeviceRequest {
+  pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct MuteRoomRequest {
+  pub is_muted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ReportTargetType {
+  Item,
+  User,
+  Message,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ReportReason {
+  Fraud,
+  Prohibited,
+  Counterfeit,
+  Abuse,
+  Spam,
+  Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ReportStatus {
+  Open,
+  Assigned,
+  Resolved,
+  Dismissed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct CreateReportRequest {
+  pub target_type: ReportTargetType,
+  pub target_id: i64,
+  pub reason: ReportReason,
+  pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct GetReportsRequest {
+  pub status: Option<ReportStatus>,
+  pub assignee_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct AssignReportRequest {
+  // defaults to the moderator making the request
+  pub assignee_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct CloseReportRequest {
+  pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct ModerationActionRequest {
+  pub report_id: Option<i64>,
+  pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct ReportResponse {
+  pub id: i64,
+  pub reporter_id: i64,
+  pub target_type: String,
+  pub target_id: i64,
+  pub reason: String,
+  pub description: String,
+  pub status: String,
+  pub assignee_id: Option<i64>,
+  pub created_at: Timestamp,
+  pub resolved_at: Option<Timestamp>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct Reports {
+  pub reports: Vec<ReportResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct ModerationActionResponse {
+  pub id: i64,
+  pub report_id: Option<i64>,
+  pub moderator_id: i64,
+  pub action: String,
+  pub target_type: String,
+  pub target_id: i64,
+  pub note: String,
+  pub created_at: Timestamp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct ReportDetails {
+  pub report: ReportResponse,
+  pub actions: Vec<ModerationActionResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct BlockUserRequest {
+  pub user_id: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct BlockedUser {
+  pub id: i64,
+  pub name: String,
+  pub blocked_at: Timestamp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct BlockedUsers {
+  pub users: Vec<BlockedUser>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct ItemListingResponse {
+  pub id: i64,
+  pub bumped_at: Timestamp,
+  pub expires_at: Timestamp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct RecommendationsRequest {
+  pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct ImportRowError {
+  pub row: u64,
+  pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct ImportItemsResponse {
+  pub item_ids: Vec<i64>,
+  pub errors: Vec<ImportRowError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct CreateCategoryAttributeRequest {
+  pub name: String,
+  pub attribute_type: String,
+  pub unit: Option<String>,
+  pub allowed_values: Option<Vec<String>>,
+  pub required: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct CategoryAttributeResponse {
+  pub id: i64,
+  pub category_id: i64,
+  pub name: String,
+  pub attribute_type: String,
+  pub unit: Option<String>,
+  pub allowed_values: Vec<String>,
+  pub required: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct CategoryAttributes {
+  pub attributes: Vec<CategoryAttributeResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct KaratResponse {
+  pub id: i64,
+  pub name: String,
+  pub localized_name: String,
+  pub description: String,
+  pub gold_purity: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct GeofenceNode {
+  pub id: i64,
+  pub name: String,
+  pub localized_name: String,
+  pub geofence_type: String,
+  pub children: Vec<GeofenceNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct CreateGeofenceRequest {
+  pub name: String,
+  pub geofence_type: String,
+  pub parent_region_id: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct MoveGeofenceRequest {
+  pub parent_region_id: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct ResolveRegionRequest {
+  pub latitude: f64,
+  pub longitude: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct RegionResponse {
+  pub id: i64,
+  pub name: String,
+  pub localized_name: String,
+  pub geofence_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct SetTranslationRequest {
+  pub entity_type: String,
+  pub entity_id: i64,
+  pub locale: String,
+  pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalUploadQuery {
+  pub token: String,
+}
@@ -0,0 +1,386 @@
+use actix::Addr;
+use actix_web::{get, post, web, Error, HttpMessage, HttpRequest, HttpResponse};
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+
+use super::models::{
+  AssignReportRequest, CloseReportRequest, GetReportsRequest, ModerationActionRequest,
+  ModerationActionResponse, ReportDetails, ReportResponse, ReportStatus, Reports,
+};
+use super::report::{to_report_response, ITEM_TARGET, MESSAGE_TARGET, USER_TARGET};
+use super::DbPool;
+use super::{route_error_handler, RouteError};
+use crate::repository::auth::delete_all_refresh_tokens;
+use crate::repository::item::{get_item_by_id, hide_item_by_moderator};
+use crate::repository::message::delete_message;
+use crate::repository::moderation_action::{
+  add_moderation_action, get_actions_for_report, InsertModerationAction, ModerationAction,
+  ASSIGN_ACTION, DELETE_MESSAGE_ACTION, DISMISS_ACTION, HIDE_ITEM_ACTION, RESOLVE_ACTION,
+  SUSPEND_USER_ACTION,
+};
+use crate::repository::report::{
+  assign_report as repo_assign_report, close_report, get_report_by_id,
+  get_reports as repo_get_reports, ASSIGNED_REPORT_STATUS, DISMISSED_REPORT_STATUS,
+  OPEN_REPORT_STATUS, RESOLVED_REPORT_STATUS,
+};
+use crate::repository::user::{get_user_by_id, suspend_user as repo_suspend_user, User};
+use crate::ws::lobby::Lobby;
+use crate::ws::messages::DisconnectUser;
+
+const REPORT_TARGET: &str = "Report";
+
+#[get("/moderation/reports")]
+pub async fn get_reports(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  query: web::Query<GetReportsRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  // the queue shows the reports still waiting for a decision by default
+  let statuses = match query.status {
+    Some(ReportStatus::Open) => vec![OPEN_REPORT_STATUS],
+    Some(ReportStatus::Assigned) => vec![ASSIGNED_REPORT_STATUS],
+    Some(ReportStatus::Resolved) => vec![RESOLVED_REPORT_STATUS],
+    Some(ReportStatus::Dismissed) => vec![DISMISSED_REPORT_STATUS],
+    None => vec![OPEN_REPORT_STATUS, ASSIGNED_REPORT_STATUS],
+  };
+  let assignee_id = query.assignee_id;
+  let resp = web::block(move || -> Result<Reports, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      get_moderator(&mut conn, user_id)?;
+      let reports = repo_get_reports(&mut conn, &statuses, assignee_id)?;
+      return Ok(Reports {
+        reports: reports.into_iter().map(to_report_response).collect(),
+      });
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+#[get("/moderation/reports/{report_id}")]
+pub async fn get_report(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  report_id: web::Path<i64>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let report_id = report_id.into_inner();
+  let resp = web::block(move || -> Result<ReportDetails, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      get_moderator(&mut conn, user_id)?;
+      let report = get_report_by_id(&mut conn, report_id)?;
+      let actions = get_actions_for_report(&mut conn, report_id)?;
+      return Ok(ReportDetails {
+        report: to_report_response(report),
+        actions: actions
+          .into_iter()
+          .map(to_moderation_action_response)
+          .collect(),
+      });
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+#[post("/moderation/reports/{report_id}/assign")]
+pub async fn assign_report(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  report_id: web::Path<i64>,
+  form: web::Json<AssignReportRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let report_id = report_id.into_inner();
+  let assignee_id = form.assignee_id.unwrap_or(user_id);
+  let resp = web::block(move || -> Result<ReportResponse, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      get_moderator(&mut conn, user_id)?;
+      if assignee_id != user_id {
+        let assignee = get_user_by_id(&mut conn, assignee_id)?;
+        if !assignee.is_moderator() {
+          return Err(RouteError::BadRequest(
+            "assignee is not a moderator".to_string(),
+          ));
+        }
+      }
+      let report = conn.transaction::<_, DieselError, _>(|conn| {
+        let report = repo_assign_report(conn, report_id, assignee_id)?;
+        record_action(
+          conn,
+          Some(report_id),
+          user_id,
+          ASSIGN_ACTION,
+          USER_TARGET,
+          assignee_id,
+          String::new(),
+        )?;
+        Ok(report)
+      })?;
+      return Ok(to_report_response(report));
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+#[post("/moderation/reports/{report_id}/resolve")]
+pub async fn resolve_report(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  report_id: web::Path<i64>,
+  form: web::Json<CloseReportRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let note = form.into_inner().note.unwrap_or_default();
+  let resp = web::block(move || {
+    close(
+      &pool,
+      user_id,
+      report_id.into_inner(),
+      RESOLVED_REPORT_STATUS,
+      RESOLVE_ACTION,
+      note,
+    )
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+#[post("/moderation/reports/{report_id}/dismiss")]
+pub async fn dismiss_report(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  report_id: web::Path<i64>,
+  form: web::Json<CloseReportRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let note = form.into_inner().note.unwrap_or_default();
+  let resp = web::block(move || {
+    close(
+      &pool,
+      user_id,
+      report_id.into_inner(),
+      DISMISSED_REPORT_STATUS,
+      DISMISS_ACTION,
+      note,
+    )
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+#[post("/moderation/items/{item_id}/hide")]
+pub async fn hide_item(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  item_id: web::Path<i64>,
+  form: web::Json<ModerationActionRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let item_id = item_id.into_inner();
+  let form = form.into_inner();
+  let resp = web::block(move || -> Result<ModerationActionResponse, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      get_moderator(&mut conn, user_id)?;
+      verify_report(&mut conn, form.report_id)?;
+      get_item_by_id(&mut conn, item_id)?;
+      let action = conn.transaction::<_, DieselError, _>(|conn| {
+        hide_item_by_moderator(conn, item_id)?;
+        record_action(
+          conn,
+          form.report_id,
+          user_id,
+          HIDE_ITEM_ACTION,
+          ITEM_TARGET,
+          item_id,
+          form.note.unwrap_or_default(),
+        )
+      })?;
+      return Ok(to_moderation_action_response(action));
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+#[post("/moderation/users/{user_id}/suspend")]
+pub async fn suspend_user(
+  pool: web::Data<DbPool>,
+  srv: web::Data<Addr<Lobby>>,
+  req: HttpRequest,
+  target_user_id: web::Path<i64>,
+  form: web::Json<ModerationActionRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let target_user_id = target_user_id.into_inner();
+  let form = form.into_inner();
+  let resp = web::block(move || -> Result<ModerationActionResponse, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      get_moderator(&mut conn, user_id)?;
+      verify_report(&mut conn, form.report_id)?;
+      let target_user = get_user_by_id(&mut conn, target_user_id)?;
+      if target_user.is_moderator() {
+        return Err(RouteError::BadRequest(
+          "moderators can not be suspended".to_string(),
+        ));
+      }
+      let action = conn.transaction::<_, DieselError, _>(|conn| {
+        repo_suspend_user(conn, target_user_id)?;
+        // signed in sessions can not be refreshed anymore
+        delete_all_refresh_tokens(conn, target_user_id)?;
+        record_action(
+          conn,
+          form.report_id,
+          user_id,
+          SUSPEND_USER_ACTION,
+          USER_TARGET,
+          target_user_id,
+          form.note.unwrap_or_default(),
+        )
+      })?;
+      return Ok(to_moderation_action_response(action));
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  // the open chat connection goes too, the access token is refused from now on
+  srv.do_send(DisconnectUser {
+    user_id: target_user_id,
+  });
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+#[post("/moderation/messages/{message_id}/delete")]
+pub async fn delete_message_by_moderator(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  message_id: web::Path<i64>,
+  form: web::Json<ModerationActionRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let message_id = message_id.into_inner();
+  let form = form.into_inner();
+  let resp = web::block(move || -> Result<ModerationActionResponse, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      get_moderator(&mut conn, user_id)?;
+      verify_report(&mut conn, form.report_id)?;
+      let action = conn.transaction::<_, DieselError, _>(|conn| {
+        delete_message(conn, message_id)?;
+        record_action(
+          conn,
+          form.report_id,
+          user_id,
+          DELETE_MESSAGE_ACTION,
+          MESSAGE_TARGET,
+          message_id,
+          form.note.unwrap_or_default(),
+        )
+      })?;
+      return Ok(to_moderation_action_response(action));
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+// only active moderators and admins can work the queue
+fn get_moderator(conn: &mut PgConnection, user_id: i64) -> Result<User, RouteError> {
+  let user = get_user_by_id(conn, user_id)?;
+  if !user.is_moderator() || user.is_suspended() {
+    return Err(RouteError::Unauthorized);
+  }
+  Ok(user)
+}
+
+fn verify_report(conn: &mut PgConnection, report_id: Option<i64>) -> Result<(), RouteError> {
+  if let Some(report_id) = report_id {
+    get_report_by_id(conn, report_id)?;
+  }
+  Ok(())
+}
+
+fn close(
+  pool: &DbPool,
+  user_id: i64,
+  report_id: i64,
+  new_status: &str,
+  action: &str,
+  note: String,
+) -> Result<ReportResponse, RouteError> {
+  if let Ok(mut conn) = pool.get() {
+    get_moderator(&mut conn, user_id)?;
+    let report = conn.transaction::<_, DieselError, _>(|conn| {
+      let report = close_report(conn, report_id, new_status)?;
+      record_action(
+        conn,
+        Some(report_id),
+        user_id,
+        action,
+        REPORT_TARGET,
+        report_id,
+        note,
+      )?;
+      Ok(report)
+    })?;
+    return Ok(to_report_response(report));
+  }
+  Err(RouteError::PoolingErr)
+}
+
+fn record_action(
+  conn: &mut PgConnection,
+  report_id: Option<i64>,
+  moderator_id: i64,
+  action: &str,
+  target_type: &str,
+  target_id: i64,
+  note: String,
+) -> Result<ModerationAction, DieselError> {
+  add_moderation_action(
+    conn,
+    &InsertModerationAction {
+      report_id,
+      moderator_id,
+      action: action.to_string(),
+      target_type: target_type.to_string(),
+      target_id,
+      note,
+    },
+  )
+}
+
+fn to_moderation_action_response(action: ModerationAction) -> ModerationActionResponse {
+  ModerationActionResponse {
+    id: action.id,
+    report_id: action.report_id,
+    moderator_id: action.moderator_id,
+    action: action.action,
+    target_type: action.target_type,
+    target_id: action.target_id,
+    note: action.note,
+    created_at: action.created_at.timestamp(),
+  }
+}
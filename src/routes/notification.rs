@@ -0,0 +1,163 @@
+use actix::Addr;
+use actix_web::{get, post, web, Error, HttpMessage, HttpRequest, HttpResponse};
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use log::warn;
+
+use super::models::{
+  GetNotificationsRequest, NotificationResponse, NotificationsResponse, UnreadNotificationsCount,
+};
+use super::DbPool;
+use super::{route_error_handler, RouteError};
+use crate::repository::notification::{
+  add_notification, count_unread_notifications, get_notifications_by_user_id,
+  mark_all_notifications_as_read, mark_notification_as_read, InsertNotification, Notification,
+};
+use crate::ws::lobby::Lobby;
+use crate::ws::messages::{NotifyUser, ServerNotification};
+
+const DEFAULT_NOTIFICATIONS_LIMIT: i64 = 20;
+const MAX_NOTIFICATIONS_LIMIT: i64 = 100;
+
+#[get("/notifications")]
+pub async fn get_notifications(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  query: web::Query<GetNotificationsRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let before_id = query.before_id;
+  let limit = query
+    .limit
+    .unwrap_or(DEFAULT_NOTIFICATIONS_LIMIT)
+    .clamp(1, MAX_NOTIFICATIONS_LIMIT);
+
+  let resp = web::block(move || -> Result<NotificationsResponse, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      let notifications = get_notifications_by_user_id(&mut conn, user_id, before_id, limit)?;
+      let unread_count = count_unread_notifications(&mut conn, user_id)?;
+      return Ok(NotificationsResponse {
+        notifications: notifications
+          .into_iter()
+          .map(|notification| NotificationResponse {
+            id: notification.id,
+            notification_type: notification.notification_type,
+            title: notification.title,
+            body: notification.body,
+            item_id: notification.item_id,
+            room_id: notification.room_id,
+            is_read: notification.is_read,
+            created_at: notification.created_at.timestamp(),
+          })
+          .collect(),
+        unread_count,
+      });
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+#[get("/notifications/unreadCount")]
+pub async fn get_unread_notifications_count(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let unread_count = web::block(move || {
+    if let Ok(mut conn) = pool.get() {
+      let unread_count = count_unread_notifications(&mut conn, user_id)?;
+      return Ok(unread_count);
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(UnreadNotificationsCount { unread_count }))
+}
+
+#[post("/notifications/{notification_id}/read")]
+pub async fn read_notification(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  notification_id: web::Path<i64>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  web::block(move || {
+    if let Ok(mut conn) = pool.get() {
+      mark_notification_as_read(&mut conn, notification_id.into_inner(), user_id)?;
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+#[post("/notifications/readAll")]
+pub async fn read_all_notifications(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  web::block(move || {
+    if let Ok(mut conn) = pool.get() {
+      mark_all_notifications_as_read(&mut conn, user_id)?;
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+// stores the notification and, when asked to, pushes it to the user's websocket session
+pub fn notify_user(
+  conn: &mut PgConnection,
+  srv: &Addr<Lobby>,
+  new_notification: &InsertNotification,
+  push: bool,
+) -> Result<Notification, DieselError> {
+  let notification = add_notification(conn, new_notification)?;
+  if push {
+    srv.do_send(NotifyUser {
+      user_id: notification.user_id,
+      notification: to_server_notification(&notification),
+    });
+  }
+  Ok(notification)
+}
+
+// Notifications are a side effect of the action, so failures are only logged
+pub fn try_notify_user(
+  conn: &mut PgConnection,
+  srv: &Addr<Lobby>,
+  new_notification: &InsertNotification,
+) {
+  if let Err(e) = notify_user(conn, srv, new_notification, true) {
+    warn!(
+      "failed to create {} notification for user {}: {}",
+      new_notification.notification_type, new_notification.user_id, e
+    );
+  }
+}
+
+pub fn to_server_notification(notification: &Notification) -> ServerNotification {
+  ServerNotification {
+    id: notification.id,
+    notification_type: notification.notification_type.clone(),
+    title: notification.title.clone(),
+    body: notification.body.clone(),
+    item_id: notification.item_id,
+    room_id: notification.room_id,
+    created_at: notification.created_at.to_string(),
+  }
+}
@@ -0,0 +1,243 @@
+use actix_web::{get, web, web::Path, Error, HttpMessage, HttpRequest, HttpResponse};
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use std::collections::{HashMap, HashSet};
+
+use super::models::{GetItemResponse, GetItemsResponse, ItemStatus, RecommendationsRequest};
+use super::DbPool;
+use super::{route_error_handler, RouteError};
+use crate::repository::item::{
+  get_all_visible, get_favorite_items, get_item_by_id, get_items_by_ids, Item, ItemFilter,
+  ACTIVE_ITEM_STATUS, DRAFT_ITEM_STATUS,
+};
+use crate::repository::item_event::get_viewed_item_ids;
+use crate::repository::item_image::get_docs_for_item;
+use crate::repository::user_block::get_blocked_user_ids;
+use crate::repository::user_favorite::get_co_favorite_counts;
+use crate::storage::cdn::MediaUrls;
+
+const DEFAULT_RECOMMENDATIONS_LIMIT: i64 = 20;
+const MAX_RECOMMENDATIONS_LIMIT: i64 = 50;
+const VIEW_HISTORY_LIMIT: i64 = 100;
+
+const CATEGORY_WEIGHT: f64 = 3.0;
+const KARAT_WEIGHT: f64 = 2.0;
+const REGION_WEIGHT: f64 = 1.5;
+const PRICE_WEIGHT: f64 = 1.0;
+const ITEM_WEIGHT_WEIGHT: f64 = 1.0;
+const CO_FAVORITE_WEIGHT: f64 = 0.5;
+// a few co-favorites are a signal, hundreds should not drown everything else
+const MAX_CO_FAVORITES: i64 = 5;
+
+// a favorite says more about the taste of the user than a view
+const FAVORITE_SIGNAL: f64 = 2.0;
+const VIEW_SIGNAL: f64 = 1.0;
+
+// what the user is interested in, built from the items they favorited and viewed
+#[derive(Default)]
+struct Profile {
+  categories: HashMap<i64, f64>,
+  karats: HashMap<i64, f64>,
+  regions: HashMap<i64, f64>,
+  price: f64,
+  weight: f64,
+  total: f64,
+}
+
+impl Profile {
+  fn add(&mut self, item: &Item, signal: f64) {
+    *self.categories.entry(item.category_id).or_default() += signal;
+    *self.karats.entry(item.karat_id).or_default() += signal;
+    *self.regions.entry(item.geofence_id).or_default() += signal;
+    self.price += item.price as f64 * signal;
+    self.weight += item.weight * signal;
+    self.total += signal;
+  }
+
+  fn score(&self, item: &Item) -> f64 {
+    if self.total == 0.0 {
+      return 0.0;
+    }
+    let share =
+      |values: &HashMap<i64, f64>, key: i64| values.get(&key).unwrap_or(&0.0) / self.total;
+    CATEGORY_WEIGHT * share(&self.categories, item.category_id)
+      + KARAT_WEIGHT * share(&self.karats, item.karat_id)
+      + REGION_WEIGHT * share(&self.regions, item.geofence_id)
+      + PRICE_WEIGHT * proximity(item.price as f64, self.price / self.total)
+      + ITEM_WEIGHT_WEIGHT * proximity(item.weight, self.weight / self.total)
+  }
+}
+
+#[get("/items/{item_id}/similar")]
+pub async fn get_similar_items(
+  pool: web::Data<DbPool>,
+  media_urls: web::Data<MediaUrls>,
+  req: HttpRequest,
+  item_id: Path<i64>,
+  query: web::Query<RecommendationsRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let item_id = item_id.into_inner();
+  let limit = get_limit(query.limit);
+  let resp = web::block(move || -> Result<GetItemsResponse, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      let target = get_item_by_id(&mut conn, item_id)?;
+      // drafts are only visible to their owner
+      if target.item_status == DRAFT_ITEM_STATUS && user_id != target.owner_id {
+        return Err(RouteError::DbError(DieselError::NotFound));
+      }
+      let co_favorites = get_co_favorite_counts(&mut conn, &[item_id])?
+        .into_iter()
+        .collect::<HashMap<i64, i64>>();
+      let mut scored: Vec<(f64, Item)> = get_candidates(&mut conn, user_id)?
+        .into_iter()
+        .filter(|item| item.id != target.id)
+        .map(|item| {
+          let score = similarity(&target, &item) + co_favorite_score(&co_favorites, item.id);
+          (score, item)
+        })
+        .collect();
+      return to_items_response(&mut conn, &media_urls, &mut scored, limit);
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+#[get("/users/items/recommended")]
+pub async fn get_recommended_items(
+  pool: web::Data<DbPool>,
+  media_urls: web::Data<MediaUrls>,
+  req: HttpRequest,
+  query: web::Query<RecommendationsRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let limit = get_limit(query.limit);
+  let resp = web::block(move || -> Result<GetItemsResponse, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      let favorites = get_favorite_items(&mut conn, user_id)?;
+      let viewed_ids = get_viewed_item_ids(&mut conn, user_id, VIEW_HISTORY_LIMIT)?;
+      let viewed = get_items_by_ids(&mut conn, &viewed_ids)?;
+
+      let mut profile = Profile::default();
+      for item in favorites.iter() {
+        profile.add(item, FAVORITE_SIGNAL);
+      }
+      for item in viewed.iter() {
+        profile.add(item, VIEW_SIGNAL);
+      }
+      let favorite_ids: Vec<i64> = favorites.iter().map(|item| item.id).collect();
+      let co_favorites = get_co_favorite_counts(&mut conn, &favorite_ids)?
+        .into_iter()
+        .collect::<HashMap<i64, i64>>();
+
+      // without any signal this is the regular feed, newest bumps first
+      let mut scored: Vec<(f64, Item)> = get_candidates(&mut conn, user_id)?
+        .into_iter()
+        .filter(|item| !favorite_ids.contains(&item.id))
+        .map(|item| {
+          let score = profile.score(&item) + co_favorite_score(&co_favorites, item.id);
+          (score, item)
+        })
+        .collect();
+      return to_items_response(&mut conn, &media_urls, &mut scored, limit);
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+fn get_limit(limit: Option<i64>) -> usize {
+  limit
+    .unwrap_or(DEFAULT_RECOMMENDATIONS_LIMIT)
+    .clamp(1, MAX_RECOMMENDATIONS_LIMIT) as usize
+}
+
+// active items of other users the user did not block, newest bumps first
+fn get_candidates(conn: &mut PgConnection, user_id: i64) -> Result<Vec<Item>, DieselError> {
+  let blocked_user_ids: HashSet<i64> = get_blocked_user_ids(conn, user_id)?.into_iter().collect();
+  let items = get_all_visible(conn, &ItemFilter::default())?;
+  Ok(
+    items
+      .into_iter()
+      .filter(|item| {
+        item.item_status == ACTIVE_ITEM_STATUS
+          && item.owner_id != user_id
+          && !blocked_user_ids.contains(&item.owner_id)
+      })
+      .collect(),
+  )
+}
+
+fn similarity(target: &Item, item: &Item) -> f64 {
+  let mut score = 0.0;
+  if item.category_id == target.category_id {
+    score += CATEGORY_WEIGHT;
+  }
+  if item.karat_id == target.karat_id {
+    score += KARAT_WEIGHT;
+  }
+  if item.geofence_id == target.geofence_id {
+    score += REGION_WEIGHT;
+  }
+  score
+    + PRICE_WEIGHT * proximity(item.price as f64, target.price as f64)
+    + ITEM_WEIGHT_WEIGHT * proximity(item.weight, target.weight)
+}
+
+fn co_favorite_score(co_favorites: &HashMap<i64, i64>, item_id: i64) -> f64 {
+  let count = co_favorites.get(&item_id).copied().unwrap_or(0);
+  CO_FAVORITE_WEIGHT * count.min(MAX_CO_FAVORITES) as f64
+}
+
+// 1 for equal values, going down to 0 when one is twice the other or more
+fn proximity(value: f64, target: f64) -> f64 {
+  let largest = value.abs().max(target.abs());
+  if largest == 0.0 {
+    return 1.0;
+  }
+  (1.0 - (value - target).abs() / largest).max(0.0)
+}
+
+// the best scored items that have an uploaded cover, ties keep the feed order
+fn to_items_response(
+  conn: &mut PgConnection,
+  media_urls: &MediaUrls,
+  scored: &mut [(f64, Item)],
+  limit: usize,
+) -> Result<GetItemsResponse, RouteError> {
+  scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+  let mut resp = GetItemsResponse { items: vec![] };
+  for (_, item) in scored.iter() {
+    if resp.items.len() >= limit {
+      break;
+    }
+    let docs = get_docs_for_item(conn, item.id)?;
+    if let Some(doc) = docs
+      .into_iter()
+      .find(|doc| doc.is_cover && doc.uploaded_to_cloud)
+    {
+      resp.items.push(GetItemResponse {
+        id: item.id,
+        price: item.price,
+        title: item.title.clone(),
+        description: item.description.clone(),
+        favorite_count: item.favorite_count,
+        message_count: item.message_count,
+        seen_count: item.seen_count,
+        item_status: ItemStatus::Active,
+        owner_id: item.owner_id,
+        created_at: item.created_at.timestamp(),
+        thumbnail: media_urls.optional_url(doc.thumbnail())?,
+        distance_km: None,
+      });
+    }
+  }
+  Ok(resp)
+}
@@ -0,0 +1,119 @@
+use actix_web::{post, web, Error, HttpMessage, HttpRequest, HttpResponse};
+
+use super::models::{CreateReportRequest, ReportReason, ReportResponse, ReportTargetType};
+use super::DbPool;
+use super::{route_error_handler, RouteError};
+use crate::repository::item::get_item_by_id;
+use crate::repository::message::get_message_by_id;
+use crate::repository::report::{add_report, get_unresolved_report, InsertReport, Report};
+use crate::repository::room_member::get_room_member;
+use crate::repository::user::get_user_by_id;
+
+const MAX_REPORT_DESCRIPTION_LENGTH: usize = 1000;
+
+pub const ITEM_TARGET: &str = "Item";
+pub const USER_TARGET: &str = "User";
+pub const MESSAGE_TARGET: &str = "Message";
+
+#[post("/reports/create")]
+pub async fn create_report(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  form: web::Json<CreateReportRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let form = form.into_inner();
+  let description = form.description.unwrap_or_default().trim().to_string();
+  let resp = web::block(move || -> Result<ReportResponse, RouteError> {
+    if description.chars().count() > MAX_REPORT_DESCRIPTION_LENGTH {
+      return Err(RouteError::BadRequest(
+        "report description is too long".to_string(),
+      ));
+    }
+    if let Ok(mut conn) = pool.get() {
+      // users can only report what they can see, and never themselves
+      match form.target_type {
+        ReportTargetType::Item => {
+          let item = get_item_by_id(&mut conn, form.target_id)?;
+          if item.owner_id == user_id {
+            return Err(RouteError::BadRequest(
+              "can not report own item".to_string(),
+            ));
+          }
+        }
+        ReportTargetType::User => {
+          get_user_by_id(&mut conn, form.target_id)?;
+          if form.target_id == user_id {
+            return Err(RouteError::BadRequest("can not report self".to_string()));
+          }
+        }
+        ReportTargetType::Message => {
+          let message = get_message_by_id(&mut conn, form.target_id)?;
+          if message.sender_id == user_id {
+            return Err(RouteError::BadRequest(
+              "can not report own message".to_string(),
+            ));
+          }
+          if get_room_member(&mut conn, &user_id, &message.room_id).is_err() {
+            return Err(RouteError::Unauthorized);
+          }
+        }
+      }
+      let target_type = report_target_type_name(&form.target_type);
+      if let Some(report) = get_unresolved_report(&mut conn, user_id, target_type, form.target_id)?
+      {
+        return Ok(to_report_response(report));
+      }
+      let report = add_report(
+        &mut conn,
+        &InsertReport {
+          reporter_id: user_id,
+          target_type: target_type.to_string(),
+          target_id: form.target_id,
+          reason: report_reason_name(&form.reason).to_string(),
+          description,
+        },
+      )?;
+      return Ok(to_report_response(report));
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+pub fn report_target_type_name(target_type: &ReportTargetType) -> &'static str {
+  match target_type {
+    ReportTargetType::Item => ITEM_TARGET,
+    ReportTargetType::User => USER_TARGET,
+    ReportTargetType::Message => MESSAGE_TARGET,
+  }
+}
+
+fn report_reason_name(reason: &ReportReason) -> &'static str {
+  match reason {
+    ReportReason::Fraud => "Fraud",
+    ReportReason::Prohibited => "Prohibited",
+    ReportReason::Counterfeit => "Counterfeit",
+    ReportReason::Abuse => "Abuse",
+    ReportReason::Spam => "Spam",
+    ReportReason::Other => "Other",
+  }
+}
+
+pub fn to_report_response(report: Report) -> ReportResponse {
+  ReportResponse {
+    id: report.id,
+    reporter_id: report.reporter_id,
+    target_type: report.target_type,
+    target_id: report.target_id,
+    reason: report.reason,
+    description: report.description,
+    status: report.report_status,
+    assignee_id: report.assignee_id,
+    created_at: report.created_at.timestamp(),
+    resolved_at: report.resolved_at.map(|value| value.timestamp()),
+  }
+}
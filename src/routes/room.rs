@@ -4,18 +4,27 @@ use actix_web::{get, post, Error, HttpMessage, HttpRequest, HttpResponse};
 use actix_web_actors::ws;
 use diesel::result::Error as DieselError;
 
-use super::models::{CreateRoomRequest, CreateRoomResponse, GetUserRoomsResponse, UserRoom};
+use super::analytics::record_item_event;
+use super::models::{
+  CreateRoomRequest, CreateRoomResponse, GetUserRoomsResponse, MuteRoomRequest, UserRoom,
+};
+use super::notification::try_notify_user;
 use super::DbPool;
 use super::{route_error_handler, RouteError};
-use crate::repository::item::{get_item_by_id, increment_message_count};
+use crate::repository::item::{get_item_by_id, increment_message_count, DRAFT_ITEM_STATUS};
+use crate::repository::item_event::ROOM_OPENED_EVENT;
 use crate::repository::item_image::get_cover_pic_for_item;
 use crate::repository::message::get_last_message_by_room_id;
+use crate::repository::notification::{
+  mark_room_notifications_as_read, InsertNotification, NEW_ROOM_NOTIFICATION,
+};
 use crate::repository::room::{create_new_room, get_room_by_item_and_creator};
 use crate::repository::room_member::{
-  create_new_room_member, get_room_member, get_rooms_by_user_id, set_last_joined_at,
+  create_new_room_member, get_room_member, get_rooms_by_user_id, set_last_joined_at, set_muted,
 };
 use crate::repository::user::get_user_by_id;
-use crate::routes::item::CLOUD_FRONT_DISTRIBUTION_DOMAIN_NAME;
+use crate::repository::user_block::is_blocked_between;
+use crate::storage::cdn::MediaUrls;
 use crate::ws::lobby::Lobby;
 use crate::ws::ws::WsConn;
 
@@ -46,6 +55,7 @@ pub async fn join_room(
     if let Ok(mut conn) = pool_cloned.get() {
       get_room_member(&mut conn, &user_id, &rid)?;
       set_last_joined_at(&mut conn, &user_id, &rid)?;
+      mark_room_notifications_as_read(&mut conn, user_id, rid)?;
       let user = get_user_by_id(&mut conn, user_id)?;
       return Ok(user);
     }
@@ -60,7 +70,11 @@ pub async fn join_room(
 }
 
 #[get("/room/getUserRooms")]
-pub async fn get_user_rooms(pool: Data<DbPool>, req: HttpRequest) -> Result<HttpResponse, Error> {
+pub async fn get_user_rooms(
+  pool: Data<DbPool>,
+  media_urls: Data<MediaUrls>,
+  req: HttpRequest,
+) -> Result<HttpResponse, Error> {
   let ext = req.extensions();
   let user_id: i64 = ext.get::<i64>().unwrap().to_owned();
 
@@ -82,14 +96,8 @@ pub async fn get_user_rooms(pool: Data<DbPool>, req: HttpRequest) -> Result<Http
           let cover_image_doc = get_cover_pic_for_item(&mut conn, item.id)?;
           resp.rooms.push(UserRoom {
             title: item.title,
-            item_image_url: format!(
-              "https://{}/{}",
-              CLOUD_FRONT_DISTRIBUTION_DOMAIN_NAME, cover_image_doc.key,
-            ),
-            secondary_user_image_url: format!(
-              "https://{}/{}",
-              CLOUD_FRONT_DISTRIBUTION_DOMAIN_NAME, cover_image_doc.key,
-            ),
+            item_image_url: media_urls.optional_url(cover_image_doc.thumbnail())?,
+            secondary_user_image_url: media_urls.optional_url(cover_image_doc.original())?,
             item_id: item.id,
             last_message: mes.msg,
             last_message_time: mes.created_at,
@@ -113,6 +121,7 @@ pub async fn get_user_rooms(pool: Data<DbPool>, req: HttpRequest) -> Result<Http
 #[post("/room/createRoom")]
 pub async fn create_room(
   pool: Data<DbPool>,
+  srv: Data<Addr<Lobby>>,
   req: HttpRequest,
   form: Json<CreateRoomRequest>,
 ) -> Result<HttpResponse, Error> {
@@ -125,6 +134,12 @@ pub async fn create_room(
   let pool_cloned = pool.clone();
   let resp = block(move || {
     if let Ok(mut conn) = pool_cloned.get() {
+      if is_blocked_between(&mut conn, user_id, secondary_user_id)? {
+        return Err(RouteError::UserBlocked);
+      }
+      if get_item_by_id(&mut conn, item_id)?.item_status == DRAFT_ITEM_STATUS {
+        return Err(RouteError::DbError(DieselError::NotFound));
+      }
       match get_room_by_item_and_creator(&mut conn, &user_id, &secondary_user_id, &item_id) {
         Ok(res) => {
           return Ok(res);
@@ -134,9 +149,22 @@ pub async fn create_room(
 
           // increment message count
           increment_message_count(&mut conn, item_id)?;
+          record_item_event(&mut conn, item_id, user_id, ROOM_OPENED_EVENT);
 
           create_new_room_member(&mut conn, &room.id, &user_id)?;
           create_new_room_member(&mut conn, &room.id, &secondary_user_id)?;
+
+          let item = get_item_by_id(&mut conn, item_id)?;
+          let user = get_user_by_id(&mut conn, user_id)?;
+          let new_notification = InsertNotification {
+            user_id: secondary_user_id,
+            notification_type: NEW_ROOM_NOTIFICATION.to_string(),
+            title: format!("{} started a chat", user.name),
+            body: item.title,
+            item_id: Some(item.id),
+            room_id: Some(room.id),
+          };
+          try_notify_user(&mut conn, &srv, &new_notification);
           return Ok(room);
         }
         Err(e) => {
@@ -153,11 +181,28 @@ pub async fn create_room(
       item_id: item_id,
       secondary_user_id: secondary_user_id,
     })),
-    Err(e) => {
-      return Err(actix_web::error::ErrorInternalServerError(format!(
-        "Internal Server Error: {:?}",
-        e.to_string()
-      )));
-    }
+    Err(e) => Err(route_error_handler(e)),
   }
 }
+
+#[post("/room/{room_id}/mute")]
+pub async fn mute_room(
+  pool: Data<DbPool>,
+  req: HttpRequest,
+  room_id: Path<i64>,
+  form: Json<MuteRoomRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let is_muted = form.is_muted;
+  block(move || {
+    if let Ok(mut conn) = pool.get() {
+      set_muted(&mut conn, &user_id, &room_id, is_muted)?;
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().body("OK"))
+}
@@ -0,0 +1,172 @@
+use actix::Addr;
+use actix_web::{delete, get, post, web, Error, HttpMessage, HttpRequest, HttpResponse};
+use diesel::PgConnection;
+use log::warn;
+
+use super::models::{CreateSavedSearchRequest, SavedSearch, SavedSearches};
+use super::notification::notify_user;
+use super::DbPool;
+use super::{route_error_handler, RouteError};
+use crate::repository::item::{Item, ItemFilter};
+use crate::repository::notification::{InsertNotification, SAVED_SEARCH_MATCH_NOTIFICATION};
+use crate::repository::saved_search::{
+  add_saved_search, delete_saved_search as repo_delete_saved_search, get_matching_saved_searches,
+  get_saved_searches_by_user_id, SavedSearch as RepoSavedSearch,
+};
+use crate::repository::user_block::get_blocker_ids;
+use crate::ws::lobby::Lobby;
+
+const MAX_SAVED_SEARCHES_PER_USER: usize = 20;
+
+#[post("/searches/create")]
+pub async fn create_saved_search(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  form: web::Json<CreateSavedSearchRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let form = form.into_inner();
+
+  let resp = web::block(move || -> Result<SavedSearch, RouteError> {
+    let name = form.name.trim().to_string();
+    if name.is_empty() {
+      return Err(RouteError::BadRequest("search name is empty".to_string()));
+    }
+    if let (Some(min_price), Some(max_price)) = (form.min_price, form.max_price) {
+      if min_price > max_price {
+        return Err(RouteError::BadRequest(
+          "min price is greater than max price".to_string(),
+        ));
+      }
+    }
+    let filter = ItemFilter {
+      category_id: form.category_id,
+      karat_id: form.karat_id,
+      geofence_id: form.geofence_id,
+      min_price: form.min_price,
+      max_price: form.max_price,
+      attributes: vec![],
+      area: None,
+      geofence_ids: None,
+    };
+    if let Ok(mut conn) = pool.get() {
+      let searches = get_saved_searches_by_user_id(&mut conn, user_id)?;
+      if searches.len() >= MAX_SAVED_SEARCHES_PER_USER {
+        return Err(RouteError::BadRequest(format!(
+          "can not save more than {} searches",
+          MAX_SAVED_SEARCHES_PER_USER
+        )));
+      }
+      let search = add_saved_search(
+        &mut conn,
+        user_id,
+        name,
+        &filter,
+        form.push_enabled.unwrap_or(true),
+      )?;
+      return Ok(to_saved_search(search));
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+#[get("/searches")]
+pub async fn get_saved_searches(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let resp = web::block(move || -> Result<SavedSearches, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      let searches = get_saved_searches_by_user_id(&mut conn, user_id)?;
+      return Ok(SavedSearches {
+        searches: searches.into_iter().map(to_saved_search).collect(),
+      });
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
+
+#[delete("/searches/{search_id}")]
+pub async fn delete_saved_search(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  search_id: web::Path<i64>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  web::block(move || {
+    if let Ok(mut conn) = pool.get() {
+      repo_delete_saved_search(&mut conn, search_id.into_inner(), user_id)?;
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+// Alerts every user with a saved search matching the newly published item.
+// Failures are only logged so they never block publishing the item.
+pub fn notify_saved_search_matches(conn: &mut PgConnection, srv: &Addr<Lobby>, new_item: &Item) {
+  let searches = match get_matching_saved_searches(conn, new_item) {
+    Ok(searches) => searches,
+    Err(e) => {
+      warn!(
+        "failed to match saved searches for item {}: {}",
+        new_item.id, e
+      );
+      return;
+    }
+  };
+  // users who blocked the seller do not hear about their listings
+  let blocker_ids = get_blocker_ids(conn, new_item.owner_id).unwrap_or_else(|e| {
+    warn!(
+      "failed to get blockers of user {}: {}",
+      new_item.owner_id, e
+    );
+    vec![]
+  });
+  for search in searches {
+    if blocker_ids.contains(&search.user_id) {
+      continue;
+    }
+    let new_notification = InsertNotification {
+      user_id: search.user_id,
+      notification_type: SAVED_SEARCH_MATCH_NOTIFICATION.to_string(),
+      title: format!("New listing for \"{}\"", search.name),
+      body: new_item.title.clone(),
+      item_id: Some(new_item.id),
+      room_id: None,
+    };
+    if let Err(e) = notify_user(conn, srv, &new_notification, search.push_enabled) {
+      warn!(
+        "failed to notify user {} about item {}: {}",
+        search.user_id, new_item.id, e
+      );
+    }
+  }
+}
+
+fn to_saved_search(search: RepoSavedSearch) -> SavedSearch {
+  SavedSearch {
+    id: search.id,
+    name: search.name,
+    category_id: search.category_id,
+    karat_id: search.karat_id,
+    geofence_id: search.geofence_id,
+    min_price: search.min_price,
+    max_price: search.max_price,
+    push_enabled: search.push_enabled,
+    created_at: search.created_at.timestamp(),
+  }
+}
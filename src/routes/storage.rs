@@ -0,0 +1,58 @@
+use actix_files::Files;
+use actix_web::http::header::CONTENT_TYPE;
+use actix_web::{guard, web, web::Path, Error, HttpRequest, HttpResponse};
+use log::warn;
+use std::sync::Arc;
+
+use super::models::LocalUploadQuery;
+use super::{route_error_handler, RouteError};
+use crate::storage::local::{LocalStorage, LOCAL_STORAGE_PATH};
+
+// the token carries the limits of the upload, this only keeps a local upload from
+// filling the memory before they are checked
+const MAX_LOCAL_OBJECT_SIZE: usize = 20 * 1024 * 1024;
+
+// the target of presigned upload URLs of the local storage
+pub async fn upload_local_object(
+  storage: web::Data<LocalStorage>,
+  key: Path<String>,
+  query: web::Query<LocalUploadQuery>,
+  req: HttpRequest,
+  body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+  let key = key.into_inner();
+  let content_type = req
+    .headers()
+    .get(CONTENT_TYPE)
+    .and_then(|value| value.to_str().ok())
+    .unwrap_or_default();
+  if !storage.verify_upload(&key, &query.token, content_type, body.len() as u64) {
+    return Err(route_error_handler(RouteError::Unauthorized));
+  }
+  web::block(move || storage.write(&key, &body))
+    .await?
+    .map_err(|e| {
+      warn!("failed to write local object: {}", e);
+      route_error_handler(RouteError::InternalErr)
+    })?;
+  Ok(HttpResponse::Ok().finish())
+}
+
+// serves the local storage directory and takes the uploads to it
+pub fn configure_local_storage(storage: Arc<LocalStorage>) -> impl FnOnce(&mut web::ServiceConfig) {
+  move |cfg| {
+    let root = storage.root().to_path_buf();
+    if let Err(e) = std::fs::create_dir_all(&root) {
+      warn!("failed to create local storage directory: {}", e);
+    }
+    cfg
+      .service(
+        web::resource(format!("{}/{{key:.*}}", LOCAL_STORAGE_PATH))
+          .guard(guard::Put())
+          .app_data(web::Data::from(storage))
+          .app_data(web::PayloadConfig::new(MAX_LOCAL_OBJECT_SIZE))
+          .route(web::put().to(upload_local_object)),
+      )
+      .service(Files::new(LOCAL_STORAGE_PATH, root));
+  }
+}
@@ -0,0 +1,83 @@
+use actix_web::{get, post, web, Error, HttpMessage, HttpRequest, HttpResponse};
+
+use super::models::{BlockUserRequest, BlockedUser, BlockedUsers};
+use super::DbPool;
+use super::{route_error_handler, RouteError};
+use crate::repository::user::get_user_by_id;
+use crate::repository::user_block::{
+  block_user as repo_block_user, get_blocks_by_blocker_id, unblock_user as repo_unblock_user,
+};
+
+#[post("/users/block")]
+pub async fn block_user(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  form: web::Json<BlockUserRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let blocked_user_id = form.user_id;
+  web::block(move || {
+    if blocked_user_id == user_id {
+      return Err(RouteError::BadRequest("can not block self".to_string()));
+    }
+    if let Ok(mut conn) = pool.get() {
+      get_user_by_id(&mut conn, blocked_user_id)?;
+      repo_block_user(&mut conn, user_id, blocked_user_id)?;
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+#[post("/users/unblock")]
+pub async fn unblock_user(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+  form: web::Json<BlockUserRequest>,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let blocked_user_id = form.user_id;
+  web::block(move || {
+    if let Ok(mut conn) = pool.get() {
+      repo_unblock_user(&mut conn, user_id, blocked_user_id)?;
+      return Ok(());
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().body("OK"))
+}
+
+#[get("/users/blocked")]
+pub async fn get_blocked_users(
+  pool: web::Data<DbPool>,
+  req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+  let user_id: i64 = req.extensions().get::<i64>().unwrap().to_owned();
+  let resp = web::block(move || -> Result<BlockedUsers, RouteError> {
+    if let Ok(mut conn) = pool.get() {
+      let blocks = get_blocks_by_blocker_id(&mut conn, user_id)?;
+      let mut users = Vec::with_capacity(blocks.len());
+      for block in blocks {
+        let user = get_user_by_id(&mut conn, block.blocked_id)?;
+        users.push(BlockedUser {
+          id: user.id,
+          name: user.name,
+          blocked_at: block.created_at.timestamp(),
+        });
+      }
+      return Ok(BlockedUsers { users });
+    }
+    Err(RouteError::PoolingErr)
+  })
+  .await?
+  .map_err(route_error_handler)?;
+
+  Ok(HttpResponse::Ok().json(resp))
+}
@@ -1,9 +1,11 @@
 use actix_web::{delete, get, post, web, Error, HttpMessage, HttpRequest, HttpResponse};
 use s3::bucket;
 
+use super::geofence::validate_home_region;
+use super::item_image::{image_extension, image_upload_constraints};
 use super::models::{
-  CreatePresignedUrlResponse, GetUserResponse, ItemStatus, NewUserRequest, NewUserResponse,
-  SignInRequest, UpdateProfileRequest, UserItem, UserItems,
+  CoverImageUploadRequest, CreatePresignedUrlResponse, GetUserResponse, ItemStatus, NewUserRequest,
+  NewUserResponse, SignInRequest, UpdateProfileRequest, UserItem, UserItems,
 };
 use super::DbPool;
 use super::{route_error_handler, RouteError};
@@ -18,11 +20,9 @@ use crate::repository::user::{
   insert_new_user, update_profile as repo_update_profile,
 };
 
-use crate::repository::item::get_items_by_user_id;
-use crate::routes::item::CLOUD_FRONT_DISTRIBUTION_DOMAIN_NAME;
-use s3::bucket::Bucket;
-
-const IMAGE_UPLOAD_EXPIRATION_SECONDS: u32 = 4000;
+use crate::repository::item::{get_items_by_user_id, DRAFT_ITEM_STATUS};
+use crate::storage::cdn::MediaUrls;
+use crate::storage::ObjectStorage;
 
 #[post("/users/signup")]
 pub async fn signup(
@@ -88,6 +88,9 @@ pub async fn signin(
       if user.password != password {
         return Err(RouteError::InvalidPassword);
       }
+      if user.is_suspended() {
+        return Err(RouteError::UserSuspended);
+      }
       if let Ok(auth_token) = create_jwt(user.id) {
         // create new refresh token
         let new_token = get_new_refresh_token();
@@ -113,7 +116,11 @@ pub async fn signin(
 }
 
 #[get("/users")]
-pub async fn get_user(pool: web::Data<DbPool>, req: HttpRequest) -> Result<HttpResponse, Error> {
+pub async fn get_user(
+  pool: web::Data<DbPool>,
+  media_urls: web::Data<MediaUrls>,
+  req: HttpRequest,
+) -> Result<HttpResponse, Error> {
   let ext = req.extensions();
   let user_id: i64 = ext.get::<i64>().unwrap().to_owned();
   let user = web::block(move || {
@@ -125,26 +132,25 @@ pub async fn get_user(pool: web::Data<DbPool>, req: HttpRequest) -> Result<HttpR
   })
   .await?
   .map_err(|e| route_error_handler(e))?;
-  let avatar = if user.cover_image != None {
-    format!(
-      "https://{}/{}",
-      CLOUD_FRONT_DISTRIBUTION_DOMAIN_NAME,
-      user.cover_image.unwrap()
-    )
-  } else {
-    "".to_owned()
+  let avatar = match user.cover_image {
+    Some(ref cover_image) => media_urls
+      .url(cover_image)
+      .map_err(|e| route_error_handler(e.into()))?,
+    None => "".to_owned(),
   };
   Ok(HttpResponse::Ok().json(GetUserResponse {
     id: user.id,
     name: user.name,
     phone_number: user.phone_number,
     avatar: avatar,
+    home_region_id: user.home_region_id,
   }))
 }
 
 #[get("/users/items")]
 pub async fn get_user_items(
   pool: web::Data<DbPool>,
+  media_urls: web::Data<MediaUrls>,
   req: HttpRequest,
 ) -> Result<HttpResponse, Error> {
   let ext = req.extensions();
@@ -155,31 +161,35 @@ pub async fn get_user_items(
       let mut resp = vec![];
       for item in items {
         let docs = get_docs_for_item(&mut conn, item.id)?;
-        for doc in docs {
-          if doc.is_cover && doc.uploaded_to_cloud {
-            let item_status = match item.item_status.as_str() {
-              "Active" => ItemStatus::Active,
-              "Sold" => ItemStatus::Sold,
-              _ => ItemStatus::Reserved,
-            };
-            resp.push(UserItem {
-              id: item.id,
-              item_name: item.title,
-              image: format!(
-                "https://{}/{}",
-                CLOUD_FRONT_DISTRIBUTION_DOMAIN_NAME, doc.key,
-              ),
-              price: item.price,
-              favorite_count: item.favorite_count,
-              message_count: item.message_count,
-              item_status: item_status,
-              is_hidden: item.is_hidden,
-              created_at: item.created_at.timestamp(),
-              updated_at: item.updated_at.timestamp(),
-            });
-            break;
-          }
-        }
+        let cover = docs
+          .into_iter()
+          .find(|doc| doc.is_cover && doc.uploaded_to_cloud);
+        let image = match cover {
+          Some(doc) => media_urls.optional_url(doc.thumbnail())?,
+          // drafts are listed without a cover so the owner can finish them
+          None if item.item_status == DRAFT_ITEM_STATUS => String::new(),
+          None => continue,
+        };
+        let item_status = match item.item_status.as_str() {
+          "Draft" => ItemStatus::Draft,
+          "Active" => ItemStatus::Active,
+          "Sold" => ItemStatus::Sold,
+          "Expired" => ItemStatus::Expired,
+          _ => ItemStatus::Reserved,
+        };
+        resp.push(UserItem {
+          id: item.id,
+          item_name: item.title,
+          image,
+          price: item.price,
+          favorite_count: item.favorite_count,
+          message_count: item.message_count,
+          item_status: item_status,
+          is_hidden: item.is_hidden,
+          created_at: item.created_at.timestamp(),
+          updated_at: item.updated_at.timestamp(),
+          expires_at: item.expires_at.timestamp(),
+        });
       }
       return Ok(resp);
     }
@@ -193,6 +203,7 @@ pub async fn get_user_items(
 #[get("/users/items/favorite")]
 pub async fn get_user_favorite_items(
   pool: web::Data<DbPool>,
+  media_urls: web::Data<MediaUrls>,
   req: HttpRequest,
 ) -> Result<HttpResponse, Error> {
   let ext = req.extensions();
@@ -211,17 +222,16 @@ pub async fn get_user_favorite_items(
         for doc in docs {
           if doc.is_cover && doc.uploaded_to_cloud {
             let item_status = match item.item_status.as_str() {
+              "Draft" => ItemStatus::Draft,
               "Active" => ItemStatus::Active,
               "Sold" => ItemStatus::Sold,
+              "Expired" => ItemStatus::Expired,
               _ => ItemStatus::Reserved,
             };
             resp.push(UserItem {
               id: item.id,
               item_name: item.title,
-              image: format!(
-                "https://{}/{}",
-                CLOUD_FRONT_DISTRIBUTION_DOMAIN_NAME, doc.key,
-              ),
+              image: media_urls.optional_url(doc.thumbnail())?,
               price: item.price,
               favorite_count: item.favorite_count,
               message_count: item.message_count,
@@ -229,6 +239,7 @@ pub async fn get_user_favorite_items(
               is_hidden: item.is_hidden,
               created_at: item.created_at.timestamp(),
               updated_at: item.updated_at.timestamp(),
+              expires_at: item.expires_at.timestamp(),
             });
             break;
           }
@@ -246,6 +257,7 @@ pub async fn get_user_favorite_items(
 #[get("/users/items/purchase")]
 pub async fn get_user_purchased_items(
   pool: web::Data<DbPool>,
+  media_urls: web::Data<MediaUrls>,
   req: HttpRequest,
 ) -> Result<HttpResponse, Error> {
   let ext = req.extensions();
@@ -264,17 +276,16 @@ pub async fn get_user_purchased_items(
         for doc in docs {
           if doc.is_cover && doc.uploaded_to_cloud {
             let item_status = match item.item_status.as_str() {
+              "Draft" => ItemStatus::Draft,
               "Active" => ItemStatus::Active,
               "Sold" => ItemStatus::Sold,
+              "Expired" => ItemStatus::Expired,
               _ => ItemStatus::Reserved,
             };
             resp.push(UserItem {
               id: item.id,
               item_name: item.title,
-              image: format!(
-                "https://{}/{}",
-                CLOUD_FRONT_DISTRIBUTION_DOMAIN_NAME, doc.key,
-              ),
+              image: media_urls.optional_url(doc.thumbnail())?,
               price: item.price,
               favorite_count: item.favorite_count,
               message_count: item.message_count,
@@ -282,6 +293,7 @@ pub async fn get_user_purchased_items(
               is_hidden: item.is_hidden,
               created_at: item.created_at.timestamp(),
               updated_at: item.updated_at.timestamp(),
+              expires_at: item.expires_at.timestamp(),
             });
             break;
           }
@@ -299,20 +311,25 @@ pub async fn get_user_purchased_items(
 #[get("/users/coverImage/presignedUrl")]
 pub async fn get_presigned_url_for_cover_image(
   req: HttpRequest,
-  bucket: web::Data<Bucket>,
+  storage: web::Data<dyn ObjectStorage>,
+  query: web::Query<CoverImageUploadRequest>,
 ) -> Result<HttpResponse, Error> {
   // create presigned url for the user and respond back
   let ext = req.extensions();
   let user_id: i64 = ext.get::<i64>().unwrap().to_owned();
-  let object_name = format!("images/{0}/{1}", user_id, get_timestamp_as_nano(),);
-  match bucket.presign_put(
-    format!("{0}", &object_name),
-    IMAGE_UPLOAD_EXPIRATION_SECONDS,
-    None,
-  ) {
-    Ok(url) => {
+  let constraints = image_upload_constraints(&query.content_type).map_err(route_error_handler)?;
+  let object_name = format!(
+    "images/{0}/{1}.{2}",
+    user_id,
+    get_timestamp_as_nano(),
+    image_extension(&query.content_type)
+  );
+  match storage.presign_upload(&object_name, &constraints) {
+    Ok(upload) => {
       return Ok(HttpResponse::Ok().json(CreatePresignedUrlResponse {
-        url: url,
+        url: upload.url,
+        method: upload.method,
+        fields: upload.fields,
         image_name: object_name,
       }));
     }
@@ -334,9 +351,19 @@ pub async fn update_profile(
   let user_id: i64 = ext.get::<i64>().unwrap().to_owned();
   let new_cover_image = form.image.to_owned();
   let new_name = form.name.to_owned();
+  let new_home_region_id = form.home_region_id;
   web::block(move || {
     if let Ok(mut conn) = pool.get() {
-      repo_update_profile(&mut conn, user_id, new_cover_image, new_name)?;
+      if let Some(home_region_id) = new_home_region_id {
+        validate_home_region(&mut conn, home_region_id)?;
+      }
+      repo_update_profile(
+        &mut conn,
+        user_id,
+        new_cover_image,
+        new_name,
+        new_home_region_id,
+      )?;
       return Ok(());
     }
     return Err(RouteError::PoolingErr);
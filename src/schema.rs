@@ -8,6 +8,35 @@ diesel::table! {
         created_at -> Timestamp,
         updated_at -> Timestamp,
         deleted_at -> Nullable<Timestamptz>,
+        parent_id -> Nullable<Int8>,
+        position -> Int4,
+    }
+}
+
+diesel::table! {
+    category_attribute (id) {
+        id -> Int8,
+        category_id -> Int8,
+        name -> Varchar,
+        attribute_type -> Varchar,
+        unit -> Nullable<Varchar>,
+        allowed_values -> Array<Text>,
+        required -> Bool,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        deleted_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    device_token (id) {
+        id -> Int8,
+        user_id -> Int8,
+        token -> Varchar,
+        platform -> Varchar,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        deleted_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -20,6 +49,11 @@ diesel::table! {
         created_at -> Timestamp,
         updated_at -> Timestamp,
         deleted_at -> Nullable<Timestamptz>,
+        boundary -> Nullable<Text>,
+        min_latitude -> Nullable<Float8>,
+        min_longitude -> Nullable<Float8>,
+        max_latitude -> Nullable<Float8>,
+        max_longitude -> Nullable<Float8>,
     }
 }
 
@@ -44,6 +78,33 @@ diesel::table! {
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
         deleted_at -> Nullable<Timestamptz>,
+        hidden_by_moderator -> Bool,
+        bumped_at -> Timestamptz,
+        expires_at -> Timestamptz,
+        expiry_notified_at -> Nullable<Timestamptz>,
+        latitude -> Nullable<Float8>,
+        longitude -> Nullable<Float8>,
+    }
+}
+
+diesel::table! {
+    item_attribute (id) {
+        id -> Int8,
+        item_id -> Int8,
+        attribute_id -> Int8,
+        value -> Varchar,
+        number_value -> Nullable<Float8>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    item_event (id) {
+        id -> Int8,
+        item_id -> Int8,
+        user_id -> Int8,
+        event_type -> Varchar,
+        created_at -> Timestamptz,
     }
 }
 
@@ -58,6 +119,21 @@ diesel::table! {
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
         deleted_at -> Nullable<Timestamptz>,
+        thumbnail_key -> Nullable<Varchar>,
+        medium_key -> Nullable<Varchar>,
+        processed_at -> Nullable<Timestamptz>,
+        position -> Int4,
+        pending_cover -> Bool,
+    }
+}
+
+diesel::table! {
+    item_price_history (id) {
+        id -> Int8,
+        item_id -> Int8,
+        old_price -> Int8,
+        new_price -> Int8,
+        created_at -> Timestamptz,
     }
 }
 
@@ -86,6 +162,34 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    moderation_action (id) {
+        id -> Int8,
+        report_id -> Nullable<Int8>,
+        moderator_id -> Int8,
+        action -> Varchar,
+        target_type -> Varchar,
+        target_id -> Int8,
+        note -> Varchar,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    notification (id) {
+        id -> Int8,
+        user_id -> Int8,
+        notification_type -> Varchar,
+        title -> Varchar,
+        body -> Varchar,
+        item_id -> Nullable<Int8>,
+        room_id -> Nullable<Int8>,
+        is_read -> Bool,
+        created_at -> Timestamptz,
+        read_at -> Nullable<Timestamptz>,
+    }
+}
+
 diesel::table! {
     purchase (id) {
         id -> Int8,
@@ -106,6 +210,22 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    report (id) {
+        id -> Int8,
+        reporter_id -> Int8,
+        target_type -> Varchar,
+        target_id -> Int8,
+        reason -> Varchar,
+        description -> Varchar,
+        report_status -> Varchar,
+        assignee_id -> Nullable<Int8>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        resolved_at -> Nullable<Timestamptz>,
+    }
+}
+
 diesel::table! {
     room (id) {
         id -> Int8,
@@ -124,6 +244,47 @@ diesel::table! {
         created_at -> Timestamptz,
         last_joined_at -> Timestamptz,
         deleted_at -> Nullable<Timestamptz>,
+        is_muted -> Bool,
+    }
+}
+
+diesel::table! {
+    saved_search (id) {
+        id -> Int8,
+        user_id -> Int8,
+        name -> Varchar,
+        category_id -> Nullable<Int8>,
+        karat_id -> Nullable<Int8>,
+        geofence_id -> Nullable<Int8>,
+        min_price -> Nullable<Int8>,
+        max_price -> Nullable<Int8>,
+        push_enabled -> Bool,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        deleted_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    translation (id) {
+        id -> Int8,
+        entity_type -> Varchar,
+        entity_id -> Int8,
+        locale -> Varchar,
+        name -> Varchar,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    user_block (id) {
+        id -> Int8,
+        blocker_id -> Int8,
+        blocked_id -> Int8,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        deleted_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -147,37 +308,68 @@ diesel::table! {
         cover_image -> Nullable<Varchar>,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        role -> Varchar,
+        suspended_at -> Nullable<Timestamptz>,
+        home_region_id -> Nullable<Int8>,
     }
 }
 
+diesel::joinable!(category_attribute -> category (category_id));
+diesel::joinable!(device_token -> users (user_id));
 diesel::joinable!(item -> category (category_id));
 diesel::joinable!(item -> geofence (geofence_id));
 diesel::joinable!(item -> karat (karat_id));
 diesel::joinable!(item -> users (owner_id));
+diesel::joinable!(item_attribute -> category_attribute (attribute_id));
+diesel::joinable!(item_attribute -> item (item_id));
+diesel::joinable!(item_event -> item (item_id));
+diesel::joinable!(item_event -> users (user_id));
 diesel::joinable!(item_image -> item (item_id));
 diesel::joinable!(item_image -> users (user_id));
+diesel::joinable!(item_price_history -> item (item_id));
 diesel::joinable!(message -> room (room_id));
 diesel::joinable!(message -> users (sender_id));
+diesel::joinable!(moderation_action -> report (report_id));
+diesel::joinable!(moderation_action -> users (moderator_id));
+diesel::joinable!(notification -> item (item_id));
+diesel::joinable!(notification -> room (room_id));
+diesel::joinable!(notification -> users (user_id));
 diesel::joinable!(purchase -> item (item_id));
 diesel::joinable!(refresh_token -> users (user_id));
 diesel::joinable!(room -> item (item_id));
 diesel::joinable!(room -> users (created_by));
 diesel::joinable!(room_member -> room (room_id));
 diesel::joinable!(room_member -> users (member_id));
+diesel::joinable!(saved_search -> category (category_id));
+diesel::joinable!(saved_search -> geofence (geofence_id));
+diesel::joinable!(saved_search -> karat (karat_id));
+diesel::joinable!(saved_search -> users (user_id));
 diesel::joinable!(user_favorite -> item (item_id));
 diesel::joinable!(user_favorite -> users (user_id));
+diesel::joinable!(users -> geofence (home_region_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
   category,
+  category_attribute,
+  device_token,
   geofence,
   item,
+  item_attribute,
+  item_event,
   item_image,
+  item_price_history,
   karat,
   message,
+  moderation_action,
+  notification,
   purchase,
   refresh_token,
+  report,
   room,
   room_member,
+  saved_search,
+  translation,
+  user_block,
   user_favorite,
   users,
 );
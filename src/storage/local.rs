@@ -0,0 +1,218 @@
+use actix_files::file_extension_to_mime;
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+
+use super::{
+  encode_key, ObjectMeta, ObjectStorage, PresignedUpload, StorageError, UploadConstraints,
+};
+use crate::helpers::{get_env, get_env_or};
+
+// the path the directory is served under, see `routes::storage`
+pub const LOCAL_STORAGE_PATH: &str = "/storage";
+
+// where the app serves the directory, LOCAL_STORAGE_URL when it is behind a proxy
+pub fn local_storage_url() -> String {
+  let default_url = format!(
+    "http://localhost:{}{}",
+    get_env("SERVER_PORT"),
+    LOCAL_STORAGE_PATH
+  );
+  get_env_or("LOCAL_STORAGE_URL", &default_url)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct UploadClaim {
+  key: String,
+  content_type: String,
+  max_size: u64,
+  exp: usize,
+}
+
+// Keeps the objects in a directory served by the app itself so the server runs
+// without S3. Uploads need the signed token of a presigned URL, downloads are public.
+pub struct LocalStorage {
+  root: PathBuf,
+  // where the directory is served, like http://localhost:8080/storage
+  base_url: String,
+  // presigned URLs do not outlive the process
+  secret: Vec<u8>,
+}
+
+impl LocalStorage {
+  pub fn new(root: PathBuf, base_url: String) -> LocalStorage {
+    let mut secret = vec![0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    LocalStorage {
+      root,
+      base_url: base_url.trim_end_matches('/').to_string(),
+      secret,
+    }
+  }
+
+  pub fn from_env() -> LocalStorage {
+    LocalStorage::new(
+      PathBuf::from(get_env_or("LOCAL_STORAGE_DIR", "storage")),
+      local_storage_url(),
+    )
+  }
+
+  pub fn root(&self) -> &Path {
+    &self.root
+  }
+
+  // whether the token of a presigned upload URL allows the upload, the same
+  // constraints S3 checks against its POST policies
+  pub fn verify_upload(&self, key: &str, token: &str, content_type: &str, size: u64) -> bool {
+    decode::<UploadClaim>(
+      token,
+      &DecodingKey::from_secret(&self.secret),
+      &Validation::new(Algorithm::HS256),
+    )
+    .is_ok_and(|data| {
+      data.claims.key == key
+        && data.claims.content_type == content_type
+        && size > 0
+        && size <= data.claims.max_size
+    })
+  }
+
+  pub fn write(&self, key: &str, content: &[u8]) -> Result<(), StorageError> {
+    let path = self.path(key)?;
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent).map_err(|e| StorageError(e.to_string()))?;
+    }
+    fs::write(path, content).map_err(|e| StorageError(e.to_string()))
+  }
+
+  // keys are relative paths below the root, `..` is refused
+  fn path(&self, key: &str) -> Result<PathBuf, StorageError> {
+    let relative = Path::new(key);
+    if key.is_empty()
+      || !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+      return Err(StorageError(format!("invalid key: {}", key)));
+    }
+    Ok(self.root.join(relative))
+  }
+
+  fn url(&self, key: &str) -> String {
+    format!("{}/{}", self.base_url, encode_key(key))
+  }
+}
+
+impl ObjectStorage for LocalStorage {
+  fn presign_upload(
+    &self,
+    key: &str,
+    constraints: &UploadConstraints,
+  ) -> Result<PresignedUpload, StorageError> {
+    self.path(key)?;
+    let claim = UploadClaim {
+      key: key.to_string(),
+      content_type: constraints.content_type.clone(),
+      max_size: constraints.max_size,
+      exp: (Utc::now() + Duration::seconds(constraints.expiry_secs as i64)).timestamp() as usize,
+    };
+    let token = encode(
+      &Header::new(Algorithm::HS256),
+      &claim,
+      &EncodingKey::from_secret(&self.secret),
+    )
+    .map_err(|e| StorageError(e.to_string()))?;
+    Ok(PresignedUpload {
+      method: "PUT".to_string(),
+      url: format!("{}?token={}", self.url(key), token),
+      fields: HashMap::new(),
+    })
+  }
+
+  fn presign_get(&self, key: &str, _expiry_secs: u32) -> Result<String, StorageError> {
+    self.path(key)?;
+    Ok(self.url(key))
+  }
+
+  fn get(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+    fs::read(self.path(key)?).map_err(|e| StorageError(e.to_string()))
+  }
+
+  // the content type is not kept, it is guessed from the extension
+  fn put(&self, key: &str, content: &[u8], _content_type: &str) -> Result<(), StorageError> {
+    self.write(key, content)
+  }
+
+  fn head(&self, key: &str) -> Result<Option<ObjectMeta>, StorageError> {
+    match fs::metadata(self.path(key)?) {
+      Ok(metadata) if metadata.is_file() => Ok(Some(to_object_meta(key.to_string(), &metadata))),
+      Ok(_) => Ok(None),
+      Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+      Err(e) => Err(StorageError(e.to_string())),
+    }
+  }
+
+  fn delete(&self, key: &str) -> Result<(), StorageError> {
+    match fs::remove_file(self.path(key)?) {
+      Ok(()) => Ok(()),
+      Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+      Err(e) => Err(StorageError(e.to_string())),
+    }
+  }
+
+  fn list(&self, prefix: &str) -> Result<Vec<ObjectMeta>, StorageError> {
+    let mut objects = vec![];
+    let mut directories = vec![self.root.clone()];
+    while let Some(directory) = directories.pop() {
+      let entries = match fs::read_dir(&directory) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => continue,
+        Err(e) => return Err(StorageError(e.to_string())),
+      };
+      for entry in entries {
+        let entry = entry.map_err(|e| StorageError(e.to_string()))?;
+        let metadata = entry.metadata().map_err(|e| StorageError(e.to_string()))?;
+        let path = entry.path();
+        if metadata.is_dir() {
+          directories.push(path);
+          continue;
+        }
+        let key = match path.strip_prefix(&self.root) {
+          Ok(relative) => relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+          Err(_) => continue,
+        };
+        if key.starts_with(prefix) {
+          objects.push(to_object_meta(key, &metadata));
+        }
+      }
+    }
+    objects.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(objects)
+  }
+}
+
+// the content type is guessed from the extension like actix-files does when serving
+fn to_object_meta(key: String, metadata: &fs::Metadata) -> ObjectMeta {
+  let content_type = Path::new(&key)
+    .extension()
+    .and_then(|extension| extension.to_str())
+    .map(|extension| file_extension_to_mime(extension).essence_str().to_string());
+  ObjectMeta {
+    size: metadata.len(),
+    content_type,
+    last_modified: metadata
+      .modified()
+      .ok()
+      .map(|modified| DateTime::<Utc>::from(modified).naive_utc()),
+    key,
+  }
+}
@@ -0,0 +1,89 @@
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+use std::fmt;
+
+pub mod cdn;
+pub mod local;
+pub mod s3;
+
+pub const S3_STORAGE_BACKEND: &str = "s3";
+pub const LOCAL_STORAGE_BACKEND: &str = "local";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectMeta {
+  pub key: String,
+  pub size: u64,
+  // not known for listed objects
+  pub content_type: Option<String>,
+  pub last_modified: Option<NaiveDateTime>,
+}
+
+// the limits a presigned upload holds the client to
+#[derive(Debug, Clone)]
+pub struct UploadConstraints {
+  pub content_type: String,
+  pub max_size: u64,
+  pub expiry_secs: u32,
+}
+
+// How the client uploads the object: a form POST with the fields before the
+// file for S3, a PUT with the content type header for the local storage.
+#[derive(Debug, Clone)]
+pub struct PresignedUpload {
+  pub method: String,
+  pub url: String,
+  pub fields: HashMap<String, String>,
+}
+
+#[derive(Debug)]
+pub struct StorageError(pub String);
+
+impl fmt::Display for StorageError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Storage error: {}", self.0)
+  }
+}
+
+// Stores the uploaded images. Clients upload and download objects themselves
+// through presigned URLs, the server only inspects and removes them. Every call
+// may block on the network or the disk, so it is made from `web::block` or a job.
+pub trait ObjectStorage: Send + Sync {
+  // the object is refused unless it keeps to the constraints
+  fn presign_upload(
+    &self,
+    key: &str,
+    constraints: &UploadConstraints,
+  ) -> Result<PresignedUpload, StorageError>;
+  fn presign_get(&self, key: &str, expiry_secs: u32) -> Result<String, StorageError>;
+  fn get(&self, key: &str) -> Result<Vec<u8>, StorageError>;
+  fn put(&self, key: &str, content: &[u8], content_type: &str) -> Result<(), StorageError>;
+  // None when there is no such object
+  fn head(&self, key: &str) -> Result<Option<ObjectMeta>, StorageError>;
+  // deleting a missing object is not an error
+  fn delete(&self, key: &str) -> Result<(), StorageError>;
+  // every object whose key starts with the prefix
+  fn list(&self, prefix: &str) -> Result<Vec<ObjectMeta>, StorageError>;
+}
+
+// Objects that fail to be removed are only logged, the upload cleanup job removes
+// what is left behind.
+pub fn delete_objects(storage: &dyn ObjectStorage, keys: &[String]) {
+  for key in keys {
+    if let Err(e) = storage.delete(key) {
+      log::warn!("failed to remove {}: {}", key, e);
+    }
+  }
+}
+
+// percent encodes everything but unreserved characters and the path separator
+pub fn encode_key(key: &str) -> String {
+  key
+    .bytes()
+    .map(|byte| match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+        (byte as char).to_string()
+      }
+      _ => format!("%{:02X}", byte),
+    })
+    .collect()
+}
@@ -0,0 +1,219 @@
+use actix_web::rt::Runtime;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use s3::bucket::Bucket;
+use s3::creds::Credentials;
+use s3::error::S3Error;
+use serde_json::json;
+use std::collections::HashMap;
+use std::future::Future;
+
+use super::{ObjectMeta, ObjectStorage, PresignedUpload, StorageError, UploadConstraints};
+use crate::helpers;
+
+const SIGNING_ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+pub struct S3Storage {
+  bucket: Bucket,
+}
+
+impl S3Storage {
+  pub fn new(bucket: Bucket) -> S3Storage {
+    S3Storage { bucket }
+  }
+
+  pub fn from_env() -> S3Storage {
+    let s3_bucket_name = helpers::get_env("S3_BUCKET_NAME");
+    let s3_bucket_region: s3::Region = helpers::get_env("S3_BUCKET_REGION").parse().unwrap();
+    let aws_bucket_access_key = helpers::get_env("AWS_BUCKET_ACCESS_KEY");
+    let aws_bucket_secret_key = helpers::get_env("AWS_BUCKET_SECRET_KEY");
+    let credentials = Credentials::new(
+      Some(aws_bucket_access_key.as_str()),
+      Some(aws_bucket_secret_key.as_str()),
+      None,
+      None,
+      None,
+    )
+    .unwrap();
+    S3Storage::new(Bucket::new(&s3_bucket_name, s3_bucket_region, credentials).unwrap())
+  }
+}
+
+impl ObjectStorage for S3Storage {
+  // a POST policy signed with signature version 4, S3 checks the upload against it
+  fn presign_upload(
+    &self,
+    key: &str,
+    constraints: &UploadConstraints,
+  ) -> Result<PresignedUpload, StorageError> {
+    let credentials = self
+      .bucket
+      .credentials()
+      .read()
+      .map_err(|e| StorageError(e.to_string()))?
+      .clone();
+    let (access_key, secret_key) = match (credentials.access_key, credentials.secret_key) {
+      (Some(access_key), Some(secret_key)) => (access_key, secret_key),
+      _ => return Err(StorageError("missing bucket credentials".to_string())),
+    };
+    let now = Utc::now();
+    let date = now.format("%Y%m%d").to_string();
+    let region = self.bucket.region().to_string();
+    let mut fields = HashMap::from([
+      ("key".to_string(), key.to_string()),
+      ("Content-Type".to_string(), constraints.content_type.clone()),
+      ("x-amz-algorithm".to_string(), SIGNING_ALGORITHM.to_string()),
+      (
+        "x-amz-credential".to_string(),
+        format!("{}/{}/{}/s3/aws4_request", access_key, date, region),
+      ),
+      (
+        "x-amz-date".to_string(),
+        now.format("%Y%m%dT%H%M%SZ").to_string(),
+      ),
+    ]);
+    if let Some(token) = credentials.security_token.or(credentials.session_token) {
+      fields.insert("x-amz-security-token".to_string(), token);
+    }
+    let mut conditions = vec![
+      json!({ "bucket": self.bucket.name() }),
+      json!(["content-length-range", 1, constraints.max_size]),
+    ];
+    conditions.extend(fields.iter().map(|(name, value)| json!({ name: value })));
+    let expiration = now + Duration::seconds(constraints.expiry_secs as i64);
+    let policy = STANDARD.encode(
+      json!({
+        "expiration": expiration.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        "conditions": conditions,
+      })
+      .to_string(),
+    );
+
+    let mut signing_key = format!("AWS4{}", secret_key).into_bytes();
+    for scope in [date.as_str(), region.as_str(), "s3", "aws4_request"] {
+      signing_key = hmac_sha256(&signing_key, scope.as_bytes())?;
+    }
+    let signature = hmac_sha256(&signing_key, policy.as_bytes())?
+      .iter()
+      .map(|byte| format!("{:02x}", byte))
+      .collect();
+    fields.insert("policy".to_string(), policy);
+    fields.insert("x-amz-signature".to_string(), signature);
+    Ok(PresignedUpload {
+      method: "POST".to_string(),
+      url: self.bucket.url(),
+      fields,
+    })
+  }
+
+  fn presign_get(&self, key: &str, expiry_secs: u32) -> Result<String, StorageError> {
+    self
+      .bucket
+      .presign_get(key, expiry_secs, None)
+      .map_err(to_storage_error)
+  }
+
+  fn get(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+    let bucket = self.bucket.clone();
+    let key = key.to_string();
+    let response =
+      block_on(move || async move { bucket.get_object(key).await })?.map_err(to_storage_error)?;
+    Ok(response.to_vec())
+  }
+
+  fn put(&self, key: &str, content: &[u8], content_type: &str) -> Result<(), StorageError> {
+    let bucket = self.bucket.clone();
+    let key = key.to_string();
+    let content = content.to_vec();
+    let content_type = content_type.to_string();
+    block_on(move || async move {
+      bucket
+        .put_object_with_content_type(key, &content, &content_type)
+        .await
+    })?
+    .map_err(to_storage_error)?;
+    Ok(())
+  }
+
+  fn head(&self, key: &str) -> Result<Option<ObjectMeta>, StorageError> {
+    let bucket = self.bucket.clone();
+    let key = key.to_string();
+    block_on(move || async move {
+      match bucket.head_object(&key).await {
+        Ok((head, status)) if (200..300).contains(&status) => Ok(Some(ObjectMeta {
+          key,
+          size: head.content_length.unwrap_or_default().max(0) as u64,
+          content_type: head.content_type,
+          last_modified: head
+            .last_modified
+            .and_then(|value| DateTime::parse_from_rfc2822(&value).ok())
+            .map(|value| value.naive_utc()),
+        })),
+        Ok(_) | Err(S3Error::Http(404, _)) => Ok(None),
+        Err(e) => Err(to_storage_error(e)),
+      }
+    })?
+  }
+
+  fn delete(&self, key: &str) -> Result<(), StorageError> {
+    let bucket = self.bucket.clone();
+    let key = key.to_string();
+    block_on(move || async move {
+      match bucket.delete_object(&key).await {
+        Ok(_) | Err(S3Error::Http(404, _)) => Ok(()),
+        Err(e) => Err(to_storage_error(e)),
+      }
+    })?
+  }
+
+  fn list(&self, prefix: &str) -> Result<Vec<ObjectMeta>, StorageError> {
+    let bucket = self.bucket.clone();
+    let prefix = prefix.to_string();
+    let pages = block_on(move || async move { bucket.list(prefix, None).await })?
+      .map_err(to_storage_error)?;
+    Ok(
+      pages
+        .into_iter()
+        .flat_map(|page| page.contents)
+        .map(|object| ObjectMeta {
+          key: object.key,
+          size: object.size,
+          content_type: None,
+          last_modified: DateTime::parse_from_rfc3339(&object.last_modified)
+            .ok()
+            .map(|value| value.naive_utc()),
+        })
+        .collect(),
+    )
+  }
+}
+
+// rust-s3 only has an async client. Each call gets its own thread and runtime so
+// the storage can be used from blocking code and from actors alike.
+fn block_on<T, F, Fut>(make_future: F) -> Result<T, StorageError>
+where
+  T: Send + 'static,
+  F: FnOnce() -> Fut + Send + 'static,
+  Fut: Future<Output = T>,
+{
+  std::thread::spawn(move || Runtime::new().map(|runtime| runtime.block_on(make_future())))
+    .join()
+    .map_err(|_| StorageError("storage request panicked".to_string()))?
+    .map_err(|e| StorageError(e.to_string()))
+}
+
+fn hmac_sha256(key: &[u8], content: &[u8]) -> Result<Vec<u8>, StorageError> {
+  let to_error = |e: openssl::error::ErrorStack| StorageError(e.to_string());
+  let key = PKey::hmac(key).map_err(to_error)?;
+  let mut signer = Signer::new(MessageDigest::sha256(), &key).map_err(to_error)?;
+  signer.update(content).map_err(to_error)?;
+  signer.sign_to_vec().map_err(to_error)
+}
+
+fn to_storage_error(err: S3Error) -> StorageError {
+  StorageError(err.to_string())
+}
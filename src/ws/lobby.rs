@@ -1,31 +1,67 @@
-use actix::prelude::{Actor, Context, Handler, Recipient};
+use actix::prelude::{Actor, AsyncContext, Context, Handler, Recipient};
+use diesel::result::Error as DieselError;
+use diesel::PgConnection;
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
 
 use super::messages::{
-  ClientActorMessage, ClientWsMessageType, Connect, Disconnect, ServerActorMessage,
-  ServerActorMessages, WsMessage,
+  ClientActorMessage, ClientWsMessageType, CloseSession, Connect, Disconnect, DisconnectUser,
+  NotifyUser, ServerActorMessage, ServerActorMessages, ServerNotifications, WsMessage,
 };
-use crate::helpers::new_naive_date;
+use crate::helpers::{get_env_or, new_naive_date};
+use crate::push::{PushMessage, PushSender};
 
+use crate::repository::device_token::{get_device_tokens_by_user_id, DeviceToken};
 use crate::repository::message::{create_new_message_with_date, get_messages_for_room_id};
+use crate::repository::notification::{
+  add_notification, get_unread_room_notification, InsertNotification, NEW_MESSAGE_NOTIFICATION,
+};
 
-use crate::repository::room_member::set_last_joined_at;
+use crate::repository::room_member::{get_room_members, set_last_joined_at, RoomMember};
+use crate::repository::user_block::{get_blocked_user_ids, get_blocker_ids};
+use crate::routes::notification::to_server_notification;
 use crate::routes::DbPool;
 
+const NOTIFICATION_PREVIEW_LENGTH: usize = 100;
+const DEFAULT_PUSH_BATCH_INTERVAL_SECONDS: &str = "10";
+
 pub type Socket = Recipient<WsMessage>;
 
+// messages of a room received by an offline member since the last push
+struct PendingPush {
+  sender_name: String,
+  last_message: String,
+  count: usize,
+}
+
 pub struct Lobby {
-  sessions: HashMap<i64, Socket>,    //self id to self
-  rooms: HashMap<i64, HashSet<i64>>, //room id  to list of session ids
+  sessions: HashMap<i64, Socket>,                 //self id to self
+  closers: HashMap<i64, Recipient<CloseSession>>, //self id to the close handle of its session
+  rooms: HashMap<i64, HashSet<i64>>,              //room id  to list of session ids
   pool: DbPool,
+  push_sender: Arc<dyn PushSender>,
+  pending_pushes: HashMap<(i64, i64), PendingPush>, //(user id, room id) to pending push
+  push_batch_interval: Duration,
 }
 
 impl Lobby {
-  pub fn new(pool: DbPool) -> Lobby {
+  pub fn new(pool: DbPool, push_sender: Arc<dyn PushSender>) -> Lobby {
     Lobby {
       sessions: HashMap::new(),
+      closers: HashMap::new(),
       rooms: HashMap::new(),
       pool,
+      push_sender,
+      pending_pushes: HashMap::new(),
+      push_batch_interval: Duration::from_secs(
+        get_env_or(
+          "PUSH_BATCH_INTERVAL_SECONDS",
+          DEFAULT_PUSH_BATCH_INTERVAL_SECONDS,
+        )
+        .parse()
+        .unwrap(),
+      ),
     }
   }
 }
@@ -49,15 +85,195 @@ impl Lobby {
     }
   }
 
+  // same as `send_message` to everyone in the room except the given sessions
+  fn send_message_except(&self, room: &i64, message: &str, skip_ids: &HashSet<i64>) {
+    if let Some(sessions) = self.rooms.get(room) {
+      for id in sessions {
+        if skip_ids.contains(id) {
+          continue;
+        }
+        if let Some(addr) = self.sessions.get(id) {
+          addr.do_send(WsMessage(message.to_owned()));
+        }
+      }
+    }
+  }
+
   fn send_unique_mes(&self, to: &i64, message: &str) {
     if let Some(addr) = self.sessions.get(to) {
       addr.do_send(WsMessage(message.to_owned()));
     }
   }
+
+  // members who are not in the room get a notification instead of the message,
+  // which is pushed to them if they are connected to another room
+  fn notify_absent_members(
+    &mut self,
+    conn: &mut PgConnection,
+    room: &i64,
+    sender_id: &i64,
+    sender_name: &str,
+    message: &str,
+    blocker_ids: &HashSet<i64>,
+  ) {
+    let members = match get_room_members(conn, room) {
+      Ok(members) => members,
+      Err(e) => {
+        println!("failed to get room members: {e}, {room}");
+        return;
+      }
+    };
+    let absent_members = self.absent_members(members, room, sender_id, blocker_ids);
+    self.queue_pushes(&absent_members, *room, sender_name, message);
+    for member in absent_members {
+      // one unread notification per room is enough
+      match get_unread_room_notification(conn, member.member_id, *room, NEW_MESSAGE_NOTIFICATION) {
+        Ok(None) => {}
+        Ok(Some(_)) => continue,
+        Err(e) => {
+          println!("failed to get unread notification: {e}, {room}");
+          continue;
+        }
+      }
+      let new_notification = InsertNotification {
+        user_id: member.member_id,
+        notification_type: NEW_MESSAGE_NOTIFICATION.to_string(),
+        title: format!("New message from {}", sender_name),
+        body: message.chars().take(NOTIFICATION_PREVIEW_LENGTH).collect(),
+        item_id: None,
+        room_id: Some(*room),
+      };
+      match add_notification(conn, &new_notification) {
+        Ok(notification) => self.send_unique_mes(
+          &member.member_id,
+          serde_json::to_string(&ServerNotifications {
+            notifications: vec![to_server_notification(&notification)],
+          })
+          .unwrap()
+          .as_str(),
+        ),
+        Err(e) => {
+          println!("failed to create notification: {e}, {room}");
+        }
+      }
+    }
+  }
+}
+
+impl Lobby {
+  // the members who are not in the room and did not block the sender
+  fn absent_members(
+    &self,
+    members: Vec<RoomMember>,
+    room: &i64,
+    sender_id: &i64,
+    blocker_ids: &HashSet<i64>,
+  ) -> Vec<RoomMember> {
+    let present = self.rooms.get(room);
+    members
+      .into_iter()
+      .filter(|member| {
+        member.member_id != *sender_id
+          && !present.is_some_and(|present| present.contains(&member.member_id))
+          && !blocker_ids.contains(&member.member_id)
+      })
+      .collect()
+  }
+
+  // offline members of unmuted rooms get a device push with the next batch
+  fn queue_pushes(
+    &mut self,
+    absent_members: &[RoomMember],
+    room: i64,
+    sender_name: &str,
+    message: &str,
+  ) {
+    for member in absent_members {
+      if !member.is_muted && !self.sessions.contains_key(&member.member_id) {
+        self.queue_push(member.member_id, room, sender_name, message);
+      }
+    }
+  }
+
+  fn queue_push(&mut self, user_id: i64, room: i64, sender_name: &str, message: &str) {
+    let pending = self
+      .pending_pushes
+      .entry((user_id, room))
+      .or_insert(PendingPush {
+        sender_name: String::new(),
+        last_message: String::new(),
+        count: 0,
+      });
+    pending.sender_name = sender_name.to_owned();
+    pending.last_message = message.chars().take(NOTIFICATION_PREVIEW_LENGTH).collect();
+    pending.count += 1;
+  }
+
+  fn flush_pushes(&mut self) {
+    if self.pending_pushes.is_empty() {
+      return;
+    }
+    let mut conn = match self.pool.get() {
+      Ok(conn) => conn,
+      Err(e) => {
+        println!("failed to get connection for pushes: {e}");
+        return;
+      }
+    };
+    self.send_pending_pushes(|user_id| get_device_tokens_by_user_id(&mut conn, user_id));
+  }
+
+  // one push per device of every (user, room) pair queued since the last batch
+  fn send_pending_pushes<F>(&mut self, mut get_device_tokens: F)
+  where
+    F: FnMut(i64) -> Result<Vec<DeviceToken>, DieselError>,
+  {
+    let pending_pushes = std::mem::take(&mut self.pending_pushes);
+    let mut messages: Vec<PushMessage> = Vec::new();
+    for ((user_id, room), pending) in pending_pushes {
+      // users who came back online already received the messages
+      if self.sessions.contains_key(&user_id) {
+        continue;
+      }
+      let tokens = match get_device_tokens(user_id) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+          println!("failed to get device tokens: {e}, {user_id}");
+          continue;
+        }
+      };
+      let title = if pending.count > 1 {
+        format!(
+          "{} new messages from {}",
+          pending.count, pending.sender_name
+        )
+      } else {
+        format!("New message from {}", pending.sender_name)
+      };
+      for device in tokens {
+        messages.push(PushMessage {
+          token: device.token,
+          title: title.clone(),
+          body: pending.last_message.clone(),
+          room_id: Some(room),
+        });
+      }
+    }
+    if messages.is_empty() {
+      return;
+    }
+    if let Err(e) = self.push_sender.send(messages) {
+      println!("failed to send pushes: {e}");
+    }
+  }
 }
 
 impl Actor for Lobby {
   type Context = Context<Self>;
+
+  fn started(&mut self, ctx: &mut Self::Context) {
+    ctx.run_interval(self.push_batch_interval, |act, _| act.flush_pushes());
+  }
 }
 
 impl Handler<Connect> for Lobby {
@@ -80,13 +296,21 @@ impl Handler<Connect> for Lobby {
 
     // store the address
     self.sessions.insert(msg.user_id, msg.addr);
+    self.closers.insert(msg.user_id, msg.close_addr);
     println!("{} joined", msg.user_id);
     // TODO: send to user old conversations
     // let pool_cloned = self.pool.clone();
     let mut conn = self.pool.get().unwrap();
     let mes = get_messages_for_room_id(&mut conn, &msg.lobby_id).unwrap();
+    let blocked_ids = get_blocked_user_ids(&mut conn, msg.user_id).unwrap_or_else(|e| {
+      println!("failed to get blocked users: {e}, {0}", msg.user_id);
+      vec![]
+    });
     let mut resp: Vec<ServerActorMessage> = Vec::new();
     for m in mes {
+      if blocked_ids.contains(&m.sender_id) {
+        continue;
+      }
       resp.push(ServerActorMessage {
         message: m.msg,
         sender_name: m.sender_name,
@@ -109,6 +333,7 @@ impl Handler<Disconnect> for Lobby {
   fn handle(&mut self, msg: Disconnect, _: &mut Context<Self>) -> Self::Result {
     // remove the session
     if self.sessions.remove(&msg.user_id).is_some() {
+      self.closers.remove(&msg.user_id);
       // send message to everyone in the room that the user_id just left
       self.send_message(
         &msg.room_id,
@@ -128,6 +353,33 @@ impl Handler<Disconnect> for Lobby {
   }
 }
 
+impl Handler<DisconnectUser> for Lobby {
+  type Result = ();
+
+  // the session leaves its room once the connection has stopped, see `Disconnect`
+  fn handle(&mut self, msg: DisconnectUser, _: &mut Context<Self>) -> Self::Result {
+    if let Some(closer) = self.closers.get(&msg.user_id) {
+      closer.do_send(CloseSession);
+    }
+  }
+}
+
+impl Handler<NotifyUser> for Lobby {
+  type Result = ();
+
+  fn handle(&mut self, msg: NotifyUser, _: &mut Context<Self>) -> Self::Result {
+    // users who are not connected will see it in their notification list
+    self.send_unique_mes(
+      &msg.user_id,
+      serde_json::to_string(&ServerNotifications {
+        notifications: vec![msg.notification],
+      })
+      .unwrap()
+      .as_str(),
+    );
+  }
+}
+
 impl Handler<ClientActorMessage> for Lobby {
   type Result = ();
 
@@ -158,18 +410,42 @@ impl Handler<ClientActorMessage> for Lobby {
             created_at: dt.to_string(),
           }],
         };
-        let res = self.send_message(&msg.room_id, &serde_json::to_string(&mes).unwrap(), None);
+        let mut conn = self.pool.get().ok();
+        // members who blocked the sender do not receive their messages
+        let blocker_ids: HashSet<i64> = match conn.as_mut() {
+          Some(conn) => get_blocker_ids(conn, msg.user_id)
+            .unwrap_or_else(|e| {
+              println!("failed to get blockers: {e}, {0}", msg.user_id);
+              vec![]
+            })
+            .into_iter()
+            .collect(),
+          None => HashSet::new(),
+        };
+        let res = self.send_message_except(
+          &msg.room_id,
+          &serde_json::to_string(&mes).unwrap(),
+          &blocker_ids,
+        );
 
-        if let Ok(mut conn) = self.pool.get() {
+        if let Some(conn) = conn.as_mut() {
           // TODO: make it async or use channel
           create_new_message_with_date(
-            &mut conn,
+            conn,
             &msg.room_id,
             &msg.user_id,
             &msg.user_name,
             &received_msg.message,
             dt,
           );
+          self.notify_absent_members(
+            conn,
+            &msg.room_id,
+            &msg.user_id,
+            &msg.user_name,
+            &received_msg.message,
+            &blocker_ids,
+          );
         }
         return res;
       }
@@ -177,3 +453,255 @@ impl Handler<ClientActorMessage> for Lobby {
     // TODO: Log err received invalid mes
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::push::RecordingPushSender;
+  use diesel::r2d2::{ConnectionManager, Pool};
+
+  struct TestSession;
+
+  impl Actor for TestSession {
+    type Context = Context<Self>;
+  }
+
+  impl Handler<WsMessage> for TestSession {
+    type Result = ();
+
+    fn handle(&mut self, _: WsMessage, _: &mut Context<Self>) -> Self::Result {}
+  }
+
+  // the pool never connects, the tests hand the device tokens over themselves
+  fn test_lobby(push_sender: &RecordingPushSender) -> Lobby {
+    let manager = ConnectionManager::<PgConnection>::new("postgres://localhost/ketalk_test");
+    let pool = Pool::builder().min_idle(Some(0)).build_unchecked(manager);
+    Lobby::new(pool, Arc::new(push_sender.clone()))
+  }
+
+  fn connect(lobby: &mut Lobby, user_id: i64, room: Option<i64>) {
+    lobby
+      .sessions
+      .insert(user_id, TestSession.start().recipient());
+    if let Some(room) = room {
+      lobby.rooms.entry(room).or_default().insert(user_id);
+    }
+  }
+
+  fn member(room_id: i64, member_id: i64, is_muted: bool) -> RoomMember {
+    RoomMember {
+      id: member_id,
+      room_id,
+      member_id,
+      created_at: new_naive_date(),
+      last_joined_at: new_naive_date(),
+      deleted_at: None,
+      is_muted,
+    }
+  }
+
+  fn device_tokens(user_id: i64) -> Result<Vec<DeviceToken>, DieselError> {
+    Ok(vec![DeviceToken {
+      id: user_id,
+      user_id,
+      token: format!("device-{}", user_id),
+      platform: "ios".to_string(),
+      created_at: new_naive_date(),
+      updated_at: new_naive_date(),
+      deleted_at: None,
+    }])
+  }
+
+  // what the lobby does with a message of `sender_id` in `room`
+  fn send(lobby: &mut Lobby, room: i64, sender_id: i64, members: Vec<RoomMember>, message: &str) {
+    let blocker_ids = HashSet::new();
+    send_with_blockers(lobby, room, sender_id, members, message, &blocker_ids);
+  }
+
+  fn send_with_blockers(
+    lobby: &mut Lobby,
+    room: i64,
+    sender_id: i64,
+    members: Vec<RoomMember>,
+    message: &str,
+    blocker_ids: &HashSet<i64>,
+  ) -> Vec<i64> {
+    let absent_members = lobby.absent_members(members, &room, &sender_id, blocker_ids);
+    lobby.queue_pushes(&absent_members, room, "Ann", message);
+    absent_members
+      .iter()
+      .map(|member| member.member_id)
+      .collect()
+  }
+
+  fn pushed_tokens(push_sender: &RecordingPushSender) -> Vec<String> {
+    let mut tokens: Vec<String> = push_sender
+      .sent()
+      .into_iter()
+      .map(|message| message.token)
+      .collect();
+    tokens.sort();
+    tokens
+  }
+
+  #[test]
+  fn reads_the_batch_interval_from_env() {
+    std::env::set_var("PUSH_BATCH_INTERVAL_SECONDS", "3");
+    let lobby = test_lobby(&RecordingPushSender::new());
+    assert_eq!(lobby.push_batch_interval, Duration::from_secs(3));
+  }
+
+  #[test]
+  fn batches_the_messages_of_an_interval() {
+    let push_sender = RecordingPushSender::new();
+    let mut lobby = test_lobby(&push_sender);
+    for message in ["hi", "is it available?", "ping"] {
+      send(
+        &mut lobby,
+        1,
+        1,
+        vec![member(1, 1, false), member(1, 2, false)],
+        message,
+      );
+    }
+    lobby.send_pending_pushes(device_tokens);
+    assert_eq!(
+      push_sender.sent(),
+      vec![PushMessage {
+        token: "device-2".to_string(),
+        title: "3 new messages from Ann".to_string(),
+        body: "ping".to_string(),
+        room_id: Some(1),
+      }]
+    );
+
+    // the next interval starts a new batch
+    send(&mut lobby, 1, 1, vec![member(1, 2, false)], "still there?");
+    lobby.send_pending_pushes(device_tokens);
+    let sent = push_sender.sent();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[1].title, "New message from Ann");
+    assert_eq!(sent[1].body, "still there?");
+
+    // nothing is sent without new messages
+    lobby.send_pending_pushes(device_tokens);
+    assert_eq!(push_sender.sent().len(), 2);
+  }
+
+  #[test]
+  fn batches_per_room() {
+    let push_sender = RecordingPushSender::new();
+    let mut lobby = test_lobby(&push_sender);
+    send(&mut lobby, 1, 1, vec![member(1, 2, false)], "first room");
+    send(&mut lobby, 5, 1, vec![member(5, 2, false)], "second room");
+    lobby.send_pending_pushes(device_tokens);
+    let mut room_ids: Vec<Option<i64>> = push_sender
+      .sent()
+      .iter()
+      .map(|message| message.room_id)
+      .collect();
+    room_ids.sort();
+    assert_eq!(room_ids, vec![Some(1), Some(5)]);
+  }
+
+  #[test]
+  fn truncates_the_preview() {
+    let push_sender = RecordingPushSender::new();
+    let mut lobby = test_lobby(&push_sender);
+    let message = "a".repeat(NOTIFICATION_PREVIEW_LENGTH + 20);
+    send(&mut lobby, 1, 1, vec![member(1, 2, false)], &message);
+    lobby.send_pending_pushes(device_tokens);
+    assert_eq!(
+      push_sender.sent()[0].body.len(),
+      NOTIFICATION_PREVIEW_LENGTH
+    );
+  }
+
+  #[test]
+  fn skips_muted_rooms() {
+    let push_sender = RecordingPushSender::new();
+    let mut lobby = test_lobby(&push_sender);
+    let absent = send_with_blockers(
+      &mut lobby,
+      1,
+      1,
+      vec![member(1, 1, false), member(1, 2, true), member(1, 3, false)],
+      "hi",
+      &HashSet::new(),
+    );
+    // muted members still get the notification
+    assert_eq!(absent, vec![2, 3]);
+    lobby.send_pending_pushes(device_tokens);
+    assert_eq!(pushed_tokens(&push_sender), vec!["device-3"]);
+  }
+
+  #[actix_web::test]
+  async fn skips_online_members() {
+    let push_sender = RecordingPushSender::new();
+    let mut lobby = test_lobby(&push_sender);
+    // 2 is in the room, 3 is connected to another room
+    connect(&mut lobby, 2, Some(1));
+    connect(&mut lobby, 3, Some(7));
+    let absent = send_with_blockers(
+      &mut lobby,
+      1,
+      1,
+      vec![
+        member(1, 2, false),
+        member(1, 3, false),
+        member(1, 4, false),
+        member(1, 5, false),
+      ],
+      "hi",
+      &HashSet::new(),
+    );
+    assert_eq!(absent, vec![3, 4, 5]);
+    // 5 came back online before the batch was sent
+    connect(&mut lobby, 5, Some(7));
+    lobby.send_pending_pushes(device_tokens);
+    assert_eq!(pushed_tokens(&push_sender), vec!["device-4"]);
+  }
+
+  #[test]
+  fn excludes_blockers() {
+    let push_sender = RecordingPushSender::new();
+    let mut lobby = test_lobby(&push_sender);
+    let blocker_ids = HashSet::from([3]);
+    let absent = send_with_blockers(
+      &mut lobby,
+      1,
+      1,
+      vec![
+        member(1, 1, false),
+        member(1, 2, false),
+        member(1, 3, false),
+      ],
+      "hi",
+      &blocker_ids,
+    );
+    assert_eq!(absent, vec![2]);
+    lobby.send_pending_pushes(device_tokens);
+    assert_eq!(pushed_tokens(&push_sender), vec!["device-2"]);
+  }
+
+  #[test]
+  fn skips_users_whose_tokens_fail_to_load() {
+    let push_sender = RecordingPushSender::new();
+    let mut lobby = test_lobby(&push_sender);
+    send(
+      &mut lobby,
+      1,
+      1,
+      vec![member(1, 2, false), member(1, 3, false)],
+      "hi",
+    );
+    lobby.send_pending_pushes(|user_id| {
+      if user_id == 2 {
+        Err(DieselError::NotFound)
+      } else {
+        device_tokens(user_id)
+      }
+    });
+    assert_eq!(pushed_tokens(&push_sender), vec!["device-3"]);
+  }
+}
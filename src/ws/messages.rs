@@ -10,6 +10,7 @@ pub struct WsMessage(pub String);
 #[rtype(result = "()")]
 pub struct Connect {
   pub addr: Recipient<WsMessage>,
+  pub close_addr: Recipient<CloseSession>,
   pub lobby_id: i64,
   pub user_id: i64,
 }
@@ -22,6 +23,26 @@ pub struct Disconnect {
   pub user_id: i64,
 }
 
+//the lobby sends this to a WsConn to close the connection
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct CloseSession;
+
+//routes send this to the lobby to close the session of a user, like a suspended one
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct DisconnectUser {
+  pub user_id: i64,
+}
+
+//routes send this to the lobby to push a notification to a connected user
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct NotifyUser {
+  pub user_id: i64,
+  pub notification: ServerNotification,
+}
+
 //client sends this to the lobby for the lobby to echo out.
 #[derive(Message)]
 #[rtype(result = "()")]
@@ -64,6 +85,24 @@ pub struct ServerActorMessage {
   pub created_at: String,
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct ServerNotifications {
+  pub notifications: Vec<ServerNotification>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct ServerNotification {
+  pub id: i64,
+  pub notification_type: String,
+  pub title: String,
+  pub body: String,
+  pub item_id: Option<i64>,
+  pub room_id: Option<i64>,
+  pub created_at: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all(serialize = "camelCase"))]
 pub struct UserRooms {
@@ -8,7 +8,8 @@ use std::time::{Duration, Instant};
 
 use super::lobby::Lobby;
 use super::messages::{
-  ClientActorMessage, ClientWsMessage, ClientWsMessageType, Connect, Disconnect, WsMessage,
+  ClientActorMessage, ClientWsMessage, ClientWsMessageType, CloseSession, Connect, Disconnect,
+  WsMessage,
 };
 
 // TODO: move to env
@@ -45,7 +46,8 @@ impl Actor for WsConn {
     self
       .lobby_addr
       .send(Connect {
-        addr: addr.recipient(),
+        addr: addr.clone().recipient(),
+        close_addr: addr.recipient(),
         lobby_id: self.room,
         user_id: self.user_id,
       })
@@ -76,6 +78,14 @@ impl Handler<WsMessage> for WsConn {
   }
 }
 
+impl Handler<CloseSession> for WsConn {
+  type Result = ();
+  fn handle(&mut self, _: CloseSession, ctx: &mut Self::Context) -> Self::Result {
+    ctx.close(Some(ws::CloseCode::Policy.into()));
+    ctx.stop();
+  }
+}
+
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsConn {
   fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
     match msg {